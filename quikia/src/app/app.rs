@@ -2,47 +2,86 @@ use std::ops::Deref;
 use std::sync::{Arc, Mutex};
 
 use winit::event_loop::EventLoopProxy;
-use winit::window::Window;
+use winit::window::{CursorIcon, Window};
 
 use crate::animation::Animation;
-use crate::app::Theme;
+use crate::app::{Theme, UiHandle, UiSender, WindowSizeClass};
+use crate::property::{Gettable, SharedProperty};
 use crate::ui::{LayoutDirection, PointerType};
 
 #[derive(Clone, Debug)]
 pub(crate) enum UserEvent {
     Empty,
-    TimerExpired(usize,String)
+    TimerExpired(usize,String),
+    /// Tasks were posted through a [`UiSender`].
+    RunUiTasks,
 }
 
 pub struct App {
     window: Option<Window>,
+    /// Logical width, height and scale factor of the content when the app runs without a window.
+    headless_size: Option<(f32, f32, f32)>,
     theme: Theme,
     pub(crate) animations: Arc<Mutex<Vec<Animation>>>,
     pub(crate) need_redraw: bool,
     pub(crate) need_layout: bool,
+    /// Increased by every full layout request, measure and layout results of older generations are discarded.
+    layout_generation: usize,
     pub(crate) need_rebuild: bool,
-    event_loop_proxy: EventLoopProxy<UserEvent>,
+    event_loop_proxy: Option<EventLoopProxy<UserEvent>>,
+    ui_sender: UiSender,
     layout_direction: LayoutDirection,
+    window_size_class: SharedProperty<WindowSizeClass>,
     pub(crate) focused_item_id: Option<usize>,
     pub(crate) request_focus_id: Option<usize>,
 
     pub(crate) pointer_catch: Option<(PointerType, usize)>,
+    cursor_icon: CursorIcon,
+    focus_ring_enabled: bool,
 }
 
 impl App {
     pub(crate) fn new(event_loop_proxy: EventLoopProxy<UserEvent>, theme: Theme) -> Self {
         Self {
             window: None,
+            headless_size: None,
             theme,
             animations: Arc::new(Mutex::new(Vec::new())),
             need_redraw: false,
             need_layout: false,
+            layout_generation: 0,
             need_rebuild: false,
-            event_loop_proxy,
+            ui_sender: UiSender::new(Some(event_loop_proxy.clone())),
+            event_loop_proxy: Some(event_loop_proxy),
             layout_direction: LayoutDirection::LeftToRight,
+            window_size_class: WindowSizeClass::Compact.into(),
             focused_item_id: None,
             request_focus_id: None,
             pointer_catch: None,
+            cursor_icon: CursorIcon::Default,
+            focus_ring_enabled: false,
+        }
+    }
+
+    pub(crate) fn new_headless(theme: Theme, width: f32, height: f32, scale_factor: f32) -> Self {
+        Self {
+            window: None,
+            headless_size: Some((width, height, scale_factor)),
+            theme,
+            animations: Arc::new(Mutex::new(Vec::new())),
+            need_redraw: false,
+            need_layout: false,
+            layout_generation: 0,
+            need_rebuild: false,
+            event_loop_proxy: None,
+            ui_sender: UiSender::new(None),
+            layout_direction: LayoutDirection::LeftToRight,
+            window_size_class: WindowSizeClass::from_width(width).into(),
+            focused_item_id: None,
+            request_focus_id: None,
+            pointer_catch: None,
+            cursor_icon: CursorIcon::Default,
+            focus_ring_enabled: false,
         }
     }
 
@@ -58,34 +97,61 @@ impl App {
         self.window = Some(window);
     }
 
+    pub(crate) fn set_headless_size(&mut self, width: f32, height: f32, scale_factor: f32) {
+        self.headless_size = Some((width, height, scale_factor));
+    }
+
+    pub(crate) fn event_loop_proxy(&self) -> Option<EventLoopProxy<UserEvent>> {
+        self.event_loop_proxy.clone()
+    }
+
     pub(crate) fn send_event(&self, event: UserEvent) {
-        self.event_loop_proxy.send_event(event).unwrap();
+        if let Some(event_loop_proxy) = &self.event_loop_proxy {
+            event_loop_proxy.send_event(event).unwrap();
+        }
     }
 
     pub fn request_redraw(&mut self) {
         if !self.need_layout {
-            self.window.as_mut().unwrap().request_redraw();
+            if let Some(window) = self.window.as_mut() {
+                window.request_redraw();
+            }
         }
         self.need_redraw = true;
     }
 
+    /// Measure and layout every item again with the next frame.
     pub fn request_layout(&mut self) {
+        self.layout_generation = self.layout_generation.wrapping_add(1);
+        self.request_incremental_layout();
+    }
+
+    /// Run a layout pass with the next frame that only visits invalidated items, see [`LayoutNode`](crate::ui::LayoutNode).
+    pub(crate) fn request_incremental_layout(&mut self) {
         self.need_layout = true;
         if !self.need_redraw {
             self.request_redraw();
         }
     }
 
+    pub(crate) fn layout_generation(&self) -> usize {
+        self.layout_generation
+    }
+
     pub fn request_rebuild(&mut self) {
         self.need_rebuild = true;
     }
 
     pub fn activate_ime(&mut self){
-        self.window().set_ime_allowed(true);
+        if let Some(window) = self.try_window() {
+            window.set_ime_allowed(true);
+        }
     }
 
     pub fn deactivate_ime(&mut self){
-        self.window().set_ime_allowed(false);
+        if let Some(window) = self.try_window() {
+            window.set_ime_allowed(false);
+        }
     }
 
     pub(crate) fn redraw_done(&mut self) {
@@ -102,30 +168,92 @@ impl App {
 
     pub fn request_focus(&mut self, id: usize) {
         self.request_focus_id = Some(id);
+        self.request_redraw();
+    }
+
+    pub fn focused_item_id(&self) -> Option<usize> {
+        self.focused_item_id
+    }
+
+    pub fn focus_ring_enabled(&self) -> bool {
+        self.focus_ring_enabled
+    }
+
+    pub fn set_focus_ring_enabled(&mut self, focus_ring_enabled: bool) {
+        self.focus_ring_enabled = focus_ring_enabled;
+        self.request_redraw();
     }
 
     pub fn catch_pointer(&mut self, pointer_type: PointerType, id:usize) {
         self.pointer_catch = Some((pointer_type, id));
     }
 
+    pub fn release_pointer(&mut self, pointer_type: PointerType) {
+        if let Some((catch_pointer_type, _)) = self.pointer_catch {
+            if catch_pointer_type == pointer_type {
+                self.pointer_catch = None;
+            }
+        }
+    }
+
+    pub fn pointer_catch(&self) -> Option<(PointerType, usize)> {
+        self.pointer_catch
+    }
+
+    pub fn cursor_icon(&self) -> CursorIcon {
+        self.cursor_icon
+    }
+
+    pub fn set_cursor_icon(&mut self, cursor_icon: CursorIcon) {
+        if self.cursor_icon == cursor_icon {
+            return;
+        }
+        self.cursor_icon = cursor_icon;
+        if let Some(window) = self.try_window() {
+            window.set_cursor_icon(cursor_icon);
+        }
+    }
+
+    /// Panics if the app has no window, e.g. when it was created for headless rendering.
     pub fn window(&self) -> &Window {
         self.window.as_ref().unwrap()
     }
 
+    /// Panics if the app has no window, e.g. when it was created for headless rendering.
     pub fn window_mut(&mut self) -> &mut Window {
         self.window.as_mut().unwrap()
     }
 
+    pub fn try_window(&self) -> Option<&Window> {
+        self.window.as_ref()
+    }
+
+    pub fn is_headless(&self) -> bool {
+        self.window.is_none() && self.headless_size.is_some()
+    }
+
     pub fn content_width(&self) -> f32 {
-        self.window().inner_size().width as f32 / self.window().scale_factor() as f32
+        match (&self.window, self.headless_size) {
+            (Some(window), _) => window.inner_size().width as f32 / window.scale_factor() as f32,
+            (None, Some((width, _, _))) => width,
+            (None, None) => 0.0,
+        }
     }
 
     pub fn content_height(&self) -> f32 {
-        self.window().inner_size().height as f32 / self.window().scale_factor() as f32
+        match (&self.window, self.headless_size) {
+            (Some(window), _) => window.inner_size().height as f32 / window.scale_factor() as f32,
+            (None, Some((_, height, _))) => height,
+            (None, None) => 0.0,
+        }
     }
 
     pub fn scale_factor(&self) -> f32 {
-        self.window().scale_factor() as f32
+        match (&self.window, self.headless_size) {
+            (Some(window), _) => window.scale_factor() as f32,
+            (None, Some((_, _, scale_factor))) => scale_factor,
+            (None, None) => 1.0,
+        }
     }
 
     pub fn layout_direction(&self) -> LayoutDirection {
@@ -148,6 +276,14 @@ impl SharedApp {
         }
     }
 
+    /// Create an app that is not bound to a window or an event loop.
+    /// The content has the given logical size and is rendered with the given scale factor.
+    pub fn new_headless(theme: Theme, width: f32, height: f32, scale_factor: f32) -> Self {
+        Self {
+            app: Arc::new(Mutex::new(App::new_headless(theme, width, height, scale_factor)))
+        }
+    }
+
     pub fn app(&self) -> Arc<Mutex<App>> {
         self.app.clone()
     }
@@ -181,14 +317,48 @@ impl SharedApp {
         self.app.lock().unwrap().send_event(event);
     }
 
+    /// A handle that posts tasks to the event loop thread from other threads.
+    pub fn ui_sender(&self) -> UiSender {
+        self.app.lock().unwrap().ui_sender.clone()
+    }
+
+    /// Keep `value` on the event loop thread and return a handle that other threads can use to update it.
+    /// Must be called on the event loop thread.
+    pub fn ui_handle<T: 'static>(&self, value: T) -> UiHandle<T> {
+        UiHandle::new(self.ui_sender(), value)
+    }
+
+    /// Run the tasks posted through the [`UiSender`] and request a layout pass if there were any.
+    pub(crate) fn run_ui_tasks(&self) {
+        if self.ui_sender().run_tasks() {
+            self.request_layout();
+        }
+    }
+
     pub fn request_focus(&self, id: usize) {
         self.app.lock().unwrap().request_focus(id);
     }
 
+    pub fn focused_item_id(&self) -> Option<usize> {
+        self.app.lock().unwrap().focused_item_id()
+    }
+
+    pub fn set_focus_ring_enabled(&self, focus_ring_enabled: bool) {
+        self.app.lock().unwrap().set_focus_ring_enabled(focus_ring_enabled);
+    }
+
     pub fn catch_pointer(&self, pointer_type: PointerType, id: usize) {
         self.app.lock().unwrap().catch_pointer(pointer_type, id);
     }
 
+    pub fn release_pointer(&self, pointer_type: PointerType) {
+        self.app.lock().unwrap().release_pointer(pointer_type);
+    }
+
+    pub fn pointer_catch(&self) -> Option<(PointerType, usize)> {
+        self.app.lock().unwrap().pointer_catch()
+    }
+
     pub fn request_redraw(&self) {
         self.app.lock().unwrap().request_redraw();
     }
@@ -197,6 +367,10 @@ impl SharedApp {
         self.app.lock().unwrap().request_layout();
     }
 
+    pub(crate) fn layout_generation(&self) -> usize {
+        self.app.lock().unwrap().layout_generation()
+    }
+
     pub fn request_rebuild(&self) {
         self.app.lock().unwrap().request_rebuild();
     }
@@ -233,6 +407,58 @@ impl SharedApp {
         self.app.lock().unwrap().scale_factor()
     }
 
+    pub fn is_headless(&self) -> bool {
+        self.app.lock().unwrap().is_headless()
+    }
+
+    pub fn cursor_icon(&self) -> CursorIcon {
+        self.app.lock().unwrap().cursor_icon()
+    }
+
+    pub fn set_cursor_icon(&self, cursor_icon: CursorIcon) {
+        self.app.lock().unwrap().set_cursor_icon(cursor_icon);
+    }
+
+    pub(crate) fn set_headless_size(&self, width: f32, height: f32, scale_factor: f32) {
+        self.app.lock().unwrap().set_headless_size(width, height, scale_factor);
+        self.update_window_size_class();
+    }
+
+    /// The window size class of the current content width. It changes when the window is resized across a breakpoint.
+    pub fn window_size_class(&self) -> SharedProperty<WindowSizeClass> {
+        self.app.lock().unwrap().window_size_class.clone()
+    }
+
+    /// A property holding `compact`, `medium` or `expanded`, following the window size class.
+    pub fn responsive<T: Clone + 'static>(&self, compact: T, medium: T, expanded: T) -> SharedProperty<T> {
+        self.responsive_with(move |window_size_class| match window_size_class {
+            WindowSizeClass::Compact => compact.clone(),
+            WindowSizeClass::Medium => medium.clone(),
+            WindowSizeClass::Expanded => expanded.clone(),
+        })
+    }
+
+    /// A property generated from the window size class, and generated again when it changes.
+    /// Unlike [`SharedApp::responsive`], the value does not have to be `Clone`, e.g. for an [`ItemCollectionProperty`](crate::property::ItemCollectionProperty).
+    pub fn responsive_with<T: 'static>(&self, generator: impl Fn(WindowSizeClass) -> T + 'static) -> SharedProperty<T> {
+        let window_size_class = self.window_size_class();
+        let property = {
+            let window_size_class = window_size_class.clone();
+            SharedProperty::from_generator(Box::new(move || generator(window_size_class.get())))
+        };
+        property.observe(&window_size_class);
+        property
+    }
+
+    /// Recompute the window size class from the content width, observers are only notified when it changes.
+    pub(crate) fn update_window_size_class(&self) {
+        let window_size_class = self.window_size_class();
+        let new_window_size_class = WindowSizeClass::from_width(self.content_width());
+        if window_size_class.get() != new_window_size_class {
+            window_size_class.set_value(new_window_size_class);
+        }
+    }
+
     pub fn layout_direction(&self) -> LayoutDirection {
         self.app.lock().unwrap().layout_direction()
     }
@@ -0,0 +1,42 @@
+use crate::app::SharedApp;
+use crate::ui::{Item, PointerAction};
+
+/// Deliver a pointer action to the item tree.
+///
+/// If an item caught this pointer with [`SharedApp::catch_pointer`], the action goes to that item only.
+/// Otherwise the tree is hit-tested with [`Item::dispatch_pointer_input`].
+/// The catch is released when the pointer goes up or is cancelled.
+pub(crate) fn dispatch_pointer_action(app: &SharedApp, ui: &mut Item, action: PointerAction) -> bool {
+    let pointer_catch = app.pointer_catch();
+    let caught_id = match (pointer_catch, action.pointer_type()) {
+        (Some((catch_pointer_type, id)), Some(pointer_type)) if catch_pointer_type == pointer_type => Some(id),
+        (Some((_, id)), None) => Some(id),
+        _ => None,
+    };
+
+    let handled = match caught_id {
+        Some(id) => {
+            match ui.find_item_mut(id, |item| item.pointer_input(action)) {
+                Some(handled) => handled,
+                None => {
+                    // The item that caught the pointer is gone.
+                    app.lock().unwrap().pointer_catch = None;
+                    ui.dispatch_pointer_input(action)
+                }
+            }
+        }
+        None => ui.dispatch_pointer_input(action),
+    };
+
+    match action {
+        PointerAction::Up { pointer_type, .. } => {
+            app.release_pointer(pointer_type);
+        }
+        PointerAction::Cancel => {
+            app.lock().unwrap().pointer_catch = None;
+        }
+        _ => {}
+    }
+
+    handled
+}
@@ -0,0 +1,123 @@
+use skia_safe::{Canvas, Paint, PaintStyle, Rect};
+use winit::event::{DeviceId, ElementState, KeyEvent};
+use winit::keyboard::{Key, NamedKey};
+
+use crate::app::{SharedApp, ThemeColor};
+use crate::property::Gettable;
+use crate::ui::{ImeAction, Item};
+
+/// Move the focus to the item requested with [`SharedApp::request_focus`].
+/// An id of `0` clears the focus.
+pub(crate) fn apply_focus_request(app: &SharedApp, ui: &mut Item) {
+    let (request_focus_id, focused_item_id) = {
+        let app = app.lock().unwrap();
+        (app.request_focus_id, app.focused_item_id)
+    };
+    let Some(request_focus_id) = request_focus_id else { return; };
+    let request_focus_id = if request_focus_id == 0 { None } else { Some(request_focus_id) };
+
+    if request_focus_id != focused_item_id {
+        if let Some(focused_item_id) = focused_item_id {
+            ui.find_item_mut(focused_item_id, |item| item.set_focus_state(false));
+        }
+        let ime_enabled = match request_focus_id {
+            Some(id) => ui.find_item_mut(id, |item| {
+                item.set_focus_state(true);
+                item.get_ime_enabled().get()
+            }),
+            None => None,
+        };
+        app.lock().unwrap().focused_item_id = if ime_enabled.is_some() { request_focus_id } else { None };
+
+        // Only editable items receive IME input.
+        if ime_enabled.unwrap_or(false) {
+            app.activate_ime();
+        } else {
+            app.deactivate_ime();
+        }
+        app.request_redraw();
+    }
+
+    // The transitions above may have requested focus again through the `focused` property observers.
+    app.lock().unwrap().request_focus_id = None;
+}
+
+/// Focus the next focusable item in tree order, or the previous one if `backward` is `true`.
+/// The traversal wraps around at both ends. Returns `true` if the focus moved to another item.
+pub(crate) fn move_focus(app: &SharedApp, ui: &mut Item, backward: bool) -> bool {
+    let mut ids = Vec::new();
+    ui.collect_focusable(&mut ids);
+    if ids.is_empty() {
+        return false;
+    }
+    let focused_item_id = app.lock().unwrap().focused_item_id;
+    let current = focused_item_id.and_then(|id| ids.iter().position(|focusable_id| *focusable_id == id));
+    let next = match (current, backward) {
+        (Some(index), false) => (index + 1) % ids.len(),
+        (Some(index), true) => (index + ids.len() - 1) % ids.len(),
+        (None, false) => 0,
+        (None, true) => ids.len() - 1,
+    };
+    app.request_focus(ids[next]);
+    apply_focus_request(app, ui);
+    app.lock().unwrap().focused_item_id != focused_item_id
+}
+
+/// Deliver a key event to the focused item and bubble it up to its ancestors.
+/// If none of them handles a pressed Tab or Shift+Tab, it moves the focus, and is only consumed if the focus moved.
+pub(crate) fn dispatch_keyboard_input(app: &SharedApp, ui: &mut Item, device_id: DeviceId, event: KeyEvent, is_synthetic: bool, shift: bool) -> bool {
+    let is_tab_press = event.logical_key == Key::Named(NamedKey::Tab) && event.state == ElementState::Pressed;
+
+    let focused_item_id = app.lock().unwrap().focused_item_id;
+    let handled = match focused_item_id {
+        Some(id) => ui.bubble_from(id, &mut |item| item.keyboard_input(device_id, event.clone(), is_synthetic)),
+        None => None,
+    };
+    let handled = match handled {
+        Some(handled) => handled,
+        None => ui.keyboard_input(device_id, event, is_synthetic),
+    };
+    apply_focus_request(app, ui);
+
+    if !handled && is_tab_press {
+        return move_focus(app, ui, shift);
+    }
+    handled
+}
+
+/// Deliver an IME action to the focused item and bubble it up to its ancestors.
+pub(crate) fn dispatch_ime_input(app: &SharedApp, ui: &mut Item, action: ImeAction) -> bool {
+    let focused_item_id = app.lock().unwrap().focused_item_id;
+    let handled = match focused_item_id {
+        Some(id) => ui.bubble_from(id, &mut |item| item.ime_input(action.clone())).unwrap_or(false),
+        None => false,
+    };
+    apply_focus_request(app, ui);
+    handled
+}
+
+/// Draw a ring in the theme's outline color around the focused item, if enabled with [`SharedApp::set_focus_ring_enabled`].
+pub(crate) fn draw_focus_ring(canvas: &Canvas, ui: &mut Item) {
+    let app = ui.get_app();
+    let (enabled, focused_item_id, color) = {
+        let app = app.lock().unwrap();
+        (app.focus_ring_enabled(), app.focused_item_id, app.theme().get_color(ThemeColor::Outline))
+    };
+    if !enabled {
+        return;
+    }
+    let Some(id) = focused_item_id else { return; };
+    let Some(layout_params) = ui.find_item_mut(id, |item| item.get_layout_params().clone()) else { return; };
+
+    let mut paint = Paint::default();
+    paint.set_anti_alias(true);
+    paint.set_style(PaintStyle::Stroke);
+    paint.set_stroke_width(FOCUS_RING_WIDTH);
+    paint.set_color(color);
+
+    let rect = Rect::from_xywh(layout_params.x(), layout_params.y(), layout_params.width, layout_params.height)
+        .with_outset((FOCUS_RING_WIDTH, FOCUS_RING_WIDTH));
+    canvas.draw_round_rect(rect, FOCUS_RING_WIDTH * 2.0, FOCUS_RING_WIDTH * 2.0, &paint);
+}
+
+const FOCUS_RING_WIDTH: f32 = 2.0;
@@ -0,0 +1,44 @@
+use std::cell::RefCell;
+
+use skia_safe::{Canvas, Color};
+
+use crate::app::draw_focus_ring;
+use crate::ui::{Item, MeasureMode};
+
+thread_local! {
+    /// The tasks to run when the layout pass on this thread is done, see [`after_layout`].
+    static AFTER_LAYOUT: RefCell<Vec<Box<dyn FnOnce()>>> = RefCell::new(Vec::new());
+}
+
+/// Run `task` when the current layout pass is done, e.g. to set a property whose observers may change the layout.
+pub(crate) fn after_layout(task: impl FnOnce() + 'static) {
+    AFTER_LAYOUT.with(|tasks| tasks.borrow_mut().push(Box::new(task)));
+}
+
+/// Measure and layout the root item so that it fills the given logical size.
+pub(crate) fn layout_frame(ui: &mut Item, width: f32, height: f32) {
+    ui.measure(MeasureMode::Specified(width), MeasureMode::Specified(height));
+    ui.layout(0.0, 0.0);
+
+    // A task may queue more tasks, they run in the same loop.
+    loop {
+        let tasks = AFTER_LAYOUT.with(|tasks| std::mem::take(&mut *tasks.borrow_mut()));
+        if tasks.is_empty() {
+            break;
+        }
+        tasks.into_iter().for_each(|task| task());
+    }
+}
+
+/// Draw the root item onto a canvas whose pixels are `scale_factor` times the logical size.
+pub(crate) fn draw_frame(canvas: &Canvas, ui: &mut Item, scale_factor: f32) {
+    canvas.clear(Color::TRANSPARENT);
+
+    canvas.save();
+    canvas.scale((scale_factor, scale_factor));
+
+    ui.draw(canvas);
+    draw_focus_ring(canvas, ui);
+
+    canvas.restore();
+}
@@ -0,0 +1,123 @@
+use skia_safe::{surfaces, Data, EncodedImageFormat, Image, Surface};
+use winit::window::CursorIcon;
+
+use crate::app::{apply_focus_request, dispatch_pointer_action, draw_frame, layout_frame, move_focus, SharedApp, Theme};
+use crate::ui::{Item, PointerAction};
+
+/// Renders an item tree into a CPU raster surface without a window or a GPU.
+///
+/// It runs the same measure, layout and draw passes as [`run_app`](crate::app::run_app),
+/// so it can be used on machines without a display, e.g. for tests.
+pub struct Headless {
+    app: SharedApp,
+    surface: Surface,
+    ui: Item,
+}
+
+impl Headless {
+    /// `width` and `height` are logical sizes. The surface has `width * scale_factor` by `height * scale_factor` pixels.
+    pub fn new(width: f32, height: f32, scale_factor: f32, theme: Theme, ui: impl Fn(SharedApp) -> Item) -> Self {
+        let app = SharedApp::new_headless(theme, width, height, scale_factor);
+        let surface = create_raster_surface(width, height, scale_factor);
+        let ui = ui(app.clone());
+        Self {
+            app,
+            surface,
+            ui,
+        }
+    }
+
+    pub fn app(&self) -> SharedApp {
+        self.app.clone()
+    }
+
+    pub fn item(&self) -> &Item {
+        &self.ui
+    }
+
+    pub fn item_mut(&mut self) -> &mut Item {
+        &mut self.ui
+    }
+
+    pub fn surface_mut(&mut self) -> &mut Surface {
+        &mut self.surface
+    }
+
+    pub fn width(&self) -> f32 {
+        self.app.content_width()
+    }
+
+    pub fn height(&self) -> f32 {
+        self.app.content_height()
+    }
+
+    pub fn scale_factor(&self) -> f32 {
+        self.app.scale_factor()
+    }
+
+    pub fn resize(&mut self, width: f32, height: f32, scale_factor: f32) {
+        self.app.set_headless_size(width, height, scale_factor);
+        self.surface = create_raster_surface(width, height, scale_factor);
+        self.app.request_layout();
+    }
+
+    /// Run the tasks posted through the [`UiSender`](crate::app::UiSender), then the measure and layout passes on the root item.
+    pub fn layout(&mut self) {
+        self.app.run_ui_tasks();
+        let width = self.app.content_width();
+        let height = self.app.content_height();
+        layout_frame(&mut self.ui, width, height);
+        self.app.re_layout_done();
+    }
+
+    /// Deliver a pointer action in logical coordinates, as the window would.
+    pub fn pointer_input(&mut self, action: PointerAction) -> bool {
+        dispatch_pointer_action(&self.app, &mut self.ui, action)
+    }
+
+    /// Turn the mouse wheel with the cursor at (x, y). The deltas are in logical pixels, see [`Item::mouse_wheel`].
+    pub fn mouse_wheel(&mut self, x: f32, y: f32, delta_x: f32, delta_y: f32) -> bool {
+        self.ui.dispatch_mouse_wheel(x, y, delta_x, delta_y)
+    }
+
+    /// Move the focus as an unhandled Tab, or Shift+Tab if `backward` is `true`, would.
+    /// Returns `true` if the focus moved to another item.
+    pub fn move_focus(&mut self, backward: bool) -> bool {
+        move_focus(&self.app, &mut self.ui, backward)
+    }
+
+    /// Apply a focus change requested with [`SharedApp::request_focus`].
+    pub fn apply_focus_request(&mut self) {
+        apply_focus_request(&self.app, &mut self.ui);
+    }
+
+    /// Move the cursor to a logical position, or out of the window with `None`, and update the hover state.
+    pub fn cursor_moved(&mut self, position: Option<(f32, f32)>) {
+        let cursor_icon = self.ui.update_hover(position);
+        self.app.set_cursor_icon(cursor_icon.unwrap_or(CursorIcon::Default));
+        if let Some((x, y)) = position {
+            self.ui.dispatch_cursor_moved(x, y);
+        }
+    }
+
+    /// Layout and draw the item tree, then return a snapshot of the surface.
+    pub fn render(&mut self) -> Image {
+        self.layout();
+        let scale_factor = self.app.scale_factor();
+        draw_frame(self.surface.canvas(), &mut self.ui, scale_factor);
+        self.app.redraw_done();
+        self.surface.image_snapshot()
+    }
+
+    /// Render the item tree and encode it as PNG.
+    pub fn render_png(&mut self) -> Option<Data> {
+        let image = self.render();
+        image.encode(None, EncodedImageFormat::PNG, None)
+    }
+}
+
+fn create_raster_surface(width: f32, height: f32, scale_factor: f32) -> Surface {
+    let width = (width * scale_factor).ceil().max(1.0) as i32;
+    let height = (height * scale_factor).ceil().max(1.0) as i32;
+    surfaces::raster_n32_premul((width, height)).expect("Could not create raster surface")
+}
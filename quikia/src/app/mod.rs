@@ -5,10 +5,22 @@ use std::time::Duration;
 pub use app::*;
 pub use theme::*;
 pub use window::*;
+pub use headless::*;
+pub use window_size_class::*;
+pub use ui_thread::*;
+pub(crate) use frame::*;
+pub(crate) use dispatch::*;
+pub(crate) use focus::*;
 
 mod app;
 mod window;
 mod theme;
+mod headless;
+mod frame;
+mod dispatch;
+mod focus;
+mod window_size_class;
+mod ui_thread;
 
 // use winapi::shared::windef::HWND;
 // use winapi::um::libloaderapi::{GetProcAddress, LoadLibraryA};
@@ -0,0 +1,136 @@
+use std::any::Any;
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::marker::PhantomData;
+use std::rc::Rc;
+use std::sync::{Arc, Mutex};
+use std::thread::{self, ThreadId};
+
+use winit::event_loop::EventLoopProxy;
+
+use crate::app::UserEvent;
+use crate::property::get_observable_id;
+
+pub(crate) type UiTask = Box<dyn FnOnce() + Send>;
+
+thread_local! {
+    /// The values of the [`UiHandle`]s created on this thread, by their id.
+    static UI_VALUES: RefCell<HashMap<usize, Rc<dyn Any>>> = RefCell::new(HashMap::new());
+}
+
+/// Posts tasks to the thread that runs the event loop, it can be sent to other threads.
+///
+/// Properties and items can only be used on the event loop thread, a worker thread hands them its results through
+/// [`UiSender::post`] or a [`UiHandle`] instead. Every batch of tasks is followed by a layout pass.
+#[derive(Clone)]
+pub struct UiSender {
+    tasks: Arc<Mutex<Vec<UiTask>>>,
+    event_loop_proxy: Option<EventLoopProxy<UserEvent>>,
+}
+
+impl UiSender {
+    pub(crate) fn new(event_loop_proxy: Option<EventLoopProxy<UserEvent>>) -> Self {
+        Self {
+            tasks: Arc::new(Mutex::new(Vec::new())),
+            event_loop_proxy,
+        }
+    }
+
+    /// Run the task on the event loop thread.
+    ///
+    /// Without an event loop, e.g. in [`Headless`](crate::app::Headless), the tasks run with the next layout.
+    pub fn post(&self, task: impl FnOnce() + Send + 'static) {
+        self.tasks.lock().unwrap().push(Box::new(task));
+        if let Some(event_loop_proxy) = &self.event_loop_proxy {
+            // The event loop is gone when the app exits, there is nothing left to update then.
+            let _ = event_loop_proxy.send_event(UserEvent::RunUiTasks);
+        }
+    }
+
+    /// Schedule a layout pass and a redraw from any thread.
+    pub fn request_layout(&self) {
+        self.post(|| {});
+    }
+
+    /// Run the posted tasks, returns whether there were any.
+    pub(crate) fn run_tasks(&self) -> bool {
+        let tasks = std::mem::take(&mut *self.tasks.lock().unwrap());
+        let ran = !tasks.is_empty();
+        tasks.into_iter().for_each(|task| task());
+        ran
+    }
+}
+
+struct UiHandleInner {
+    id: usize,
+    /// The thread the value lives on.
+    owner: ThreadId,
+    sender: Mutex<UiSender>,
+}
+
+impl Drop for UiHandleInner {
+    fn drop(&mut self) {
+        let id = self.id;
+        // The value is not `Send`, so it is dropped on the thread it was created on.
+        self.sender.lock().unwrap().post(move || {
+            let value = UI_VALUES.with(|values| values.borrow_mut().remove(&id));
+            drop(value);
+        });
+    }
+}
+
+/// A value that stays on the event loop thread, with a handle that can be sent to other threads.
+///
+/// Created with [`SharedApp::ui_handle`](crate::app::SharedApp::ui_handle), e.g. for a property that a worker thread
+/// updates with its result. The value is dropped when the last clone of the handle is dropped.
+///
+/// The handle has to be created on the event loop thread. Updates that end up on another thread are dropped, and fail a
+/// debug assertion.
+pub struct UiHandle<T> {
+    inner: Arc<UiHandleInner>,
+    _value: PhantomData<fn() -> T>,
+}
+
+impl<T: 'static> UiHandle<T> {
+    pub(crate) fn new(sender: UiSender, value: T) -> Self {
+        let id = get_observable_id();
+        UI_VALUES.with(|values| values.borrow_mut().insert(id, Rc::new(value)));
+        Self {
+            inner: Arc::new(UiHandleInner {
+                id,
+                owner: thread::current().id(),
+                sender: Mutex::new(sender),
+            }),
+            _value: PhantomData,
+        }
+    }
+
+    /// Run `f` with the value on the event loop thread, followed by a layout pass.
+    pub fn update(&self, f: impl FnOnce(&T) + Send + 'static) {
+        let id = self.inner.id;
+        let owner = self.inner.owner;
+        self.inner.sender.lock().unwrap().post(move || {
+            let current = thread::current().id();
+            debug_assert_eq!(
+                current, owner,
+                "The UiHandle was created on thread {:?}, but the event loop runs on thread {:?}, create it on the event loop thread",
+                owner, current
+            );
+            let Some(value) = UI_VALUES.with(|values| values.borrow().get(&id).cloned()) else {
+                return;
+            };
+            if let Some(value) = value.downcast_ref::<T>() {
+                f(value);
+            }
+        });
+    }
+}
+
+impl<T> Clone for UiHandle<T> {
+    fn clone(&self) -> Self {
+        Self {
+            inner: Arc::clone(&self.inner),
+            _value: PhantomData,
+        }
+    }
+}
@@ -15,20 +15,21 @@ use glutin::{
 use glutin::context::NotCurrentGlContext;
 use glutin_winit::DisplayBuilder;
 use raw_window_handle::HasRawWindowHandle;
-use skia_safe::{Color, ColorType, gpu::{self, backend_render_targets, gl::FramebufferInfo, SurfaceOrigin}, Surface};
+use skia_safe::{ColorType, gpu::{self, backend_render_targets, gl::FramebufferInfo, SurfaceOrigin}, Surface};
 use winit::{
-    event::{Event, WindowEvent},
+    event::{ElementState, Event, MouseButton, WindowEvent},
     event_loop::{ControlFlow, EventLoop},
-    window::{Window, WindowBuilder},
+    window::{CursorIcon, Window, WindowBuilder},
 };
 use winit::event_loop::{EventLoopBuilder, EventLoopWindowTarget};
+use winit::keyboard::ModifiersState;
 #[cfg(target_os = "android")]
 use winit::platform::android::activity::AndroidApp;
 #[cfg(target_os = "android")]
 use winit::platform::android::EventLoopBuilderExtAndroid;
 
-use crate::app::{SharedApp, Theme, UserEvent};
-use crate::ui::{Item, MeasureMode};
+use crate::app::{apply_focus_request, dispatch_ime_input, dispatch_keyboard_input, dispatch_pointer_action, draw_frame, layout_frame, SharedApp, Theme, UserEvent};
+use crate::ui::{Item, mouse_wheel_delta, PointerAction};
 use crate::widget::{Rectangle, RectangleExt};
 
 struct Env {
@@ -154,6 +155,11 @@ fn run(app: SharedApp, event_loop: EventLoop<UserEvent>, window_builder: WindowB
 
     let mut ui = app.rectangle().item();
 
+    // Logical position of the cursor and the held mouse buttons, in the order they were pressed.
+    let mut cursor_position = (0.0_f32, 0.0_f32);
+    let mut pressed_buttons: Vec<MouseButton> = Vec::new();
+    let mut modifiers = ModifiersState::empty();
+
     event_loop.run(move |event, elwt| {
         if let Event::Resumed = event {
             if env.is_none() {
@@ -168,7 +174,16 @@ fn run(app: SharedApp, event_loop: EventLoop<UserEvent>, window_builder: WindowB
         }
 
         match event {
-            Event::UserEvent(_user_event) => {
+            Event::UserEvent(user_event) => {
+                match user_event {
+                    UserEvent::TimerExpired(id, name) => {
+                        ui.find_item_mut(id, |item| item.timer_expired(&name));
+                    }
+                    UserEvent::Empty => {}
+                    UserEvent::RunUiTasks => {
+                        app.run_ui_tasks();
+                    }
+                }
             }
             Event::WindowEvent { window_id: _window_id, event } => {
                 match event {
@@ -199,26 +214,63 @@ fn run(app: SharedApp, event_loop: EventLoop<UserEvent>, window_builder: WindowB
                         let width = width as f32 / app.scale_factor();
                         let height = height as f32 / app.scale_factor();
 
-                        ui.measure(MeasureMode::Specified(width), MeasureMode::Specified(height));
-                        ui.layout(0.0, 0.0);
+                        app.update_window_size_class();
+                        layout_frame(&mut ui, width, height);
                     }
-                    WindowEvent::CursorMoved { device_id, position, .. } => {
-                        // physical_cursor_position.x = position.x as f32;
-                        // physical_cursor_position.y = position.y as f32;
-                        // let scale_factor = app.scale_factor();
-                        // cursor_position = position.to_logical(scale_factor as f64);
-
+                    WindowEvent::CursorMoved { position, .. } => {
+                        let scale_factor = app.scale_factor();
+                        let position = position.to_logical::<f32>(scale_factor as f64);
+                        cursor_position = (position.x, position.y);
+                        let (x, y) = cursor_position;
+
+                        let cursor_icon = ui.update_hover(Some((x, y)));
+                        app.set_cursor_icon(cursor_icon.unwrap_or(CursorIcon::Default));
+                        ui.dispatch_cursor_moved(x, y);
+                        dispatch_pointer_action(&app, &mut ui, PointerAction::from_cursor_moved(&pressed_buttons, x, y));
+                    }
+                    WindowEvent::CursorLeft { .. } => {
+                        ui.update_hover(None);
+                        app.set_cursor_icon(CursorIcon::Default);
                     }
                     WindowEvent::MouseInput { device_id, state, button } => {
+                        match state {
+                            ElementState::Pressed => {
+                                if !pressed_buttons.contains(&button) {
+                                    pressed_buttons.push(button);
+                                }
+                            }
+                            ElementState::Released => pressed_buttons.retain(|pressed_button| *pressed_button != button),
+                        }
+                        let (x, y) = cursor_position;
+
+                        ui.dispatch_mouse_input(device_id, state.into(), button, x, y);
+                        dispatch_pointer_action(&app, &mut ui, PointerAction::from_mouse(state.into(), button, x, y));
+                    }
+                    WindowEvent::MouseWheel { delta, .. } => {
+                        let (delta_x, delta_y) = mouse_wheel_delta(delta, app.scale_factor());
+                        let (x, y) = cursor_position;
+
+                        ui.dispatch_mouse_wheel(x, y, delta_x, delta_y);
+                    }
+                    WindowEvent::Touch(touch) => {
+                        let scale_factor = app.scale_factor();
+                        let location = touch.location.to_logical::<f32>(scale_factor as f64);
+
+                        dispatch_pointer_action(&app, &mut ui, PointerAction::from_touch(touch.phase, location, touch.force, touch.id));
+                    }
+
+                    WindowEvent::ModifiersChanged(new_modifiers) => {
+                        modifiers = new_modifiers.state();
                     }
 
                     WindowEvent::KeyboardInput {
-                        device_id: _device_id, event: _event, is_synthetic: _is_synthetic
+                        device_id, event, is_synthetic
                     } => {
-
+                        dispatch_keyboard_input(&app, &mut ui, device_id, event, is_synthetic, modifiers.shift_key());
                     }
 
                     WindowEvent::Ime(ime) => {
+                        dispatch_ime_input(&app, &mut ui, ime.into());
                     }
 
                     WindowEvent::RedrawRequested => {
@@ -242,34 +294,24 @@ fn run(app: SharedApp, event_loop: EventLoop<UserEvent>, window_builder: WindowB
         //     app.request_layout();
         // }
 
-        // if app.lock().unwrap().need_layout {
-        //     let (width, height): (f32, f32) = app.lock().unwrap().window().inner_size().into();
-        //     let scale_factor = app.scale_factor();
-        //     let width = width / scale_factor;
-        //     let height = height / scale_factor;
-        //     pages.iter_mut().for_each(|page_item| {
-        //         page_item.root_item_mut().measure(MeasureMode::Specified(width), MeasureMode::Specified(height));
-        //         page_item.root_item_mut().layout(0.0, 0.0);
-        //     });
-        //     app.re_layout_done();
-        //     app.request_redraw();
-        // }
+        if env.is_some() {
+            apply_focus_request(&app, &mut ui);
+        }
+
+        if app.lock().unwrap().need_layout && env.is_some() {
+            let width = app.content_width();
+            let height = app.content_height();
+            layout_frame(&mut ui, width, height);
+            app.re_layout_done();
+            app.request_redraw();
+        }
 
 
         if app.lock().unwrap().need_redraw {
             let env = env.as_mut().unwrap();
             let scale_factor = app.scale_factor();
 
-            let canvas = env.surface.canvas();
-
-            canvas.clear(Color::TRANSPARENT);
-
-            canvas.save();
-            canvas.scale((scale_factor, scale_factor));
-
-            ui.draw(canvas);
-
-            canvas.restore();
+            draw_frame(env.surface.canvas(), &mut ui, scale_factor);
 
             env.gr_context.flush_and_submit();
             env.gl_surface.swap_buffers(&env.gl_context).unwrap();
@@ -0,0 +1,28 @@
+/// The Material window size classes, picked by the logical width of the content.
+///
+/// Bind properties to [`SharedApp::window_size_class`](crate::app::SharedApp::window_size_class),
+/// or create them with [`SharedApp::responsive`](crate::app::SharedApp::responsive), to switch layouts when the window resizes.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum WindowSizeClass {
+    /// Narrower than 600, most phones in portrait.
+    Compact,
+    /// From 600 to 840, tablets in portrait and foldables.
+    Medium,
+    /// 840 and wider, tablets in landscape and desktop windows.
+    Expanded,
+}
+
+impl WindowSizeClass {
+    pub const MEDIUM_MIN_WIDTH: f32 = 600.0;
+    pub const EXPANDED_MIN_WIDTH: f32 = 840.0;
+
+    pub fn from_width(width: f32) -> Self {
+        if width >= Self::EXPANDED_MIN_WIDTH {
+            WindowSizeClass::Expanded
+        } else if width >= Self::MEDIUM_MIN_WIDTH {
+            WindowSizeClass::Medium
+        } else {
+            WindowSizeClass::Compact
+        }
+    }
+}
@@ -1,12 +1,18 @@
+use std::sync::{Arc, Mutex};
+
 use crate::app::SharedApp;
-use crate::impl_item_property;
-use crate::item::{Gravity, Item, ItemEvent, LayoutDirection, measure_child, MeasureMode};
-use crate::property::{Gettable, SharedProperty};
+use crate::layout::{children_property, gravity_offset, init_child_margins};
+use crate::property::{Gettable, SharedProperty, Size};
+use crate::ui::{clamp_size, Item, ItemEvent, LayoutParams, LogicalX, measure_child, MeasureMode};
 
 #[macro_export]
 macro_rules! axis_layout {
-    ($($child:expr)*) => {
-        $crate::layout::AxisLayout::new(vec![$($child),*])
+    ($($child:expr),+ $(,)?) => {
+        {
+            let children = vec![$($child),*];
+            let app = children.first().unwrap().get_app().clone();
+            $crate::layout::AxisLayout::new(app, children)
+        }
     }
 }
 
@@ -19,249 +25,188 @@ pub enum Axis {
 
 pub type AxisProperty = SharedProperty<Axis>;
 
+struct AxisLayoutProperties {
+    axis: AxisProperty,
+}
+
+/// Places the children one after another along an axis.
+/// Children whose size along the axis is [`Size::Fill`] share the space left by the other children.
 pub struct AxisLayout {
     item: Item,
-    axis: AxisProperty,
+    properties: Arc<Mutex<AxisLayoutProperties>>,
 }
 
 impl AxisLayout {
-    pub fn new(children: Vec<Item>) -> Self {
-        let axis: AxisProperty = Axis::Horizontal.into();
+    pub fn new(app: SharedApp, children: Vec<Item>) -> Self {
+        let properties = Arc::new(Mutex::new(AxisLayoutProperties {
+            axis: Axis::Horizontal.into(),
+        }));
         let mut item = Item::new(
+            app,
             ItemEvent::default()
-                .set_on_measure(
+                .set_measure_event(
                     {
-                        let axis = axis.clone();
+                        let properties = properties.clone();
                         move |item, width_measure_mode, height_measure_mode| {
-                            let mut layout_params = item.get_layout_params_mut().clone();
+                            let mut layout_params = item.get_layout_params().clone();
                             layout_params.init_from_item(item);
-                            let max_width = layout_params.max_width;
-                            let max_height = layout_params.max_height;
-                            let min_width = layout_params.min_width;
-                            let min_height = layout_params.min_height;
-                            match axis.get() {
-                                Axis::Horizontal => {
-                                    let mut width = 0.0;
-                                    let mut height = 0.0_f32;
-
-                                    let mut remaining_width = match width_measure_mode {
-                                        MeasureMode::Exactly(width) => width,
-                                        MeasureMode::AtMost(width) => width,
+                            let axis = properties.lock().unwrap().axis.get();
+
+                            let horizontal_padding = layout_params.padding_start + layout_params.padding_end;
+                            let vertical_padding = layout_params.padding_top + layout_params.padding_bottom;
+                            let available = match axis {
+                                Axis::Horizontal => width_measure_mode.value() - horizontal_padding,
+                                Axis::Vertical => height_measure_mode.value() - vertical_padding,
+                            };
+
+                            // The size of the children along the axis and across it.
+                            let mut occupied = 0.0_f32;
+                            let mut cross = 0.0_f32;
+                            let mut fill_children = Vec::new();
+
+                            let children = item.get_children();
+                            let mut children = children.lock();
+
+                            children.iter_mut().enumerate().for_each(|(index, child)| {
+                                init_child_margins(child);
+                                // Without a bound along the axis, e.g. in a scroll, there is no remaining space to fill.
+                                if is_fill(child, axis) && available.is_finite() {
+                                    fill_children.push(index);
+                                    return;
+                                }
+                                let remaining = (available - occupied).max(0.0);
+                                let (child_width_measure_mode, child_height_measure_mode) = match axis {
+                                    Axis::Horizontal => measure_child(child, &layout_params, width_measure_mode.with_value(remaining + horizontal_padding), height_measure_mode),
+                                    Axis::Vertical => measure_child(child, &layout_params, width_measure_mode, height_measure_mode.with_value(remaining + vertical_padding)),
+                                };
+                                child.measure(child_width_measure_mode, child_height_measure_mode);
+                                let (child_occupied, child_cross) = occupied_size(child.get_layout_params(), axis);
+                                occupied += child_occupied;
+                                cross = cross.max(child_cross);
+                            });
+
+                            if !fill_children.is_empty() {
+                                let fill_size = (available - occupied).max(0.0) / fill_children.len() as f32;
+                                fill_children.iter().for_each(|index| {
+                                    let child = children.get_mut(*index).unwrap();
+                                    let (child_width_measure_mode, child_height_measure_mode) = match axis {
+                                        Axis::Horizontal => measure_child(child, &layout_params, MeasureMode::Specified(fill_size + horizontal_padding), height_measure_mode),
+                                        Axis::Vertical => measure_child(child, &layout_params, width_measure_mode, MeasureMode::Specified(fill_size + vertical_padding)),
                                     };
+                                    child.measure(child_width_measure_mode, child_height_measure_mode);
+                                    let (child_occupied, child_cross) = occupied_size(child.get_layout_params(), axis);
+                                    occupied += child_occupied;
+                                    cross = cross.max(child_cross);
+                                });
+                            }
+                            drop(children);
 
-                                    let measure =|child:&mut Item| {
-                                        let width_measure_mode = match width_measure_mode {
-                                            MeasureMode::Exactly(_) => MeasureMode::Exactly(remaining_width),
-                                            MeasureMode::AtMost(_) => MeasureMode::AtMost(remaining_width),
-                                        };
-
-                                        let mut child_occupied_width = 0.0;
-                                        let (child_width_measure_mode, child_height_measure_mode) = measure_child(child,layout_params, width_measure_mode, height_measure_mode);
-                                        child.measure(child_width_measure_mode, child_height_measure_mode);
-                                        let child_layout_params = child.get_layout_params();
-
-                                        child_occupied_width = child_layout_params.width + child_layout_params.margin_start + child_layout_params.margin_end;
-                                        height = height.max(child_layout_params.height + child_layout_params.margin_top + child_layout_params.margin_bottom);
+                            let (content_width, content_height) = match axis {
+                                Axis::Horizontal => (occupied, cross),
+                                Axis::Vertical => (cross, occupied),
+                            };
 
-                                        width += child_occupied_width;
+                            layout_params.width = clamp_size(match width_measure_mode {
+                                MeasureMode::Specified(width) => width,
+                                MeasureMode::Unspecified(width) => width.min(content_width + horizontal_padding),
+                            }, layout_params.min_width, layout_params.max_width);
 
-                                        if remaining_width - child_occupied_width < 0.0 {
-                                            remaining_width = 0.0;
-                                        } else {
-                                            remaining_width -= child_occupied_width;
-                                        }
-                                    };
+                            layout_params.height = clamp_size(match height_measure_mode {
+                                MeasureMode::Specified(height) => height,
+                                MeasureMode::Unspecified(height) => height.min(content_height + vertical_padding),
+                            }, layout_params.min_height, layout_params.max_height);
 
-                                    match item.get_layout_direction().get() {
-                                        LayoutDirection::LeftToRight => {
-                                            item.get_children_mut().iter_mut().for_each(measure);
-                                        }
-                                        LayoutDirection::RightToLeft => {
-                                            item.get_children_mut().iter_mut().rev().for_each(measure);
-                                        }
-                                    };
-                                    match width_measure_mode {
-                                        MeasureMode::Exactly(measured_width) => {
-                                            layout_params.width = measured_width.clamp(min_width, max_width);
-                                        }
-                                        MeasureMode::AtMost(measured_width) => {
-                                            layout_params.width = measured_width.min(width).clamp(min_width, max_width);
-                                        }
-                                    }
-
-                                    match height_measure_mode {
-                                        MeasureMode::Exactly(measured_height) => {
-                                            layout_params.height = measured_height.clamp(min_height, max_height);
-                                        }
-                                        MeasureMode::AtMost(measured_height) => {
-                                            layout_params.height = measured_height.min(height).clamp(min_height, max_height);
-                                        }
-                                    }
-                                }
-                                Axis::Vertical => {
-                                    let mut width = 0.0_f32;
-                                    let mut height = 0.0;
-
-                                    let mut remaining_height = match height_measure_mode {
-                                        MeasureMode::Exactly(height) => height,
-                                        MeasureMode::AtMost(height) => height,
-                                    };
+                            if let Some(background) = item.get_background().lock().as_mut() {
+                                background.measure(MeasureMode::Specified(layout_params.width), MeasureMode::Specified(layout_params.height));
+                            }
 
-                                    item.get_children_mut().iter_mut().for_each(|child| {
-                                        let height_measure_mode = match height_measure_mode {
-                                            MeasureMode::Exactly(_) => MeasureMode::Exactly(remaining_height),
-                                            MeasureMode::AtMost(_) => MeasureMode::AtMost(remaining_height),
-                                        };
-
-                                        let mut child_occupied_height = 0.0;
-                                        let (child_width_measure_mode, child_height_measure_mode) = measure_child(child,layout_params, width_measure_mode, height_measure_mode);
-                                        child.measure(child_width_measure_mode, child_height_measure_mode);
-                                        let child_layout_params = child.get_layout_params();
-
-                                        child_occupied_height = child_layout_params.height + child_layout_params.margin_top + child_layout_params.margin_bottom;
-                                        width = width.max(child_layout_params.width + child_layout_params.margin_start + child_layout_params.margin_end);
-
-                                        height += child_occupied_height;
-
-                                        if remaining_height - child_occupied_height < 0.0 {
-                                            remaining_height = 0.0;
-                                        } else {
-                                            remaining_height -= child_occupied_height;
-                                        }
-                                    });
-
-                                    match width_measure_mode {
-                                        MeasureMode::Exactly(measured_width) => {
-                                            layout_params.width = measured_width.clamp(min_width, max_width);
-                                        }
-                                        MeasureMode::AtMost(measured_width) => {
-                                            layout_params.width = measured_width.min(width).clamp(min_width, max_width);
-                                        }
-                                    }
-
-                                    match height_measure_mode {
-                                        MeasureMode::Exactly(measured_height) => {
-                                            layout_params.height = measured_height.clamp(min_height, max_height);
-                                        }
-                                        MeasureMode::AtMost(measured_height) => {
-                                            layout_params.height = measured_height.min(height).clamp(min_height, max_height);
-                                        }
-                                    }
-                                }
+                            if let Some(foreground) = item.get_foreground().lock().as_mut() {
+                                foreground.measure(MeasureMode::Specified(layout_params.width), MeasureMode::Specified(layout_params.height));
                             }
 
-                            item.set_layout_params(layout_params);
+                            item.set_layout_params(&layout_params);
                         }
                     }
                 )
-                .set_on_layout(
+                .set_layout_event(
                     {
-                        let axis = axis.clone();
+                        let properties = properties.clone();
                         move |item, x, y| {
-                            let mut layout_params = item.get_layout_params().clone();
-                            layout_params.x = x;
-                            layout_params.y = y;
-                            item.set_layout_params(layout_params);
+                            let axis = properties.lock().unwrap().axis.get();
                             let direction = item.get_layout_direction().get();
+                            let horizontal_gravity = item.get_horizontal_gravity().get();
                             let vertical_gravity = item.get_vertical_gravity().get();
-                            match axis.get() {
-                                Axis::Horizontal => {
-                                    let children_occupied_width = item.get_children().iter().fold(0.0, |acc, child| {
-                                        let child_layout_params = child.get_layout_params();
-                                        acc + child_layout_params.width + child_layout_params.margin_start + child_layout_params.margin_end
-                                    });
-                                    match direction {
-                                        LayoutDirection::LeftToRight => {
-                                            let mut x = match item.get_horizontal_gravity().get() {
-                                                Gravity::Start => {
-                                                    layout_params.x
-                                                }
-                                                Gravity::Center => {
-                                                    layout_params.x + (layout_params.width - children_occupied_width) / 2.0
-                                                }
-                                                Gravity::End => {
-                                                    layout_params.x + layout_params.width - children_occupied_width
-                                                }
-                                            };
-                                            item.get_children_mut().iter_mut().for_each(|child| {
-                                                let child_layout_params = child.get_layout_params().clone();
-                                                let child_x = x + child_layout_params.margin_start;
-                                                let child_y = match vertical_gravity {
-                                                    Gravity::Start => {
-                                                        layout_params.y + child_layout_params.margin_top
-                                                    }
-                                                    Gravity::Center => {
-                                                        layout_params.y + (layout_params.height - child_layout_params.height) / 2.0
-                                                    }
-                                                    Gravity::End => {
-                                                        layout_params.y + layout_params.height - child_layout_params.height - child_layout_params.margin_bottom
-                                                    }
-                                                };
-                                                child.layout(child_x, child_y);
-                                                x += child_layout_params.width + child_layout_params.margin_start + child_layout_params.margin_end;
-                                            });
-                                        }
-                                        LayoutDirection::RightToLeft => {
-                                            let mut x = match item.get_horizontal_gravity().get() {
-                                                Gravity::Start => {
-                                                    layout_params.x + layout_params.x + layout_params.width
-                                                }
-                                                Gravity::Center => {
-                                                    layout_params.x + layout_params.width - (layout_params.width - children_occupied_width) / 2.0
-                                                }
-                                                Gravity::End => {
-                                                    layout_params.x + layout_params.width
-                                                }
-                                            };
-                                            item.get_children_mut().iter_mut().for_each(|child| {
-                                                let child_layout_params = child.get_layout_params().clone();
-                                                let child_x = x - child_layout_params.margin_start - child_layout_params.width;
-                                                let child_y = match vertical_gravity {
-                                                    Gravity::Start => {
-                                                        layout_params.y + child_layout_params.margin_top
-                                                    }
-                                                    Gravity::Center => {
-                                                        layout_params.y + (layout_params.height - child_layout_params.height) / 2.0
-                                                    }
-                                                    Gravity::End => {
-                                                        layout_params.y + layout_params.height - child_layout_params.height - child_layout_params.margin_bottom
-                                                    }
-                                                };
-                                                child.layout(child_x, child_y);
-                                                x -= child_layout_params.width + child_layout_params.margin_start + child_layout_params.margin_end;
-                                            });
-                                        }
-                                    }
-                                }
-                                Axis::Vertical => {
-                                    let mut y = layout_params.y;
-                                    item.get_children_mut().iter_mut().for_each(|child| {
-                                        let child_layout_params = child.get_layout_params().clone();
-                                        let child_x = match direction {
-                                            LayoutDirection::LeftToRight => {
-                                                layout_params.x + child_layout_params.margin_start
-                                            }
-                                            LayoutDirection::RightToLeft => {
-                                                layout_params.x + layout_params.width - child_layout_params.margin_start - child_layout_params.width
-                                            }
-                                        };
-                                        let child_y = y + child_layout_params.margin_top;
-                                        child.layout(child_x, child_y);
-                                        y += child_layout_params.height + child_layout_params.margin_top + child_layout_params.margin_bottom;
-                                    });
-                                }
+
+                            let mut layout_params = item.get_layout_params().clone();
+                            layout_params.relative_x = x;
+                            layout_params.relative_y = y;
+                            item.set_layout_params(&layout_params);
+
+                            if let Some(background) = item.get_background().lock().as_mut() {
+                                background.layout(x, y);
+                            }
+
+                            if let Some(foreground) = item.get_foreground().lock().as_mut() {
+                                foreground.layout(x, y);
                             }
+
+                            let content_width = layout_params.width - layout_params.padding_start - layout_params.padding_end;
+                            let content_height = layout_params.height - layout_params.padding_top - layout_params.padding_bottom;
+
+                            let children = item.get_children();
+                            let mut children = children.lock();
+
+                            let occupied = children.iter().fold(0.0, |acc, child| acc + occupied_size(child.get_layout_params(), axis).0);
+
+                            // The position along the axis, relative to the start of the content.
+                            let mut position = match axis {
+                                Axis::Horizontal => gravity_offset(horizontal_gravity, content_width - occupied),
+                                Axis::Vertical => gravity_offset(vertical_gravity, content_height - occupied),
+                            };
+
+                            children.iter_mut().for_each(|child| {
+                                let child_layout_params = child.get_layout_params().clone();
+                                let (child_occupied, child_cross) = occupied_size(&child_layout_params, axis);
+                                let (logical_x, child_y) = match axis {
+                                    Axis::Horizontal => (
+                                        position + child_layout_params.margin_start,
+                                        gravity_offset(vertical_gravity, content_height - child_cross) + child_layout_params.margin_top,
+                                    ),
+                                    Axis::Vertical => (
+                                        gravity_offset(horizontal_gravity, content_width - child_cross) + child_layout_params.margin_start,
+                                        position + child_layout_params.margin_top,
+                                    ),
+                                };
+                                let child_x = LogicalX::new(direction, 0.0, layout_params.padding_start + logical_x, layout_params.width)
+                                    .physical_value(child_layout_params.width);
+                                child.layout(child_x, layout_params.padding_top + child_y);
+                                position += child_occupied;
+                            });
                         }
                     }
                 )
         );
-        item.set_children(children);
+        item.set_children(children_property(children));
         Self {
             item,
-            axis: Axis::Horizontal.into(),
+            properties,
         }
     }
 
+    pub fn axis(mut self, axis: impl Into<AxisProperty>) -> Self {
+        let axis = axis.into();
+        let layout_node = self.item.get_layout_node();
+        self.item.subscribe(&axis, move || {
+            layout_node.invalidate();
+        });
+        self.properties.lock().unwrap().axis = axis;
+        self
+    }
+
+    pub fn get_axis(&self) -> AxisProperty {
+        self.properties.lock().unwrap().axis.clone()
+    }
+
     pub fn unwrap(self) -> Item {
         self.item
     }
@@ -271,3 +216,19 @@ impl AxisLayout {
     }
 }
 
+fn is_fill(child: &Item, axis: Axis) -> bool {
+    match axis {
+        Axis::Horizontal => child.get_width().get() == Size::Fill,
+        Axis::Vertical => child.get_height().get() == Size::Fill,
+    }
+}
+
+/// The space the child occupies along the axis and across it, margins included.
+fn occupied_size(child_layout_params: &LayoutParams, axis: Axis) -> (f32, f32) {
+    let width = child_layout_params.width + child_layout_params.margin_start + child_layout_params.margin_end;
+    let height = child_layout_params.height + child_layout_params.margin_top + child_layout_params.margin_bottom;
+    match axis {
+        Axis::Horizontal => (width, height),
+        Axis::Vertical => (height, width),
+    }
+}
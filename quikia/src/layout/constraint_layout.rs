@@ -0,0 +1,516 @@
+use std::collections::HashMap;
+use std::fmt::{Display, Formatter};
+use std::sync::{Arc, Mutex};
+
+use crate::app::{after_layout, SharedApp};
+use crate::layout::{children_property, init_child_margins};
+use crate::property::{Gettable, SharedProperty, Size};
+use crate::ui::{clamp_size, Item, ItemEvent, LogicalX, measure_child, MeasureMode};
+use crate::ui::additional_property::{BaseLine, ConstraintAnchor, ConstraintPlacement, PARENT};
+
+#[macro_export]
+macro_rules! constraint_layout {
+    ($($child:expr),+ $(,)?) => {
+        {
+            let children = vec![$($child),*];
+            let app = children.first().unwrap().get_app().clone();
+            $crate::layout::ConstraintLayout::new(app, children)
+        }
+    }
+}
+
+/// How the free space is shared between the children of a chain.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ChainStyle {
+    /// Equal space before, between and after the children.
+    Spread,
+    /// Equal space between the children, none before the first and after the last one.
+    SpreadInside,
+    /// The children are packed together, placed by the bias of the first child.
+    Packed,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum ConstraintError {
+    /// A constraint refers to a tag that no sibling has.
+    UnknownTag(String),
+    /// The constraints depend on each other in a loop. The tags of the children in the loop, in order.
+    Cycle(Vec<String>),
+    /// A child is in more than one chain along the same axis.
+    MultipleChains(String),
+}
+
+impl Display for ConstraintError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ConstraintError::UnknownTag(tag) => write!(f, "no child of the constraint layout has the tag \"{}\"", tag),
+            ConstraintError::Cycle(tags) => write!(f, "circular constraints: {} -> {}", tags.join(" -> "), tags.first().map(String::as_str).unwrap_or_default()),
+            ConstraintError::MultipleChains(tag) => write!(f, "the child with the tag \"{}\" is in more than one chain", tag),
+        }
+    }
+}
+
+impl std::error::Error for ConstraintError {}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Target {
+    Parent,
+    Child(usize),
+}
+
+#[derive(Clone, Copy, Debug)]
+enum Side {
+    Start,
+    End,
+}
+
+#[derive(Clone, Copy, Debug)]
+struct Anchor {
+    target: Target,
+    side: Side,
+}
+
+struct Chain {
+    members: Vec<usize>,
+    style: ChainStyle,
+}
+
+/// The constraints of a child along one axis. Top and bottom are start and end of the vertical axis.
+struct AxisNode {
+    start: Option<Anchor>,
+    end: Option<Anchor>,
+    baseline: Option<Target>,
+    size: f32,
+    /// The distance from the top to the baseline.
+    baseline_offset: f32,
+    margin_start: f32,
+    margin_end: f32,
+    bias: f32,
+    fill: bool,
+    chain: Option<usize>,
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum State {
+    Unresolved,
+    Resolving,
+    Resolved,
+}
+
+/// Resolves the positions of the children along one axis, following the constraints depth first.
+struct AxisSolver<'a> {
+    nodes: &'a mut [AxisNode],
+    chains: &'a [Chain],
+    tags: &'a [String],
+    parent_start: f32,
+    parent_end: f32,
+    positions: Vec<f32>,
+    states: Vec<State>,
+    stack: Vec<usize>,
+}
+
+impl<'a> AxisSolver<'a> {
+    fn solve(nodes: &'a mut [AxisNode], chains: &'a [Chain], tags: &'a [String], parent_start: f32, parent_end: f32) -> Result<Vec<f32>, ConstraintError> {
+        let count = nodes.len();
+        let mut solver = AxisSolver {
+            nodes,
+            chains,
+            tags,
+            parent_start,
+            parent_end,
+            positions: vec![0.0; count],
+            states: vec![State::Unresolved; count],
+            stack: Vec::new(),
+        };
+        for index in 0..count {
+            solver.resolve(index)?;
+        }
+        Ok(solver.positions)
+    }
+
+    fn cycle_error(&self, index: usize) -> ConstraintError {
+        let start = self.stack.iter().position(|member| *member == index).unwrap_or(0);
+        ConstraintError::Cycle(self.stack[start..].iter().map(|member| self.tags[*member].clone()).collect())
+    }
+
+    fn enter(&mut self, index: usize) -> Result<(), ConstraintError> {
+        if self.states[index] == State::Resolving {
+            return Err(self.cycle_error(index));
+        }
+        self.states[index] = State::Resolving;
+        self.stack.push(index);
+        Ok(())
+    }
+
+    fn leave(&mut self, index: usize) {
+        self.stack.retain(|member| *member != index);
+        self.states[index] = State::Resolved;
+    }
+
+    fn anchor_position(&mut self, anchor: Anchor) -> Result<f32, ConstraintError> {
+        match anchor.target {
+            Target::Parent => Ok(match anchor.side {
+                Side::Start => self.parent_start,
+                Side::End => self.parent_end,
+            }),
+            Target::Child(index) => {
+                self.resolve(index)?;
+                Ok(match anchor.side {
+                    Side::Start => self.positions[index],
+                    Side::End => self.positions[index] + self.nodes[index].size,
+                })
+            }
+        }
+    }
+
+    fn resolve(&mut self, index: usize) -> Result<(), ConstraintError> {
+        match self.states[index] {
+            State::Resolved => return Ok(()),
+            State::Resolving => return Err(self.cycle_error(index)),
+            State::Unresolved => {}
+        }
+        if let Some(chain) = self.nodes[index].chain {
+            return self.resolve_chain(chain);
+        }
+        self.enter(index)?;
+
+        let position = if let Some(target) = self.nodes[index].baseline {
+            let target_baseline = match target {
+                Target::Parent => self.parent_start,
+                Target::Child(target_index) => {
+                    self.resolve(target_index)?;
+                    self.positions[target_index] + self.nodes[target_index].baseline_offset
+                }
+            };
+            target_baseline - self.nodes[index].baseline_offset
+        } else {
+            let start = self.nodes[index].start.map(|anchor| self.anchor_position(anchor)).transpose()?;
+            let end = self.nodes[index].end.map(|anchor| self.anchor_position(anchor)).transpose()?;
+            let node = &mut self.nodes[index];
+            match (start, end) {
+                (Some(start), Some(end)) => {
+                    if node.fill {
+                        node.size = (end - start - node.margin_start - node.margin_end).max(0.0);
+                    }
+                    let free = end - start - node.size - node.margin_start - node.margin_end;
+                    start + node.margin_start + free * node.bias
+                }
+                (Some(start), None) => start + node.margin_start,
+                (None, Some(end)) => end - node.margin_end - node.size,
+                (None, None) => self.parent_start + node.margin_start,
+            }
+        };
+        self.positions[index] = position;
+
+        self.leave(index);
+        Ok(())
+    }
+
+    fn resolve_chain(&mut self, chain: usize) -> Result<(), ConstraintError> {
+        let members = self.chains[chain].members.clone();
+        let style = self.chains[chain].style;
+        for member in members.iter() {
+            self.enter(*member)?;
+        }
+
+        let head = *members.first().unwrap();
+        let tail = *members.last().unwrap();
+        let start = match self.nodes[head].start {
+            Some(anchor) => self.anchor_position(anchor)?,
+            None => self.parent_start,
+        };
+        let end = match self.nodes[tail].end {
+            Some(anchor) => self.anchor_position(anchor)?,
+            None => self.parent_end,
+        };
+
+        let occupied: f32 = members.iter().map(|member| {
+            let node = &self.nodes[*member];
+            node.size + node.margin_start + node.margin_end
+        }).sum();
+        let free = (end - start - occupied).max(0.0);
+        let count = members.len() as f32;
+        let (mut position, between) = match style {
+            ChainStyle::Spread => (start + free / (count + 1.0), free / (count + 1.0)),
+            ChainStyle::SpreadInside if members.len() > 1 => (start, free / (count - 1.0)),
+            ChainStyle::SpreadInside => (start + free / 2.0, 0.0),
+            ChainStyle::Packed => (start + free * self.nodes[head].bias, 0.0),
+        };
+        for member in members.iter() {
+            let node = &self.nodes[*member];
+            self.positions[*member] = position + node.margin_start;
+            position += node.size + node.margin_start + node.margin_end + between;
+        }
+
+        for member in members.iter() {
+            self.leave(*member);
+        }
+        Ok(())
+    }
+}
+
+struct ConstraintLayoutProperties {
+    horizontal_chains: Vec<(Vec<String>, ChainStyle)>,
+    vertical_chains: Vec<(Vec<String>, ChainStyle)>,
+    /// The position of each child, the x being logical.
+    positions: Vec<(f32, f32)>,
+    /// The error of the last measure, if the constraints could not be resolved.
+    error: SharedProperty<Option<ConstraintError>>,
+}
+
+/// Places each child relative to its siblings or to the layout, see [`ConstraintPlacement`].
+/// Children without constraints along an axis are placed at the start of the layout.
+///
+/// The layout takes all the space it is given. If circular constraints or unknown tags keep an axis from being resolved,
+/// all children are placed at the start of the layout along that axis and the error is reported by
+/// [`ConstraintLayout::get_error`] once the layout pass is done.
+/// Use [`ConstraintLayout::validate`] to check the constraints beforehand.
+pub struct ConstraintLayout {
+    item: Item,
+    properties: Arc<Mutex<ConstraintLayoutProperties>>,
+}
+
+impl ConstraintLayout {
+    pub fn new(app: SharedApp, children: Vec<Item>) -> Self {
+        let properties = Arc::new(Mutex::new(ConstraintLayoutProperties {
+            horizontal_chains: Vec::new(),
+            vertical_chains: Vec::new(),
+            positions: Vec::new(),
+            error: SharedProperty::from_value(None),
+        }));
+        let mut item = Item::new(
+            app,
+            ItemEvent::default()
+                .set_measure_event({
+                    let properties = properties.clone();
+                    move |item, width_measure_mode, height_measure_mode| {
+                        let mut layout_params = item.get_layout_params().clone();
+                        layout_params.init_from_item(item);
+                        layout_params.width = clamp_size(width_measure_mode.value(), layout_params.min_width, layout_params.max_width);
+                        layout_params.height = clamp_size(height_measure_mode.value(), layout_params.min_height, layout_params.max_height);
+
+                        let children = item.get_children();
+                        let mut children = children.lock();
+                        children.iter_mut().for_each(|child| {
+                            init_child_margins(child);
+                            let (child_width_measure_mode, child_height_measure_mode) = measure_child(child, &layout_params, width_measure_mode, height_measure_mode);
+                            child.measure(child_width_measure_mode, child_height_measure_mode);
+                        });
+
+                        let mut properties = properties.lock().unwrap();
+                        let tags: Vec<String> = children.iter().map(|child| child.get_tag().to_string()).collect();
+                        let children_ref: Vec<&Item> = children.iter().collect();
+
+                        let horizontal = solve(&children_ref, &tags, &properties.horizontal_chains, true, layout_params.padding_start, layout_params.width - layout_params.padding_end);
+                        let mut error = None;
+                        let (x_positions, widths) = horizontal.unwrap_or_else(|horizontal_error| {
+                            error = Some(horizontal_error);
+                            start_positions(&children_ref, true, layout_params.padding_start)
+                        });
+                        drop(children_ref);
+
+                        // Children stretched between their anchors are measured again, their heights may change with their widths.
+                        children.iter_mut().zip(widths.iter()).for_each(|(child, width)| {
+                            if child.get_layout_params().width != *width {
+                                let (_, child_height_measure_mode) = measure_child(child, &layout_params, width_measure_mode, height_measure_mode);
+                                child.measure(MeasureMode::Specified(*width), child_height_measure_mode);
+                            }
+                        });
+
+                        let children_ref: Vec<&Item> = children.iter().collect();
+                        let vertical = solve(&children_ref, &tags, &properties.vertical_chains, false, layout_params.padding_top, layout_params.height - layout_params.padding_bottom);
+                        let (y_positions, heights) = vertical.unwrap_or_else(|vertical_error| {
+                            error.get_or_insert(vertical_error);
+                            start_positions(&children_ref, false, layout_params.padding_top)
+                        });
+                        drop(children_ref);
+
+                        children.iter_mut().zip(heights.iter()).for_each(|(child, height)| {
+                            if child.get_layout_params().height != *height {
+                                let width = child.get_layout_params().width;
+                                child.measure(MeasureMode::Specified(width), MeasureMode::Specified(*height));
+                            }
+                        });
+                        drop(children);
+
+                        properties.positions = x_positions.into_iter().zip(y_positions).collect();
+                        let error_property = properties.error.clone();
+                        drop(properties);
+                        // The observers of the error may change the layout, which can not happen while it is measured.
+                        after_layout(move || {
+                            if error_property.get() != error {
+                                error_property.set_value(error);
+                            }
+                        });
+
+                        if let Some(background) = item.get_background().lock().as_mut() {
+                            background.measure(MeasureMode::Specified(layout_params.width), MeasureMode::Specified(layout_params.height));
+                        }
+
+                        if let Some(foreground) = item.get_foreground().lock().as_mut() {
+                            foreground.measure(MeasureMode::Specified(layout_params.width), MeasureMode::Specified(layout_params.height));
+                        }
+
+                        item.set_layout_params(&layout_params);
+                    }
+                })
+                .set_layout_event({
+                    let properties = properties.clone();
+                    move |item, x, y| {
+                        let direction = item.get_layout_direction().get();
+
+                        let mut layout_params = item.get_layout_params().clone();
+                        layout_params.relative_x = x;
+                        layout_params.relative_y = y;
+                        item.set_layout_params(&layout_params);
+
+                        if let Some(background) = item.get_background().lock().as_mut() {
+                            background.layout(x, y);
+                        }
+
+                        if let Some(foreground) = item.get_foreground().lock().as_mut() {
+                            foreground.layout(x, y);
+                        }
+
+                        let properties = properties.lock().unwrap();
+                        item.get_children().lock().iter_mut().zip(properties.positions.iter()).for_each(|(child, (child_x, child_y))| {
+                            let child_width = child.get_layout_params().width;
+                            let child_x = LogicalX::new(direction, 0.0, *child_x, layout_params.width).physical_value(child_width);
+                            child.layout(child_x, *child_y);
+                        });
+                    }
+                })
+        );
+        item.set_children(children_property(children));
+        Self {
+            item,
+            properties,
+        }
+    }
+
+    /// Chain the children with these tags from the start to the end. The chain is placed between
+    /// the start anchor of its first child and the end anchor of its last one, or the layout if they have none.
+    pub fn horizontal_chain(self, tags: Vec<&str>, style: ChainStyle) -> Self {
+        self.properties.lock().unwrap().horizontal_chains.push((tags.into_iter().map(String::from).collect(), style));
+        self.item.get_layout_node().invalidate();
+        self
+    }
+
+    /// Chain the children with these tags from the top to the bottom, see [`ConstraintLayout::horizontal_chain`].
+    pub fn vertical_chain(self, tags: Vec<&str>, style: ChainStyle) -> Self {
+        self.properties.lock().unwrap().vertical_chains.push((tags.into_iter().map(String::from).collect(), style));
+        self.item.get_layout_node().invalidate();
+        self
+    }
+
+    /// Check that all constraints refer to existing tags and do not depend on each other in a loop.
+    pub fn validate(&self) -> Result<(), ConstraintError> {
+        let properties = self.properties.lock().unwrap();
+        let children = self.item.get_children();
+        let children = children.lock();
+        let tags: Vec<String> = children.iter().map(|child| child.get_tag().to_string()).collect();
+        let children: Vec<&Item> = children.iter().collect();
+        solve(&children, &tags, &properties.horizontal_chains, true, 0.0, 0.0)?;
+        solve(&children, &tags, &properties.vertical_chains, false, 0.0, 0.0)?;
+        Ok(())
+    }
+
+    /// The error that kept the constraints from being resolved in the last layout pass, `None` if they were resolved.
+    /// It is updated when the pass is done, so its observers may change the layout.
+    pub fn get_error(&self) -> SharedProperty<Option<ConstraintError>> {
+        self.properties.lock().unwrap().error.clone()
+    }
+
+    pub fn unwrap(self) -> Item {
+        self.item
+    }
+}
+
+/// Place the measured children at the start of the layout along one axis, for constraints that could not be resolved.
+fn start_positions(children: &[&Item], horizontal: bool, parent_start: f32) -> (Vec<f32>, Vec<f32>) {
+    children.iter().map(|child| {
+        let layout_params = child.get_layout_params();
+        if horizontal {
+            (parent_start + layout_params.margin_start, layout_params.width)
+        } else {
+            (parent_start + layout_params.margin_top, layout_params.height)
+        }
+    }).unzip()
+}
+
+/// Resolve the positions and the sizes of the measured children along one axis.
+fn solve(children: &[&Item], tags: &[String], chains: &[(Vec<String>, ChainStyle)], horizontal: bool, parent_start: f32, parent_end: f32) -> Result<(Vec<f32>, Vec<f32>), ConstraintError> {
+    let mut indices = HashMap::new();
+    tags.iter().enumerate().for_each(|(index, tag)| {
+        if !tag.is_empty() {
+            indices.entry(tag.as_str()).or_insert(index);
+        }
+    });
+    let target = |tag: &str| -> Result<Target, ConstraintError> {
+        if tag == PARENT {
+            Ok(Target::Parent)
+        } else {
+            indices.get(tag).map(|index| Target::Child(*index)).ok_or_else(|| ConstraintError::UnknownTag(tag.to_string()))
+        }
+    };
+    let anchor = |child: &Item, anchor: ConstraintAnchor| -> Result<Option<Anchor>, ConstraintError> {
+        match child.get_constraint(anchor) {
+            Some((tag, target_anchor)) => Ok(Some(Anchor {
+                target: target(&tag)?,
+                side: match target_anchor {
+                    ConstraintAnchor::Start | ConstraintAnchor::Top => Side::Start,
+                    ConstraintAnchor::End | ConstraintAnchor::Bottom => Side::End,
+                },
+            })),
+            None => Ok(None),
+        }
+    };
+
+    let mut nodes = children.iter().map(|child| {
+        let layout_params = child.get_layout_params();
+        Ok(if horizontal {
+            AxisNode {
+                start: anchor(child, ConstraintAnchor::Start)?,
+                end: anchor(child, ConstraintAnchor::End)?,
+                baseline: None,
+                size: layout_params.width,
+                baseline_offset: 0.0,
+                margin_start: layout_params.margin_start,
+                margin_end: layout_params.margin_end,
+                bias: child.get_horizontal_bias(),
+                fill: child.get_width().get() == Size::Fill,
+                chain: None,
+            }
+        } else {
+            AxisNode {
+                start: anchor(child, ConstraintAnchor::Top)?,
+                end: anchor(child, ConstraintAnchor::Bottom)?,
+                baseline: child.get_baseline_constraint().map(|tag| target(&tag)).transpose()?,
+                size: layout_params.height,
+                baseline_offset: child.get_baseline().unwrap_or(layout_params.height),
+                margin_start: layout_params.margin_top,
+                margin_end: layout_params.margin_bottom,
+                bias: child.get_vertical_bias(),
+                fill: child.get_height().get() == Size::Fill,
+                chain: None,
+            }
+        })
+    }).collect::<Result<Vec<AxisNode>, ConstraintError>>()?;
+
+    let chains = chains.iter().filter(|(chain_tags, _)| !chain_tags.is_empty()).enumerate().map(|(chain_index, (chain_tags, style))| {
+        let members = chain_tags.iter().map(|tag| match target(tag)? {
+            Target::Child(index) => {
+                if nodes[index].chain.is_some() {
+                    return Err(ConstraintError::MultipleChains(tag.clone()));
+                }
+                nodes[index].chain = Some(chain_index);
+                Ok(index)
+            }
+            Target::Parent => Err(ConstraintError::UnknownTag(tag.clone())),
+        }).collect::<Result<Vec<usize>, ConstraintError>>()?;
+        Ok(Chain { members, style: *style })
+    }).collect::<Result<Vec<Chain>, ConstraintError>>()?;
+
+    let positions = AxisSolver::solve(&mut nodes, &chains, tags, parent_start, parent_end)?;
+    Ok((positions, nodes.iter().map(|node| node.size).collect()))
+}
@@ -1,14 +1,16 @@
+use std::collections::HashMap;
 use std::ops::Range;
 use std::sync::{Arc, Mutex};
 
 use crate::app::SharedApp;
-use crate::property::{Gettable, SharedProperty, Size};
-use crate::ui::{Item, ItemEvent, LayoutParams, LogicalX, measure_child, MeasureMode};
-use crate::ui::additional_property::BaseLine;
+use crate::layout::{children_property, init_child_margins};
+use crate::property::{Gettable, Observable, SharedProperty, Size};
+use crate::ui::{clamp_size, Item, ItemEvent, LayoutDirection, LayoutParams, LogicalX, measure_child, MeasureMode};
+use crate::ui::additional_property::{BaseLine, FlexItem};
 
 #[macro_export]
 macro_rules! flex_layout {
-    ($($child:expr)+) => {
+    ($($child:expr),+ $(,)?) => {
         {
             let children = vec![$($child),*];
             let app = children.first().unwrap().get_app().clone();
@@ -19,7 +21,7 @@ macro_rules! flex_layout {
 
 /// When the layout direction is left to right, the Start is the left position of the layout, and the End is the right position of the layout.
 /// When the layout direction is right to left, the Start is the right position of the layout, and the End is the left position of the layout.
-#[derive(Clone, Copy, PartialEq)]
+#[derive(Clone, Copy, Debug, PartialEq)]
 pub enum MainAxis {
     StartToEnd,
     EndToStart,
@@ -27,21 +29,27 @@ pub enum MainAxis {
     BottomToTop,
 }
 
+impl MainAxis {
+    fn is_horizontal(&self) -> bool {
+        matches!(self, MainAxis::StartToEnd | MainAxis::EndToStart)
+    }
+}
+
 /// The Forward is start to end, or top to bottom, and the Reverse is end to start, or bottom to top.
-#[derive(Clone, Copy, PartialEq)]
+#[derive(Clone, Copy, Debug, PartialEq)]
 pub enum CrossAxis {
     Forward,
     Reverse,
 }
 
-#[derive(Clone, Copy, PartialEq)]
+#[derive(Clone, Copy, Debug, PartialEq)]
 pub enum FlexWrap {
     NoWrap,
     Wrap,
 }
 
 /// The Start is the start position of the main axis, and the End is the end position of the main axis.
-#[derive(Clone, Copy, PartialEq)]
+#[derive(Clone, Copy, Debug, PartialEq)]
 pub enum FlexAlign {
     Start,
     End,
@@ -52,7 +60,8 @@ pub enum FlexAlign {
 }
 
 /// The Start is the start position of the cross axis, and the End is the end position of the cross axis.
-#[derive(Clone, Copy, PartialEq)]
+/// Baseline only applies when the main axis is horizontal and the cross axis is forward, otherwise it behaves like Start.
+#[derive(Clone, Copy, Debug, PartialEq)]
 pub enum ItemAlign {
     Start,
     End,
@@ -61,14 +70,27 @@ pub enum ItemAlign {
     Stretch,
 }
 
+/// A line of children along the main axis. Sizes include the margins of the children.
 #[derive(Clone, Debug)]
 struct Row {
     range: Range<usize>,
-    row_min_width: f32,
-    row_max_height: f32,
+    main_size: f32,
+    cross_size: f32,
+    /// The distance from the cross start of the row to the baseline.
     baseline: f32,
 }
 
+/// The size of a child before it was grown or shrunk, and the measure modes it was measured with.
+#[derive(Clone, Copy, Debug)]
+struct FlexBase {
+    width_measure_mode: MeasureMode,
+    height_measure_mode: MeasureMode,
+    generation: usize,
+    /// The main size, margins excluded.
+    size: f32,
+    margins: f32,
+}
+
 struct FlexLayoutProperties {
     main_axis: SharedProperty<MainAxis>,
     cross_axis: SharedProperty<CrossAxis>,
@@ -76,11 +98,14 @@ struct FlexLayoutProperties {
     flex_wrap: SharedProperty<FlexWrap>,
     /// The alignment of the child elements along the main axis.
     justify_content: SharedProperty<FlexAlign>,
-    /// The alignment of the child elements along the cross axis when the layout is not wrapped.
+    /// The alignment of the child elements along the cross axis inside their row.
     align_items: SharedProperty<ItemAlign>,
-    /// The alignment of the child elements along the cross axis when the layout is wrapped.
+    /// The alignment of the rows along the cross axis.
     align_content: SharedProperty<FlexAlign>,
     rows: Vec<Row>,
+    /// The base sizes of the last measure by child id. A grown or shrunk child keeps the measure modes of its final size,
+    /// so it is only measured for its base size again if it changed.
+    bases: HashMap<usize, FlexBase>,
 }
 
 impl Default for FlexLayoutProperties {
@@ -93,6 +118,7 @@ impl Default for FlexLayoutProperties {
             align_items: SharedProperty::from_value(ItemAlign::Start),
             align_content: SharedProperty::from_value(FlexAlign::Start),
             rows: Vec::new(),
+            bases: HashMap::new(),
         }
     }
 }
@@ -109,129 +135,238 @@ impl FlexLayout {
         let mut item = Item::new(
             app,
             ItemEvent::default()
-                .set_on_measure({// Measure the layout, get the expected width and height of the layout and its children
+                .set_measure_event({// Measure the layout, get the expected width and height of the layout and its children
                     let properties = properties.clone();
                     move |item, width_measure_mode, height_measure_mode| {
                         let mut properties = properties.lock().unwrap();
-
-                        let mut layout_params = item.get_layout_params().clone();
-                        layout_params.init_from_item(item);
-
-                        let max_width = layout_params.max_width;
-                        let max_height = layout_params.max_height;
-                        let min_width = layout_params.min_width;
-                        let min_height = layout_params.min_height;
-
-                        let mut measure_width = 0.0_f32;
-                        let mut measure_height = 0.0_f32;
-
                         let flex_wrap = properties.flex_wrap.get();
                         let main_axis = properties.main_axis.get();
                         let cross_axis = properties.cross_axis.get();
-                        let justify_content = properties.justify_content.get();
                         let align_items = properties.align_items.get();
-                        let align_content = properties.align_content.get();
 
-                        match width_measure_mode {
-                            MeasureMode::Specified(width) => {
-                                match height_measure_mode {
-                                    MeasureMode::Specified(height) => {
-                                        measure_width = width.clamp(min_width, max_width);
-                                        measure_height = height.clamp(min_height, max_height);
-
-                                        properties.rows.clear();
-
-                                        if item.get_children().len() > 0 {
-                                            let row = Row {
-                                                range: 0..0,
-                                                row_min_width: 0.0,
-                                                row_max_height: 0.0,
-                                                baseline: 0.0,
-                                            };
-                                            properties.rows.push(row);
-                                        }
-
-                                        item.get_children_mut().iter_mut().for_each(|mut child| {
-                                            match main_axis {
-                                                // The main axis is the horizontal direction
-                                                MainAxis::StartToEnd | MainAxis::EndToStart => {
-                                                    let (child_width_measure_mode, child_height_measure_mode) =
-                                                        measure_child(child, &layout_params, width_measure_mode, height_measure_mode);
-                                                    child.measure(child_width_measure_mode, child_height_measure_mode);
-
-                                                    let mut new_row = None;
-                                                    if let Some(row) = properties.rows.last_mut() {
-                                                        let new_width = row.row_min_width + child.get_layout_params().width + child.get_layout_params().margin_start + child.get_layout_params().margin_end;
-                                                        if new_width > (measure_width - layout_params.padding_start - layout_params.padding_end) && flex_wrap == FlexWrap::Wrap {
-                                                            new_row = Some(Row {
-                                                                range: row.range.end..row.range.end + 1,
-                                                                row_min_width: child.get_layout_params().width + child.get_layout_params().margin_start + child.get_layout_params().margin_end,
-                                                                row_max_height: child.get_layout_params().height + child.get_layout_params().margin_top + child.get_layout_params().margin_bottom,
-                                                                baseline: if align_items == ItemAlign::Baseline && child.get_baseline() != None {
-                                                                    child.get_baseline().unwrap() + child.get_layout_params().margin_top
-                                                                } else {
-                                                                    0.0
-                                                                },
-                                                            });
-                                                        } else {
-                                                            row.range.end += 1;
-                                                            row.row_min_width = new_width;
-                                                            if align_items == ItemAlign::Baseline && child.get_baseline() != None {
-                                                                let original_baseline = row.baseline;
-                                                                let original_above_baseline = row.row_max_height - original_baseline;
-                                                                let item_baseline = child.get_baseline().unwrap() + child.get_layout_params().margin_top;
-                                                                let item_above_baseline = child.get_layout_params().height + child.get_layout_params().margin_top + child.get_layout_params().margin_bottom - item_baseline;
-                                                                let new_baseline = original_baseline.max(item_baseline);
-                                                                let new_above_baseline = original_above_baseline.max(item_above_baseline);
-                                                                row.baseline = new_baseline;
-                                                                row.row_max_height = row.row_max_height.max(new_above_baseline + new_baseline);
-                                                            } else {
-                                                                row.row_max_height = row.row_max_height.max(child.get_layout_params().height + child.get_layout_params().margin_top + child.get_layout_params().margin_bottom);
-                                                            }
-                                                        }
-                                                    }
-                                                    if let Some(new_row) = new_row {
-                                                        properties.rows.push(new_row);
-                                                    }
-                                                }
-                                                // The main axis is the vertical direction
-                                                MainAxis::TopToBottom | MainAxis::BottomToTop => {}
-                                            }
-                                        });
-
-                                        if flex_wrap == FlexWrap::NoWrap && properties.rows.len() == 1 {
-                                            properties.rows.first_mut().unwrap().
-                                                row_max_height = measure_height;
-                                        }
-
-                                        if align_items == ItemAlign::Stretch {
-                                            let average_height = (measure_height - layout_params.padding_top - layout_params.padding_bottom) / properties.rows.len() as f32;
-                                            properties.rows.iter_mut().for_each(|row| {
-                                                row.row_max_height = average_height;
-                                                for i in row.range.clone() {
-                                                    if let Some(child) = item.get_children_mut().get_mut(i) {
-                                                        let mut child_layout_params = child.get_layout_params().clone();
-                                                        child_layout_params.height = average_height - child_layout_params.margin_top - child_layout_params.margin_bottom;
-                                                        child.set_layout_params(&child_layout_params);
-                                                    }
-                                                }
-                                            });
-                                        }
-                                    }
+                        let mut layout_params = item.get_layout_params().clone();
+                        layout_params.init_from_item(item);
 
-                                    MeasureMode::Unspecified(height) => {}
+                        let horizontal_padding = layout_params.padding_start + layout_params.padding_end;
+                        let vertical_padding = layout_params.padding_top + layout_params.padding_bottom;
+                        let (main_measure_mode, cross_measure_mode, main_padding, cross_padding) = if main_axis.is_horizontal() {
+                            (width_measure_mode, height_measure_mode, horizontal_padding, vertical_padding)
+                        } else {
+                            (height_measure_mode, width_measure_mode, vertical_padding, horizontal_padding)
+                        };
+                        let available_main = main_measure_mode.value() - main_padding;
+                        let baseline_axis = main_axis.is_horizontal() && cross_axis == CrossAxis::Forward;
+
+                        let generation = item.get_app().layout_generation();
+                        let children = item.get_children();
+                        let mut children = children.lock();
+
+                        // Without wrapping, children that fill the main axis share the space left by the others.
+                        let mut fill_children = Vec::new();
+                        let mut occupied_main = 0.0_f32;
+                        // The measure modes used along the main axis, children are measured again with them when they are stretched.
+                        let mut main_measure_modes = vec![main_measure_mode; children.len()];
+                        let mut bases: Vec<Option<FlexBase>> = vec![None; children.len()];
+                        children.iter_mut().enumerate().for_each(|(index, child)| {
+                            init_child_margins(child);
+                            if flex_wrap == FlexWrap::NoWrap && is_main_fill(child, main_axis) {
+                                fill_children.push(index);
+                                return;
+                            }
+                            let (mut child_width_measure_mode, mut child_height_measure_mode) = measure_child(child, &layout_params, width_measure_mode, height_measure_mode);
+                            if let Some(flex_basis) = child.get_flex_basis() {
+                                if main_axis.is_horizontal() {
+                                    child_width_measure_mode = MeasureMode::Specified(flex_basis);
+                                } else {
+                                    child_height_measure_mode = MeasureMode::Specified(flex_basis);
                                 }
                             }
-                            MeasureMode::Unspecified(width) => {
-                                match height_measure_mode {
-                                    MeasureMode::Specified(height) => {}
-                                    MeasureMode::Unspecified(height) => {}
+                            let base = match properties.bases.get(&child.get_id()) {
+                                Some(base) if base.width_measure_mode == child_width_measure_mode
+                                    && base.height_measure_mode == child_height_measure_mode
+                                    && base.generation == generation
+                                    && !child.get_layout_node().is_measure_dirty() => *base,
+                                _ => {
+                                    child.measure(child_width_measure_mode, child_height_measure_mode);
+                                    let child_layout_params = child.get_layout_params();
+                                    let size = if main_axis.is_horizontal() { child_layout_params.width } else { child_layout_params.height };
+                                    FlexBase {
+                                        width_measure_mode: child_width_measure_mode,
+                                        height_measure_mode: child_height_measure_mode,
+                                        generation,
+                                        size,
+                                        margins: main_size(child_layout_params, main_axis) - size,
+                                    }
+                                }
+                            };
+                            main_measure_modes[index] = if main_axis.is_horizontal() { child_width_measure_mode } else { child_height_measure_mode };
+                            occupied_main += base.size + base.margins;
+                            bases[index] = Some(base);
+                        });
+                        properties.bases = children.iter().zip(bases.iter())
+                            .filter_map(|(child, base)| base.map(|base| (child.get_id(), base)))
+                            .collect();
+
+                        if !fill_children.is_empty() {
+                            let fill_size = (available_main - occupied_main).max(0.0) / fill_children.len() as f32;
+                            let main_measure_mode = MeasureMode::Specified(fill_size + main_padding);
+                            fill_children.iter().for_each(|index| {
+                                let child = children.get_mut(*index).unwrap();
+                                let (child_width_measure_mode, child_height_measure_mode) = if main_axis.is_horizontal() {
+                                    measure_child(child, &layout_params, main_measure_mode, height_measure_mode)
+                                } else {
+                                    measure_child(child, &layout_params, width_measure_mode, main_measure_mode)
+                                };
+                                child.measure(child_width_measure_mode, child_height_measure_mode);
+                                main_measure_modes[*index] = if main_axis.is_horizontal() { child_width_measure_mode } else { child_height_measure_mode };
+                            });
+                        }
+
+                        // Break the children into rows by their hypothetical sizes.
+                        let mut rows: Vec<Row> = Vec::new();
+                        children.iter().enumerate().for_each(|(index, child)| {
+                            let child_main = match bases[index] {
+                                Some(base) => base.size + base.margins,
+                                None => main_size(child.get_layout_params(), main_axis),
+                            };
+                            let new_row = match rows.last() {
+                                Some(row) => flex_wrap == FlexWrap::Wrap && !row.range.is_empty() && row.main_size + child_main > available_main,
+                                None => true,
+                            };
+                            if new_row {
+                                rows.push(Row {
+                                    range: index..index,
+                                    main_size: 0.0,
+                                    cross_size: 0.0,
+                                    baseline: 0.0,
+                                });
+                            }
+                            let row = rows.last_mut().unwrap();
+                            row.range.end = index + 1;
+                            row.main_size += child_main;
+                        });
+
+                        // Grow or shrink the children to the free space of their rows.
+                        rows.iter_mut().for_each(|row| {
+                            let indices: Vec<usize> = row.range.clone().filter(|index| !fill_children.contains(index)).collect();
+                            let factors: Vec<FlexFactors> = indices.iter().map(|index| {
+                                let child = children.get(*index).unwrap();
+                                let base = bases[*index].unwrap();
+                                let (min, max) = if main_axis.is_horizontal() {
+                                    (child.get_min_width().get(), child.get_max_width().get())
+                                } else {
+                                    (child.get_min_height().get(), child.get_max_height().get())
+                                };
+                                FlexFactors {
+                                    base: base.size,
+                                    margins: base.margins,
+                                    grow: child.get_flex_grow(),
+                                    shrink: child.get_flex_shrink(),
+                                    min,
+                                    max,
+                                }
+                            }).collect();
+                            // Without a bounded main axis there is no free space to share.
+                            let sizes = if available_main.is_finite() {
+                                let fill_main: f32 = row.range.clone().filter(|index| fill_children.contains(index))
+                                    .map(|index| main_size(children.get(index).unwrap().get_layout_params(), main_axis))
+                                    .sum();
+                                flex_sizes(&factors, available_main - fill_main)
+                            } else {
+                                factors.iter().map(|factors| factors.base).collect()
+                            };
+
+                            // Every child is measured for its final size, which hits the measure cache if it did not change
+                            // since the last layout, and measures the descendants that were invalidated below it.
+                            indices.iter().zip(sizes.iter()).for_each(|(index, size)| {
+                                let base = bases[*index].unwrap();
+                                let child = children.get_mut(*index).unwrap();
+                                let (child_width_measure_mode, child_height_measure_mode) = if *size == base.size {
+                                    (base.width_measure_mode, base.height_measure_mode)
+                                } else if main_axis.is_horizontal() {
+                                    let (_, child_height_measure_mode) = measure_child(child, &layout_params, width_measure_mode, height_measure_mode);
+                                    (MeasureMode::Specified(*size), child_height_measure_mode)
+                                } else {
+                                    let (child_width_measure_mode, _) = measure_child(child, &layout_params, width_measure_mode, height_measure_mode);
+                                    (child_width_measure_mode, MeasureMode::Specified(*size))
+                                };
+                                child.measure(child_width_measure_mode, child_height_measure_mode);
+                                main_measure_modes[*index] = if main_axis.is_horizontal() { child_width_measure_mode } else { child_height_measure_mode };
+                            });
+                            row.main_size = row.range.clone().map(|index| main_size(children.get(index).unwrap().get_layout_params(), main_axis)).sum();
+                        });
+
+                        rows.iter_mut().for_each(|row| {
+                            // The cross size of the row above and below the baseline.
+                            let mut row_above_baseline = 0.0_f32;
+                            let mut row_below_baseline = 0.0_f32;
+                            for index in row.range.clone() {
+                                let child = children.get(index).unwrap();
+                                let child_layout_params = child.get_layout_params();
+                                let child_cross = cross_size(child_layout_params, main_axis);
+                                let baseline_enabled = baseline_axis && child.get_align_self().unwrap_or(align_items) == ItemAlign::Baseline;
+                                match child.get_baseline() {
+                                    Some(baseline) if baseline_enabled => {
+                                        let above = child_layout_params.margin_top + baseline;
+                                        row_above_baseline = row_above_baseline.max(above);
+                                        row_below_baseline = row_below_baseline.max(child_cross - above);
+                                        row.baseline = row_above_baseline;
+                                        row.cross_size = row.cross_size.max(row_above_baseline + row_below_baseline);
+                                    }
+                                    _ => {
+                                        row.cross_size = row.cross_size.max(child_cross);
+                                    }
                                 }
                             }
+                        });
+
+                        let content_main = rows.iter().fold(0.0_f32, |max, row| max.max(row.main_size));
+                        let content_cross = rows.iter().fold(0.0, |sum, row| sum + row.cross_size);
+
+                        let measured_main = match main_measure_mode {
+                            MeasureMode::Specified(size) => size,
+                            MeasureMode::Unspecified(size) => size.min(content_main + main_padding),
+                        };
+                        let measured_cross = match cross_measure_mode {
+                            MeasureMode::Specified(size) => size,
+                            MeasureMode::Unspecified(size) => size.min(content_cross + cross_padding),
+                        };
+                        if main_axis.is_horizontal() {
+                            layout_params.width = clamp_size(measured_main, layout_params.min_width, layout_params.max_width);
+                            layout_params.height = clamp_size(measured_cross, layout_params.min_height, layout_params.max_height);
+                        } else {
+                            layout_params.width = clamp_size(measured_cross, layout_params.min_width, layout_params.max_width);
+                            layout_params.height = clamp_size(measured_main, layout_params.min_height, layout_params.max_height);
                         }
 
-                        layout_params.width = measure_width;
-                        layout_params.height = measure_height;
+                        // A single row without wrapping spans the whole cross axis.
+                        if flex_wrap == FlexWrap::NoWrap {
+                            if let Some(row) = rows.first_mut() {
+                                row.cross_size = if main_axis.is_horizontal() {
+                                    layout_params.height - vertical_padding
+                                } else {
+                                    layout_params.width - horizontal_padding
+                                };
+                            }
+                        }
+
+                        rows.iter().for_each(|row| {
+                            for index in row.range.clone() {
+                                let child = children.get_mut(index).unwrap();
+                                if child.get_align_self().unwrap_or(align_items) == ItemAlign::Stretch {
+                                    let child_layout_params = child.get_layout_params().clone();
+                                    let stretched = MeasureMode::Specified(row.cross_size - (cross_size(&child_layout_params, main_axis) - cross_content_size(&child_layout_params, main_axis)));
+                                    if main_axis.is_horizontal() {
+                                        child.measure(main_measure_modes[index], stretched);
+                                    } else {
+                                        child.measure(stretched, main_measure_modes[index]);
+                                    }
+                                }
+                            }
+                        });
+                        drop(children);
+
+                        properties.rows = rows;
 
                         if let Some(background) = item.get_background().lock().as_mut() {
                             background.measure(MeasureMode::Specified(layout_params.width), MeasureMode::Specified(layout_params.height));
@@ -244,22 +379,21 @@ impl FlexLayout {
                         item.set_layout_params(&layout_params);
                     }
                 })
-                .set_on_layout({
+                .set_layout_event({
                     let properties = properties.clone();
                     move |item, x, y| {
                         let properties = properties.lock().unwrap();
-                        let flex_wrap = properties.flex_wrap.get();
                         let main_axis = properties.main_axis.get();
                         let cross_axis = properties.cross_axis.get();
                         let justify_content = properties.justify_content.get();
                         let align_items = properties.align_items.get();
                         let align_content = properties.align_content.get();
+                        let direction = item.get_layout_direction().get();
 
                         let mut layout_params = item.get_layout_params().clone();
-                        let mut width = layout_params.width;
-                        let mut height = layout_params.height;
-                        layout_params.x = x;
-                        layout_params.y = y;
+                        layout_params.relative_x = x;
+                        layout_params.relative_y = y;
+                        item.set_layout_params(&layout_params);
 
                         if let Some(background) = item.get_background().lock().as_mut() {
                             background.layout(x, y);
@@ -269,860 +403,57 @@ impl FlexLayout {
                             foreground.layout(x, y);
                         }
 
-                        item.set_layout_params(&layout_params);
-
-                        let x = LogicalX::new(item.get_layout_direction().get(), x, x, layout_params.width);
-
-                        let children_len = item.get_children().len();
-
-                        let total_row_height = properties.rows.iter().fold(0.0, |sum, row| sum + row.row_max_height);
-                        match main_axis {
-                            MainAxis::StartToEnd => {
-                                let mut row_y = match cross_axis {
-                                    CrossAxis::Forward => {
-                                        match align_content {
-                                            FlexAlign::Start => {
-                                                y + layout_params.padding_top
-                                            }
-                                            FlexAlign::End => {
-                                                y + height - total_row_height - layout_params.padding_bottom
-                                            }
-                                            FlexAlign::Center => {
-                                                y + (height - total_row_height) / 2.0
-                                            }
-                                            FlexAlign::SpaceBetween => {
-                                                if properties.rows.len() == 1 {
-                                                    y + (height - total_row_height) / 2.0
-                                                } else {
-                                                    y + layout_params.padding_top
-                                                }
-                                            }
-                                            FlexAlign::SpaceAround => {
-                                                if properties.rows.len() == 1 {
-                                                    y + (height - total_row_height) / 2.0
-                                                } else {
-                                                    let mut space = (height - total_row_height - layout_params.padding_top - layout_params.padding_bottom) / properties.rows.len() as f32;
-                                                    if space < 0.0 {
-                                                        space = 0.0;
-                                                    }
-                                                    y + space / 2.0
-                                                }
-                                            }
-                                            FlexAlign::SpaceEvenly => {
-                                                if properties.rows.len() == 1 {
-                                                    y + (height - total_row_height) / 2.0
-                                                } else {
-                                                    let mut space = (height - total_row_height - layout_params.padding_top - layout_params.padding_bottom) / (properties.rows.len() + 1) as f32;
-                                                    if space < 0.0 {
-                                                        space = 0.0;
-                                                    }
-                                                    y + space
-                                                }
-                                            }
-                                        }
-                                    }
-                                    CrossAxis::Reverse => {
-                                        match align_content {
-                                            FlexAlign::Start => {
-                                                y + height - layout_params.padding_bottom
-                                            }
-                                            FlexAlign::End => {
-                                                y + layout_params.padding_top + total_row_height
-                                            }
-                                            FlexAlign::Center => {
-                                                y + height - (height - total_row_height) / 2.0
-                                            }
-                                            FlexAlign::SpaceBetween => {
-                                                if properties.rows.len() == 1 {
-                                                    y + (height - total_row_height) / 2.0
-                                                } else {
-                                                    y + layout_params.padding_top
-                                                }
-                                            }
-                                            FlexAlign::SpaceAround => {
-                                                if properties.rows.len() == 1 {
-                                                    y + (height - total_row_height) / 2.0
-                                                } else {
-                                                    let mut space = (height - total_row_height - layout_params.padding_top - layout_params.padding_bottom) / properties.rows.len() as f32;
-                                                    if space < 0.0 {
-                                                        space = 0.0;
-                                                    }
-                                                    y + space / 2.0
-                                                }
-                                            }
-                                            FlexAlign::SpaceEvenly => {
-                                                if properties.rows.len() == 1 {
-                                                    y + (height - total_row_height) / 2.0
-                                                } else {
-                                                    let mut space = (height - total_row_height - layout_params.padding_top - layout_params.padding_bottom) / (properties.rows.len() + 1) as f32;
-                                                    if space < 0.0 {
-                                                        space = 0.0;
-                                                    }
-                                                    y + space
-                                                }
-                                            }
-                                        }
-                                    }
+                        let (content_main, content_cross) = if main_axis.is_horizontal() {
+                            (layout_params.width - layout_params.padding_start - layout_params.padding_end, layout_params.height - layout_params.padding_top - layout_params.padding_bottom)
+                        } else {
+                            (layout_params.height - layout_params.padding_top - layout_params.padding_bottom, layout_params.width - layout_params.padding_start - layout_params.padding_end)
+                        };
+                        let baseline_axis = main_axis.is_horizontal() && cross_axis == CrossAxis::Forward;
+
+                        let total_row_cross = properties.rows.iter().fold(0.0, |sum, row| sum + row.cross_size);
+                        let (mut row_cross, row_space) = distribute(align_content, content_cross - total_row_cross, properties.rows.len());
+
+                        let children = item.get_children();
+                        let mut children = children.lock();
+
+                        properties.rows.iter().for_each(|row| {
+                            let (mut child_main, child_space) = distribute(justify_content, content_main - row.main_size, row.range.len());
+                            for index in row.range.clone() {
+                                let child = children.get_mut(index).unwrap();
+                                let child_layout_params = child.get_layout_params().clone();
+                                let (main_margin_start, _) = main_margins(&child_layout_params, main_axis);
+                                let (cross_margin_start, cross_margin_end) = cross_margins(&child_layout_params, main_axis, cross_axis);
+                                let child_cross_size = cross_content_size(&child_layout_params, main_axis);
+
+                                let cross_offset = match child.get_align_self().unwrap_or(align_items) {
+                                    ItemAlign::Start | ItemAlign::Stretch => cross_margin_start,
+                                    ItemAlign::End => row.cross_size - cross_margin_end - child_cross_size,
+                                    ItemAlign::Center => (row.cross_size - cross_size(&child_layout_params, main_axis)) / 2.0 + cross_margin_start,
+                                    ItemAlign::Baseline => match child.get_baseline() {
+                                        Some(baseline) if baseline_axis => row.baseline - baseline,
+                                        _ => cross_margin_start,
+                                    },
                                 };
-                                properties.rows.iter().for_each(|row| {
-                                    let mut item_x = match justify_content {
-                                        FlexAlign::Start => {
-                                            x + layout_params.padding_start
-                                        }
-                                        FlexAlign::End => {
-                                            x + width - row.row_min_width - layout_params.padding_end
-                                        }
-                                        FlexAlign::Center => {
-                                            x + (width - row.row_min_width) / 2.0
-                                        }
-                                        FlexAlign::SpaceBetween => {
-                                            if row.range.len() == 1 {
-                                                x + (width - row.row_min_width) / 2.0
-                                            } else {
-                                                x + layout_params.padding_start
-                                            }
-                                        }
-                                        FlexAlign::SpaceAround => {
-                                            if row.range.len() == 1 {
-                                                x + (width - row.row_min_width) / 2.0
-                                            } else {
-                                                let mut space = (width - row.row_min_width - layout_params.padding_start - layout_params.padding_end) / row.range.len() as f32;
-                                                if space < 0.0 {
-                                                    space = 0.0;
-                                                }
-                                                x + space / 2.0
-                                            }
-                                        }
-                                        FlexAlign::SpaceEvenly => {
-                                            if row.range.len() == 1 {
-                                                x + (width - row.row_min_width) / 2.0
-                                            } else {
-                                                let mut space = (width - row.row_min_width - layout_params.padding_start - layout_params.padding_end) / (row.range.len() + 1) as f32;
-                                                if space < 0.0 {
-                                                    space = 0.0;
-                                                }
-                                                x + space
-                                            }
-                                        }
-                                    };
-                                    for i in row.range.clone() {
-                                        if let Some(child_item) = item.get_children_mut().get_mut(i) {
-                                            let child_layout_params = child_item.get_layout_params().clone();
-                                            let child_y = match cross_axis {
-                                                CrossAxis::Forward => {
-                                                    match align_items {
-                                                        ItemAlign::Start | ItemAlign::Stretch => {
-                                                            row_y + child_layout_params.margin_top
-                                                        }
-                                                        ItemAlign::End => {
-                                                            row_y + row.row_max_height - child_layout_params.margin_bottom - child_layout_params.height
-                                                        }
-                                                        ItemAlign::Center => {
-                                                            row_y + (row.row_max_height - child_layout_params.height) / 2.0
-                                                        }
-                                                        ItemAlign::Baseline => {
-                                                            if let Some(baseline) = child_item.get_baseline() {
-                                                                row_y + row.baseline - baseline
-                                                            } else {
-                                                                row_y + child_layout_params.margin_top
-                                                            }
-                                                        }
-                                                    }
-                                                }
-                                                CrossAxis::Reverse => {
-                                                    match align_items {
-                                                        ItemAlign::Start | ItemAlign::Stretch => {
-                                                            row_y - child_layout_params.margin_bottom
-                                                        }
-                                                        ItemAlign::End => {
-                                                            row_y - row.row_max_height + child_layout_params.margin_top + child_layout_params.height
-                                                        }
-                                                        ItemAlign::Center => {
-                                                            row_y - (row.row_max_height - child_layout_params.height) / 2.0
-                                                        }
-                                                        ItemAlign::Baseline => {
-                                                            if let Some(baseline) = child_item.get_baseline() {
-                                                                row_y + row.baseline - baseline
-                                                            } else {
-                                                                row_y + child_layout_params.margin_top
-                                                            }
-                                                        }
-                                                    }
-                                                }
-                                            };
-
-                                            child_item.layout(
-                                                item_x.physical_value(child_layout_params.width),
-                                                match cross_axis {
-                                                    CrossAxis::Forward => {
-                                                        child_y
-                                                    }
-                                                    CrossAxis::Reverse => {
-                                                        child_y - child_layout_params.height
-                                                    }
-                                                },
-                                            );
-
-                                            match justify_content {
-                                                FlexAlign::Start | FlexAlign::End | FlexAlign::Center => {
-                                                    item_x += child_layout_params.width + child_layout_params.margin_start + child_layout_params.margin_end;
-                                                }
-                                                FlexAlign::SpaceBetween => {
-                                                    let mut space = (width - row.row_min_width - layout_params.padding_start - layout_params.padding_end) / (row.range.len() - 1) as f32;
-                                                    if space < 0.0 {
-                                                        space = 0.0;
-                                                    }
-                                                    item_x += child_layout_params.width + child_layout_params.margin_start + child_layout_params.margin_end + space;
-                                                }
-                                                FlexAlign::SpaceAround => {
-                                                    let mut space = (width - row.row_min_width - layout_params.padding_start - layout_params.padding_end) / row.range.len() as f32;
-                                                    if space < 0.0 {
-                                                        space = 0.0;
-                                                    }
-                                                    item_x += child_layout_params.width + child_layout_params.margin_start + child_layout_params.margin_end + space;
-                                                }
-                                                FlexAlign::SpaceEvenly => {
-                                                    let mut space = (width - row.row_min_width - layout_params.padding_start - layout_params.padding_end) / (row.range.len() + 1) as f32;
-                                                    if space < 0.0 {
-                                                        space = 0.0;
-                                                    }
-                                                    item_x += child_layout_params.width + child_layout_params.margin_start + child_layout_params.margin_end + space;
-                                                }
-                                            }
-                                        }
-                                    }
 
-                                    match cross_axis {
-                                        CrossAxis::Forward => {
-                                            match align_content {
-                                                FlexAlign::Start | FlexAlign::Center | FlexAlign::End => {
-                                                    row_y += row.row_max_height;
-                                                }
-                                                FlexAlign::SpaceBetween => {
-                                                    let mut space = (height - total_row_height - layout_params.padding_top - layout_params.padding_bottom) / (properties.rows.len() - 1) as f32;
-                                                    if space < 0.0 {
-                                                        space = 0.0;
-                                                    }
-                                                    row_y += row.row_max_height + space;
-                                                }
-                                                FlexAlign::SpaceAround => {
-                                                    let mut space = (height - total_row_height - layout_params.padding_top - layout_params.padding_bottom) / properties.rows.len() as f32;
-                                                    if space < 0.0 {
-                                                        space = 0.0;
-                                                    }
-                                                    row_y += row.row_max_height + space;
-                                                }
-                                                FlexAlign::SpaceEvenly => {
-                                                    let mut space = (height - total_row_height - layout_params.padding_top - layout_params.padding_bottom) / (properties.rows.len() + 1) as f32;
-                                                    if space < 0.0 {
-                                                        space = 0.0;
-                                                    }
-                                                    row_y += row.row_max_height + space;
-                                                }
-                                            }
-                                        }
-                                        CrossAxis::Reverse => {
-                                            match align_content {
-                                                FlexAlign::Start | FlexAlign::Center | FlexAlign::End => {
-                                                    row_y -= row.row_max_height;
-                                                }
-                                                FlexAlign::SpaceBetween => {
-                                                    let mut space = (height - total_row_height - layout_params.padding_top - layout_params.padding_bottom) / (properties.rows.len() - 1) as f32;
-                                                    if space < 0.0 {
-                                                        space = 0.0;
-                                                    }
-                                                    row_y += row.row_max_height + space;
-                                                }
-                                                FlexAlign::SpaceAround => {
-                                                    let mut space = (height - total_row_height - layout_params.padding_top - layout_params.padding_bottom) / properties.rows.len() as f32;
-                                                    if space < 0.0 {
-                                                        space = 0.0;
-                                                    }
-                                                    row_y += row.row_max_height + space;
-                                                }
-                                                FlexAlign::SpaceEvenly => {
-                                                    let mut space = (height - total_row_height - layout_params.padding_top - layout_params.padding_bottom) / (properties.rows.len() + 1) as f32;
-                                                    if space < 0.0 {
-                                                        space = 0.0;
-                                                    }
-                                                    row_y += row.row_max_height + space;
-                                                }
-                                            }
-                                        }
-                                    };
-                                });
+                                let (child_x, child_y) = child_position(
+                                    &layout_params,
+                                    &child_layout_params,
+                                    main_axis,
+                                    cross_axis,
+                                    direction,
+                                    child_main + main_margin_start,
+                                    row_cross + cross_offset,
+                                );
+                                child.layout(child_x, child_y);
+
+                                child_main += main_size(&child_layout_params, main_axis) + child_space;
                             }
-                            MainAxis::EndToStart => {
-                                let mut row_y = match align_content {
-                                    FlexAlign::Start => {
-                                        y + layout_params.padding_top
-                                    }
-                                    FlexAlign::End => {
-                                        y + height - total_row_height - layout_params.padding_bottom
-                                    }
-                                    FlexAlign::Center => {
-                                        y + (height - total_row_height) / 2.0
-                                    }
-                                    FlexAlign::SpaceBetween => {
-                                        if properties.rows.len() == 1 {
-                                            y + (height - total_row_height) / 2.0
-                                        } else {
-                                            y + layout_params.padding_top
-                                        }
-                                    }
-                                    FlexAlign::SpaceAround => {
-                                        if properties.rows.len() == 1 {
-                                            y + (height - total_row_height) / 2.0
-                                        } else {
-                                            let mut space = (height - total_row_height - layout_params.padding_top - layout_params.padding_bottom) / properties.rows.len() as f32;
-                                            if space < 0.0 {
-                                                space = 0.0;
-                                            }
-                                            y + space / 2.0
-                                        }
-                                    }
-                                    FlexAlign::SpaceEvenly => {
-                                        if properties.rows.len() == 1 {
-                                            y + (height - total_row_height) / 2.0
-                                        } else {
-                                            let mut space = (height - total_row_height - layout_params.padding_top - layout_params.padding_bottom) / (properties.rows.len() + 1) as f32;
-                                            if space < 0.0 {
-                                                space = 0.0;
-                                            }
-                                            y + space
-                                        }
-                                    }
-                                };
-                                properties.rows.iter().for_each(|row| {
-                                    let mut item_x = match justify_content {
-                                        FlexAlign::Start => {
-                                            x + width - layout_params.padding_end
-                                        }
-                                        FlexAlign::End => {
-                                            x + layout_params.padding_start + width
-                                        }
-                                        FlexAlign::Center => {
-                                            x + width - (width - row.row_min_width) / 2.0
-                                        }
-                                        FlexAlign::SpaceBetween => {
-                                            if row.range.len() == 1 {
-                                                x + (width - row.row_min_width) / 2.0
-                                            } else {
-                                                x + layout_params.padding_start
-                                            }
-                                        }
-                                        FlexAlign::SpaceAround => {
-                                            if row.range.len() == 1 {
-                                                x + (width - row.row_min_width) / 2.0
-                                            } else {
-                                                let mut space = (width - row.row_min_width - layout_params.padding_start - layout_params.padding_end) / row.range.len() as f32;
-                                                if space < 0.0 {
-                                                    space = 0.0;
-                                                }
-                                                x + space / 2.0
-                                            }
-                                        }
-                                        FlexAlign::SpaceEvenly => {
-                                            if row.range.len() == 1 {
-                                                x + (width - row.row_min_width) / 2.0
-                                            } else {
-                                                let mut space = (width - row.row_min_width - layout_params.padding_start - layout_params.padding_end) / (row.range.len() + 1) as f32;
-                                                if space < 0.0 {
-                                                    space = 0.0;
-                                                }
-                                                x + space
-                                            }
-                                        }
-                                    };
-                                    for i in row.range.clone() {
-                                        if let Some(child_item) = item.get_children_mut().get_mut(i) {
-                                            let child_layout_params = child_item.get_layout_params().clone();
-                                            let child_y = match cross_axis {
-                                                CrossAxis::Forward => {
-                                                    match align_items {
-                                                        ItemAlign::Start | ItemAlign::Stretch => {
-                                                            row_y + child_layout_params.margin_top
-                                                        }
-                                                        ItemAlign::End => {
-                                                            row_y + row.row_max_height - child_layout_params.margin_bottom - child_layout_params.height
-                                                        }
-                                                        ItemAlign::Center => {
-                                                            row_y + (row.row_max_height - child_layout_params.height) / 2.0
-                                                        }
-                                                        ItemAlign::Baseline => {
-                                                            if let Some(baseline) = child_item.get_baseline() {
-                                                                row_y + row.baseline - baseline
-                                                            } else {
-                                                                row_y + child_layout_params.margin_top
-                                                            }
-                                                        }
-                                                    }
-                                                }
-                                                CrossAxis::Reverse => {
-                                                    0.0
-                                                }
-                                            };
-
-                                            child_item.layout((item_x - child_layout_params.width).physical_value(child_layout_params.width), child_y);
-
-                                            match justify_content {
-                                                FlexAlign::Start | FlexAlign::End | FlexAlign::Center => {
-                                                    item_x -= child_layout_params.width + child_layout_params.margin_start + child_layout_params.margin_end;
-                                                }
-                                                FlexAlign::SpaceBetween => {
-                                                    let mut space = (width - row.row_min_width - layout_params.padding_start - layout_params.padding_end) / (row.range.len() - 1) as f32;
-                                                    if space < 0.0 {
-                                                        space = 0.0;
-                                                    }
-                                                    item_x += child_layout_params.width + child_layout_params.margin_start + child_layout_params.margin_end + space;
-                                                }
-                                                FlexAlign::SpaceAround => {
-                                                    let mut space = (width - row.row_min_width - layout_params.padding_start - layout_params.padding_end) / row.range.len() as f32;
-                                                    if space < 0.0 {
-                                                        space = 0.0;
-                                                    }
-                                                    item_x += child_layout_params.width + child_layout_params.margin_start + child_layout_params.margin_end + space;
-                                                }
-                                                FlexAlign::SpaceEvenly => {
-                                                    let mut space = (width - row.row_min_width - layout_params.padding_start - layout_params.padding_end) / (row.range.len() + 1) as f32;
-                                                    if space < 0.0 {
-                                                        space = 0.0;
-                                                    }
-                                                    item_x += child_layout_params.width + child_layout_params.margin_start + child_layout_params.margin_end + space;
-                                                }
-                                            }
-                                        }
-                                    }
-                                    match align_content {
-                                        FlexAlign::Start | FlexAlign::Center | FlexAlign::End => {
-                                            row_y += row.row_max_height;
-                                        }
-                                        FlexAlign::SpaceBetween => {
-                                            let mut space = (height - total_row_height - layout_params.padding_top - layout_params.padding_bottom) / (properties.rows.len() - 1) as f32;
-                                            if space < 0.0 {
-                                                space = 0.0;
-                                            }
-                                            row_y += row.row_max_height + space;
-                                        }
-                                        FlexAlign::SpaceAround => {
-                                            let mut space = (height - total_row_height - layout_params.padding_top - layout_params.padding_bottom) / properties.rows.len() as f32;
-                                            if space < 0.0 {
-                                                space = 0.0;
-                                            }
-                                            row_y += row.row_max_height + space;
-                                        }
-                                        FlexAlign::SpaceEvenly => {
-                                            let mut space = (height - total_row_height - layout_params.padding_top - layout_params.padding_bottom) / (properties.rows.len() + 1) as f32;
-                                            if space < 0.0 {
-                                                space = 0.0;
-                                            }
-                                            row_y += row.row_max_height + space;
-                                        }
-                                    }
-                                });
-                            }
-                            MainAxis::TopToBottom => {}
-                            MainAxis::BottomToTop => {}
-                        }
-
-
-                        /*                        match flex_wrap {
-                                                    FlexWrap::NoWrap => {
-                                                        fn calculate_y(y: f32, axis_start: AxisStart, align_items: ItemAlign, layout_params: &LayoutParams, child_layout_params: &LayoutParams) -> f32 {
-                                                            let height = layout_params.height;
-                                                            match axis_start {
-                                                                AxisStart::StartTop | AxisStart::EndTop => {
-                                                                    match align_items {
-                                                                        ItemAlign::Start | ItemAlign::Stretch => {
-                                                                            y + layout_params.padding_top + child_layout_params.margin_top
-                                                                        }
-                                                                        ItemAlign::End => {
-                                                                            y + height - layout_params.padding_bottom - child_layout_params.margin_bottom - child_layout_params.height
-                                                                        }
-                                                                        ItemAlign::Center => {
-                                                                            y + (height - layout_params.padding_top - layout_params.padding_bottom - child_layout_params.height) / 2.0
-                                                                        }
-                                                                        _ => {}
-                                                                    }
-                                                                }
-                                                                AxisStart::StartBottom | AxisStart::EndBottom => {
-                                                                    match align_items {
-                                                                        ItemAlign::Start | ItemAlign::Stretch => {
-                                                                            y + height - layout_params.padding_bottom - child_layout_params.margin_bottom - child_layout_params.height
-                                                                        }
-                                                                        ItemAlign::End => {
-                                                                            y + layout_params.padding_top + child_layout_params.margin_top
-                                                                        }
-                                                                        ItemAlign::Center => {
-                                                                            y + (height - layout_params.padding_top - layout_params.padding_bottom - child_layout_params.height) / 2.0
-                                                                        }
-                                                                        _ => {}
-                                                                    }
-                                                                }
-                                                                _ => { 0.0 }
-                                                            }
-                                                        }
-
-                                                        fn calculate_x(x: LogicalX, axis_start: AxisStart, align_items: ItemAlign, layout_params: &LayoutParams, child_layout_params: &LayoutParams) -> LogicalX {
-                                                            let width = layout_params.width;
-                                                            match axis_start {
-                                                                AxisStart::TopStart | AxisStart::BottomStart => {
-                                                                    match align_items {
-                                                                        ItemAlign::Start | ItemAlign::Stretch => {
-                                                                            x + layout_params.padding_start + child_layout_params.margin_start
-                                                                        }
-                                                                        ItemAlign::End => {
-                                                                            x + width - layout_params.padding_end - child_layout_params.margin_end - child_layout_params.width
-                                                                        }
-                                                                        ItemAlign::Center => {
-                                                                            x + (width - layout_params.padding_start - layout_params.padding_end - child_layout_params.width) / 2.0
-                                                                        }
-                                                                        _ => {}
-                                                                    }
-                                                                }
-                                                                AxisStart::TopEnd | AxisStart::BottomEnd => {
-                                                                    match align_items {
-                                                                        ItemAlign::Start | ItemAlign::Stretch => {
-                                                                            x + width - layout_params.padding_end - child_layout_params.margin_end - child_layout_params.width
-                                                                        }
-                                                                        ItemAlign::End => {
-                                                                            x + layout_params.padding_start + child_layout_params.margin_start
-                                                                        }
-                                                                        ItemAlign::Center => {
-                                                                            x + (width - layout_params.padding_start - layout_params.padding_end - child_layout_params.width) / 2.0
-                                                                        }
-                                                                        _ => {}
-                                                                    }
-                                                                }
-                                                                _ => { LogicalX::new(LayoutDirection::LeftToRight, 0.0, 0.0, 0.0) }
-                                                            }
-                                                        }
-
-                                                        match axis_start {
-                                                            AxisStart::StartTop | AxisStart::StartBottom => {
-                                                                match justify_content {
-                                                                    FlexAlign::Start => {
-                                                                        let mut child_x = x + layout_params.padding_start;
-                                                                        item.get_children_mut().iter_mut().for_each(|child| {
-                                                                            let mut child_layout_params = child.get_layout_params().clone();
-                                                                            let x = child_x + child_layout_params.margin_start;
-                                                                            let y = calculate_y(y, axis_start, align_items, &layout_params, &child_layout_params);
-                                                                            child.layout(x.physical_value(child_layout_params.width), y);
-                                                                            child_x += child_layout_params.width + child_layout_params.margin_start + child_layout_params.margin_end;
-                                                                        });
-                                                                    }
-                                                                    FlexAlign::End => {
-                                                                        let mut child_x = x + width - properties.children_occupied_space.width - layout_params.padding_end;
-                                                                        item.get_children_mut().iter_mut().for_each(|child| {
-                                                                            let mut child_layout_params = child.get_layout_params().clone();
-                                                                            let x = child_x + child_layout_params.margin_start;
-                                                                            let y = calculate_y(y, axis_start, align_items, &layout_params, &child_layout_params);
-                                                                            child.layout(x.physical_value(child_layout_params.width), y);
-                                                                            child_x += child_layout_params.width + child_layout_params.margin_start + child_layout_params.margin_end;
-                                                                        });
-                                                                    }
-                                                                    FlexAlign::Center => {
-                                                                        let mut child_x = x + (width - properties.children_occupied_space.width) / 2.0;
-                                                                        item.get_children_mut().iter_mut().for_each(|child| {
-                                                                            let mut child_layout_params = child.get_layout_params().clone();
-                                                                            let x = child_x + child_layout_params.margin_start;
-                                                                            let y = calculate_y(y, axis_start, align_items, &layout_params, &child_layout_params);
-                                                                            child.layout(x.physical_value(child_layout_params.width), y);
-                                                                            child_x += child_layout_params.width + child_layout_params.margin_start + child_layout_params.margin_end;
-                                                                        });
-                                                                    }
-                                                                    FlexAlign::SpaceBetween => {
-                                                                        let mut space = (width - properties.children_occupied_space.width - layout_params.padding_start - layout_params.padding_end) / (children_len - 1) as f32;
-                                                                        if space < 0.0 {
-                                                                            space = 0.0;
-                                                                        }
-                                                                        let mut child_x = x + layout_params.padding_start;
-                                                                        item.get_children_mut().iter_mut().for_each(|child| {
-                                                                            let mut child_layout_params = child.get_layout_params().clone();
-                                                                            let x = child_x + child_layout_params.margin_start;
-                                                                            let y = calculate_y(y, axis_start, align_items, &layout_params, &child_layout_params);
-                                                                            child.layout(x.physical_value(child_layout_params.width), y);
-                                                                            child_x += child_layout_params.width + child_layout_params.margin_start + child_layout_params.margin_end + space;
-                                                                        });
-                                                                    }
-                                                                    FlexAlign::SpaceAround => {
-                                                                        let mut space = (width - properties.children_occupied_space.width - layout_params.padding_start - layout_params.padding_end) / children_len as f32;
-                                                                        if space < 0.0 {
-                                                                            space = 0.0;
-                                                                        }
-                                                                        let mut child_x = x + layout_params.padding_start + space / 2.0;
-                                                                        item.get_children_mut().iter_mut().for_each(|child| {
-                                                                            let mut child_layout_params = child.get_layout_params().clone();
-                                                                            let x = child_x + child_layout_params.margin_start;
-                                                                            let y = calculate_y(y, axis_start, align_items, &layout_params, &child_layout_params);
-                                                                            child.layout(x.physical_value(child_layout_params.width), y);
-                                                                            child_x += child_layout_params.width + child_layout_params.margin_start + child_layout_params.margin_end + space;
-                                                                        });
-                                                                    }
-                                                                    FlexAlign::SpaceEvenly => {
-                                                                        let mut space = (width - properties.children_occupied_space.width - layout_params.padding_start - layout_params.padding_end) / (children_len + 1) as f32;
-                                                                        if space < 0.0 {
-                                                                            space = 0.0;
-                                                                        }
-                                                                        let mut child_x = x + layout_params.padding_start + space;
-                                                                        item.get_children_mut().iter_mut().for_each(|child| {
-                                                                            let mut child_layout_params = child.get_layout_params().clone();
-                                                                            let x = child_x + child_layout_params.margin_start;
-                                                                            let y = calculate_y(y, axis_start, align_items, &layout_params, &child_layout_params);
-                                                                            child.layout(x.physical_value(child_layout_params.width), y);
-                                                                            child_x += child_layout_params.width + child_layout_params.margin_start + child_layout_params.margin_end + space;
-                                                                        });
-                                                                    }
-                                                                }
-                                                            }
-                                                            AxisStart::EndTop | AxisStart::EndBottom => {
-                                                                match justify_content {
-                                                                    FlexAlign::Start => {
-                                                                        let mut child_x = x + width - layout_params.padding_end;
-                                                                        item.get_children_mut().iter_mut().for_each(|child| {
-                                                                            let mut child_layout_params = child.get_layout_params().clone();
-                                                                            let x = child_x - child_layout_params.width - child_layout_params.margin_end;
-                                                                            let y = calculate_y(y, axis_start, align_items, &layout_params, &child_layout_params);
-                                                                            child.layout(x.physical_value(child_layout_params.width), y);
-                                                                            child_x -= child_layout_params.width + child_layout_params.margin_start + child_layout_params.margin_end;
-                                                                        });
-                                                                    }
-                                                                    FlexAlign::End => {
-                                                                        let mut child_x = x + layout_params.padding_start + properties.children_occupied_space.width;
-                                                                        item.get_children_mut().iter_mut().for_each(|child| {
-                                                                            let mut child_layout_params = child.get_layout_params().clone();
-                                                                            let x = child_x - child_layout_params.width - child_layout_params.margin_end;
-                                                                            let y = calculate_y(y, axis_start, align_items, &layout_params, &child_layout_params);
-                                                                            child.layout(x.physical_value(child_layout_params.width), y);
-                                                                            child_x -= child_layout_params.width + child_layout_params.margin_start + child_layout_params.margin_end;
-                                                                        });
-                                                                    }
-                                                                    FlexAlign::Center => {
-                                                                        let mut child_x = x + width - (width - properties.children_occupied_space.width) / 2.0;
-                                                                        item.get_children_mut().iter_mut().for_each(|child| {
-                                                                            let mut child_layout_params = child.get_layout_params().clone();
-                                                                            let x = child_x - child_layout_params.width - child_layout_params.margin_end;
-                                                                            let y = calculate_y(y, axis_start, align_items, &layout_params, &child_layout_params);
-                                                                            child.layout(x.physical_value(child_layout_params.width), y);
-                                                                            child_x -= child_layout_params.width + child_layout_params.margin_start + child_layout_params.margin_end;
-                                                                        });
-                                                                    }
-                                                                    FlexAlign::SpaceBetween => {
-                                                                        let mut space = (width - properties.children_occupied_space.width - layout_params.padding_start - layout_params.padding_end) / (children_len - 1) as f32;
-                                                                        if space < 0.0 {
-                                                                            space = 0.0;
-                                                                        }
-                                                                        let mut child_x = x + width - layout_params.padding_end;
-                                                                        item.get_children_mut().iter_mut().for_each(|child| {
-                                                                            let mut child_layout_params = child.get_layout_params().clone();
-                                                                            let x = child_x - child_layout_params.width - child_layout_params.margin_end;
-                                                                            let y = calculate_y(y, axis_start, align_items, &layout_params, &child_layout_params);
-                                                                            child.layout(x.physical_value(child_layout_params.width), y);
-                                                                            child_x -= child_layout_params.width + child_layout_params.margin_start + child_layout_params.margin_end + space;
-                                                                        });
-                                                                    }
-                                                                    FlexAlign::SpaceAround => {
-                                                                        let mut space = (width - properties.children_occupied_space.width - layout_params.padding_start - layout_params.padding_end) / children_len as f32;
-                                                                        if space < 0.0 {
-                                                                            space = 0.0;
-                                                                        }
-                                                                        let mut child_x = x + width - layout_params.padding_end - space / 2.0;
-                                                                        item.get_children_mut().iter_mut().for_each(|child| {
-                                                                            let mut child_layout_params = child.get_layout_params().clone();
-                                                                            let x = child_x - child_layout_params.width - child_layout_params.margin_end;
-                                                                            let y = calculate_y(y, axis_start, align_items, &layout_params, &child_layout_params);
-                                                                            child.layout(x.physical_value(child_layout_params.width), y);
-                                                                            child_x -= child_layout_params.width + child_layout_params.margin_start + child_layout_params.margin_end + space;
-                                                                        });
-                                                                    }
-                                                                    FlexAlign::SpaceEvenly => {
-                                                                        let mut space = (width - properties.children_occupied_space.width - layout_params.padding_start - layout_params.padding_end) / (children_len + 1) as f32;
-                                                                        if space < 0.0 {
-                                                                            space = 0.0;
-                                                                        }
-                                                                        let mut child_x = x + width - layout_params.padding_end - space;
-                                                                        item.get_children_mut().iter_mut().for_each(|child| {
-                                                                            let mut child_layout_params = child.get_layout_params().clone();
-                                                                            let x = child_x - child_layout_params.width - child_layout_params.margin_end;
-                                                                            let y = calculate_y(y, axis_start, align_items, &layout_params, &child_layout_params);
-                                                                            child.layout(x.physical_value(child_layout_params.width), y);
-                                                                            child_x -= child_layout_params.width + child_layout_params.margin_start + child_layout_params.margin_end + space;
-                                                                        });
-                                                                    }
-                                                                }
-                                                            }
-                                                            AxisStart::TopStart | AxisStart::TopEnd => {
-                                                                match justify_content {
-                                                                    FlexAlign::Start => {
-                                                                        let mut child_y = y + layout_params.padding_top;
-                                                                        item.get_children_mut().iter_mut().for_each(|child| {
-                                                                            let mut child_layout_params = child.get_layout_params().clone();
-                                                                            let y = child_y + child_layout_params.margin_top;
-                                                                            let x = calculate_x(x, axis_start, align_items, &layout_params, &child_layout_params);
-                                                                            child.layout(x.physical_value(child_layout_params.width), y);
-                                                                            child_y += child_layout_params.height + child_layout_params.margin_top + child_layout_params.margin_bottom;
-                                                                        });
-                                                                    }
-                                                                    FlexAlign::End => {
-                                                                        let mut child_y = y + height - properties.children_occupied_space.height - layout_params.padding_bottom;
-                                                                        item.get_children_mut().iter_mut().for_each(|child| {
-                                                                            let mut child_layout_params = child.get_layout_params().clone();
-                                                                            let y = child_y + child_layout_params.margin_top;
-                                                                            let x = calculate_x(x, axis_start, align_items, &layout_params, &child_layout_params);
-                                                                            child.layout(x.physical_value(child_layout_params.width), y);
-                                                                            child_y += child_layout_params.height + child_layout_params.margin_top + child_layout_params.margin_bottom;
-                                                                        });
-                                                                    }
-                                                                    FlexAlign::Center => {
-                                                                        let mut child_y = y + (height - properties.children_occupied_space.height) / 2.0;
-                                                                        item.get_children_mut().iter_mut().for_each(|child| {
-                                                                            let mut child_layout_params = child.get_layout_params().clone();
-                                                                            let y = child_y + child_layout_params.margin_top;
-                                                                            let x = calculate_x(x, axis_start, align_items, &layout_params, &child_layout_params);
-                                                                            child.layout(x.physical_value(child_layout_params.width), y);
-                                                                            child_y += child_layout_params.height + child_layout_params.margin_top + child_layout_params.margin_bottom;
-                                                                        });
-                                                                    }
-                                                                    FlexAlign::SpaceBetween => {
-                                                                        let mut space = (height - properties.children_occupied_space.height - layout_params.padding_top - layout_params.padding_bottom) / (children_len - 1) as f32;
-                                                                        if space < 0.0 {
-                                                                            space = 0.0;
-                                                                        }
-                                                                        let mut child_y = y + layout_params.padding_top;
-                                                                        item.get_children_mut().iter_mut().for_each(|child| {
-                                                                            let mut child_layout_params = child.get_layout_params().clone();
-                                                                            let y = child_y + child_layout_params.margin_top;
-                                                                            let x = calculate_x(x, axis_start, align_items, &layout_params, &child_layout_params);
-                                                                            child.layout(x.physical_value(child_layout_params.width), y);
-                                                                            child_y += child_layout_params.height + child_layout_params.margin_top + child_layout_params.margin_bottom + space;
-                                                                        });
-                                                                    }
-                                                                    FlexAlign::SpaceAround => {
-                                                                        let mut space = (height - properties.children_occupied_space.height - layout_params.padding_top - layout_params.padding_bottom) / children_len as f32;
-                                                                        if space < 0.0 {
-                                                                            space = 0.0;
-                                                                        }
-                                                                        let mut child_y = y + layout_params.padding_top + space / 2.0;
-                                                                        item.get_children_mut().iter_mut().for_each(|child| {
-                                                                            let mut child_layout_params = child.get_layout_params().clone();
-                                                                            let y = child_y + child_layout_params.margin_top;
-                                                                            let x = calculate_x(x, axis_start, align_items, &layout_params, &child_layout_params);
-                                                                            child.layout(x.physical_value(child_layout_params.width), y);
-                                                                            child_y += child_layout_params.height + child_layout_params.margin_top + child_layout_params.margin_bottom + space;
-                                                                        });
-                                                                    }
-                                                                    FlexAlign::SpaceEvenly => {
-                                                                        let mut space = (height - properties.children_occupied_space.height - layout_params.padding_top - layout_params.padding_bottom) / (children_len + 1) as f32;
-                                                                        if space < 0.0 {
-                                                                            space = 0.0;
-                                                                        }
-                                                                        let mut child_y = y + layout_params.padding_top + space;
-                                                                        item.get_children_mut().iter_mut().for_each(|child| {
-                                                                            let mut child_layout_params = child.get_layout_params().clone();
-                                                                            let y = child_y + child_layout_params.margin_top;
-                                                                            let x = calculate_x(x, axis_start, align_items, &layout_params, &child_layout_params);
-                                                                            child.layout(x.physical_value(child_layout_params.width), y);
-                                                                            child_y += child_layout_params.height + child_layout_params.margin_top + child_layout_params.margin_bottom + space;
-                                                                        });
-                                                                    }
-                                                                }
-                                                            }
-                                                            AxisStart::BottomStart | AxisStart::BottomEnd => {
-                                                                match justify_content {
-                                                                    FlexAlign::Start => {
-                                                                        let mut child_y = y + height - layout_params.padding_bottom;
-                                                                        item.get_children_mut().iter_mut().for_each(|child| {
-                                                                            let mut child_layout_params = child.get_layout_params().clone();
-                                                                            let y = child_y - child_layout_params.height - child_layout_params.margin_bottom;
-                                                                            let x = calculate_x(x, axis_start, align_items, &layout_params, &child_layout_params);
-                                                                            child.layout(x.physical_value(child_layout_params.width), y);
-                                                                            child_y -= child_layout_params.height + child_layout_params.margin_top + child_layout_params.margin_bottom;
-                                                                        });
-                                                                    }
-                                                                    FlexAlign::End => {
-                                                                        let mut child_y = y + layout_params.padding_top + properties.children_occupied_space.height;
-                                                                        item.get_children_mut().iter_mut().for_each(|child| {
-                                                                            let mut child_layout_params = child.get_layout_params().clone();
-                                                                            let y = child_y - child_layout_params.height - child_layout_params.margin_bottom;
-                                                                            let x = calculate_x(x, axis_start, align_items, &layout_params, &child_layout_params);
-                                                                            child.layout(x.physical_value(child_layout_params.width), y);
-                                                                            child_y -= child_layout_params.height + child_layout_params.margin_top + child_layout_params.margin_bottom;
-                                                                        });
-                                                                    }
-                                                                    FlexAlign::Center => {
-                                                                        let mut child_y = y + height - (height - properties.children_occupied_space.height) / 2.0;
-                                                                        item.get_children_mut().iter_mut().for_each(|child| {
-                                                                            let mut child_layout_params = child.get_layout_params().clone();
-                                                                            let y = child_y - child_layout_params.height - child_layout_params.margin_bottom;
-                                                                            let x = calculate_x(x, axis_start, align_items, &layout_params, &child_layout_params);
-                                                                            child.layout(x.physical_value(child_layout_params.width), y);
-                                                                            child_y -= child_layout_params.height + child_layout_params.margin_top + child_layout_params.margin_bottom;
-                                                                        });
-                                                                    }
-                                                                    FlexAlign::SpaceBetween => {
-                                                                        let mut space = (height - properties.children_occupied_space.height - layout_params.padding_top - layout_params.padding_bottom) / (children_len - 1) as f32;
-                                                                        if space < 0.0 {
-                                                                            space = 0.0;
-                                                                        }
-                                                                        let mut child_y = y + height - layout_params.padding_bottom;
-                                                                        item.get_children_mut().iter_mut().for_each(|child| {
-                                                                            let mut child_layout_params = child.get_layout_params().clone();
-                                                                            let y = child_y - child_layout_params.height - child_layout_params.margin_bottom;
-                                                                            let x = calculate_x(x, axis_start, align_items, &layout_params, &child_layout_params);
-                                                                            child.layout(x.physical_value(child_layout_params.width), y);
-                                                                            child_y -= child_layout_params.height + child_layout_params.margin_top + child_layout_params.margin_bottom + space;
-                                                                        });
-                                                                    }
-                                                                    FlexAlign::SpaceAround => {
-                                                                        let mut space = (height - properties.children_occupied_space.height - layout_params.padding_top - layout_params.padding_bottom) / children_len as f32;
-                                                                        if space < 0.0 {
-                                                                            space = 0.0;
-                                                                        }
-                                                                        let mut child_y = y + height - layout_params.padding_bottom - space / 2.0;
-                                                                        item.get_children_mut().iter_mut().for_each(|child| {
-                                                                            let mut child_layout_params = child.get_layout_params().clone();
-                                                                            let y = child_y - child_layout_params.height - child_layout_params.margin_bottom;
-                                                                            let x = calculate_x(x, axis_start, align_items, &layout_params, &child_layout_params);
-                                                                            child.layout(x.physical_value(child_layout_params.width), y);
-                                                                            child_y -= child_layout_params.height + child_layout_params.margin_top + child_layout_params.margin_bottom + space;
-                                                                        });
-                                                                    }
-                                                                    FlexAlign::SpaceEvenly => {
-                                                                        let mut space = (height - properties.children_occupied_space.height - layout_params.padding_top - layout_params.padding_bottom) / (children_len + 1) as f32;
-                                                                        if space < 0.0 {
-                                                                            space = 0.0;
-                                                                        }
-                                                                        let mut child_y = y + height - layout_params.padding_bottom - space;
-                                                                        item.get_children_mut().iter_mut().for_each(|child| {
-                                                                            let mut child_layout_params = child.get_layout_params().clone();
-                                                                            let y = child_y - child_layout_params.height - child_layout_params.margin_bottom;
-                                                                            let x = calculate_x(x, axis_start, align_items, &layout_params, &child_layout_params);
-                                                                            child.layout(x.physical_value(child_layout_params.width), y);
-                                                                            child_y -= child_layout_params.height + child_layout_params.margin_top + child_layout_params.margin_bottom + space;
-                                                                        });
-                                                                    }
-                                                                }
-                                                            }
-                                                        }
-                                                    }
-                                                    FlexWrap::Wrap => {
-                                                        match axis_start {
-                                                            AxisStart::StartTop | AxisStart::StartBottom => {
-                                                                match justify_content {
-                                                                    FlexAlign::Start => {
-
-                                                                    }
-                                                                    _ => {}
-                                                                }
-                                                            }
-                                                            _ => {}
-                                                        }
-                                                    }
-                                                }*/
+                            row_cross += row.cross_size + row_space;
+                        });
                     }
                 })
         );
-        item.set_children(children);
+        item.set_children(children_property(children));
 
         FlexLayout {
             item,
@@ -1130,48 +461,232 @@ impl FlexLayout {
         }
     }
 
+    pub fn main_axis(mut self, main_axis: impl Into<SharedProperty<MainAxis>>) -> Self {
+        let main_axis = main_axis.into();
+        self.observe_layout(&main_axis);
+        self.properties.lock().unwrap().main_axis = main_axis;
+        self
+    }
+
+    pub fn cross_axis(mut self, cross_axis: impl Into<SharedProperty<CrossAxis>>) -> Self {
+        let cross_axis = cross_axis.into();
+        self.observe_layout(&cross_axis);
+        self.properties.lock().unwrap().cross_axis = cross_axis;
+        self
+    }
+
+    pub fn flex_wrap(mut self, flex_wrap: impl Into<SharedProperty<FlexWrap>>) -> Self {
+        let flex_wrap = flex_wrap.into();
+        self.observe_layout(&flex_wrap);
+        self.properties.lock().unwrap().flex_wrap = flex_wrap;
+        self
+    }
+
+    pub fn justify_content(mut self, justify_content: impl Into<SharedProperty<FlexAlign>>) -> Self {
+        let justify_content = justify_content.into();
+        self.observe_layout(&justify_content);
+        self.properties.lock().unwrap().justify_content = justify_content;
+        self
+    }
+
+    pub fn align_items(mut self, align_items: impl Into<SharedProperty<ItemAlign>>) -> Self {
+        let align_items = align_items.into();
+        self.observe_layout(&align_items);
+        self.properties.lock().unwrap().align_items = align_items;
+        self
+    }
+
+    pub fn align_content(mut self, align_content: impl Into<SharedProperty<FlexAlign>>) -> Self {
+        let align_content = align_content.into();
+        self.observe_layout(&align_content);
+        self.properties.lock().unwrap().align_content = align_content;
+        self
+    }
+
+    fn observe_layout(&mut self, property: &(impl Observable + Clone + 'static)) {
+        let layout_node = self.item.get_layout_node();
+        self.item.subscribe(property, move || {
+            layout_node.invalidate();
+        });
+    }
+
     pub fn unwrap(self) -> Item {
         self.item
     }
 }
 
-fn measure_child_stretch(is_height: bool, child: &Item, parent_layout_params: &LayoutParams, width_measure_mode: MeasureMode, height_measure_mode: MeasureMode) -> (MeasureMode, MeasureMode) {
-    let layout_params = child.get_layout_params();
-    let max_width = match width_measure_mode {
-        MeasureMode::Specified(width) => width,
-        MeasureMode::Unspecified(width) => width,
-    } - layout_params.margin_start - layout_params.margin_end - parent_layout_params.padding_start - parent_layout_params.margin_end;
-    let max_height = match height_measure_mode {
-        MeasureMode::Specified(height) => height,
-        MeasureMode::Unspecified(height) => height,
-    } - layout_params.margin_top - layout_params.margin_bottom - parent_layout_params.padding_top - parent_layout_params.margin_bottom;
-
-    let child_width = child.get_width().get();
-    let child_height = child.get_height().get();
-
-    let child_width_measure_mode =
-        if is_height {
-            match child_width {
-                Size::Default => MeasureMode::Unspecified(max_width),
-                Size::Fill => MeasureMode::Specified(max_width),
-                Size::Fixed(width) => MeasureMode::Specified(width),
-                Size::Relative(scale) => MeasureMode::Specified(max_width * scale),
+/// The sizes of a child that take part in growing and shrinking, along the main axis.
+struct FlexFactors {
+    /// The size before growing or shrinking, margins excluded.
+    base: f32,
+    margins: f32,
+    grow: f32,
+    shrink: f32,
+    min: f32,
+    max: f32,
+}
+
+/// Resolve the main sizes of the children of a row in `available` space, as CSS flexbox resolves flexible lengths.
+/// Children clamped to their min or max size are frozen, and the rest of the free space is shared again by the others.
+fn flex_sizes(items: &[FlexFactors], available: f32) -> Vec<f32> {
+    let mut sizes: Vec<f32> = items.iter().map(|item| item.base).collect();
+    let outer: f32 = items.iter().map(|item| item.base + item.margins).sum();
+    let growing = available > outer;
+    let mut frozen: Vec<bool> = items.iter().map(|item| {
+        available == outer || (growing && item.grow == 0.0) || (!growing && item.shrink == 0.0)
+    }).collect();
+
+    while frozen.iter().any(|frozen| !frozen) {
+        let used: f32 = items.iter().enumerate()
+            .map(|(index, item)| item.margins + if frozen[index] { sizes[index] } else { item.base })
+            .sum();
+        let free = available - used;
+        let unfrozen: Vec<usize> = (0..items.len()).filter(|index| !frozen[*index]).collect();
+        let weights: Vec<f32> = unfrozen.iter().map(|index| {
+            let item = &items[*index];
+            if growing { item.grow } else { item.shrink * item.base }
+        }).collect();
+        let total_weight: f32 = weights.iter().sum();
+        if total_weight <= 0.0 {
+            break;
+        }
+
+        let mut violation = 0.0_f32;
+        let mut clamped = Vec::new();
+        unfrozen.iter().zip(weights.iter()).for_each(|(index, weight)| {
+            let item = &items[*index];
+            let size = item.base + free * weight / total_weight;
+            let clamped_size = clamp_size(size, item.min, item.max).max(0.0);
+            violation += clamped_size - size;
+            clamped.push((*index, clamped_size - size));
+            sizes[*index] = clamped_size;
+        });
+
+        // Freeze all if nothing was clamped, or else the children clamped in the direction of the total violation.
+        clamped.iter().for_each(|(index, item_violation)| {
+            if violation == 0.0 || (violation > 0.0 && *item_violation > 0.0) || (violation < 0.0 && *item_violation < 0.0) {
+                frozen[*index] = true;
             }
-        } else {
-            MeasureMode::Specified(max_width)
-        };
-
-    let child_height_measure_mode =
-        if is_height {
-            MeasureMode::Specified(max_height)
-        } else {
-            match child_height {
-                Size::Default => MeasureMode::Unspecified(max_height),
-                Size::Fill => MeasureMode::Specified(max_height),
-                Size::Fixed(height) => MeasureMode::Specified(height),
-                Size::Relative(scale) => MeasureMode::Specified(max_height * scale),
+        });
+    }
+    sizes
+}
+
+fn is_main_fill(child: &Item, main_axis: MainAxis) -> bool {
+    if main_axis.is_horizontal() {
+        child.get_width().get() == Size::Fill
+    } else {
+        child.get_height().get() == Size::Fill
+    }
+}
+
+/// The size of the child along the main axis, margins included.
+fn main_size(child_layout_params: &LayoutParams, main_axis: MainAxis) -> f32 {
+    if main_axis.is_horizontal() {
+        child_layout_params.width + child_layout_params.margin_start + child_layout_params.margin_end
+    } else {
+        child_layout_params.height + child_layout_params.margin_top + child_layout_params.margin_bottom
+    }
+}
+
+/// The size of the child along the cross axis, margins included.
+fn cross_size(child_layout_params: &LayoutParams, main_axis: MainAxis) -> f32 {
+    if main_axis.is_horizontal() {
+        child_layout_params.height + child_layout_params.margin_top + child_layout_params.margin_bottom
+    } else {
+        child_layout_params.width + child_layout_params.margin_start + child_layout_params.margin_end
+    }
+}
+
+/// The size of the child along the cross axis, margins excluded.
+fn cross_content_size(child_layout_params: &LayoutParams, main_axis: MainAxis) -> f32 {
+    if main_axis.is_horizontal() {
+        child_layout_params.height
+    } else {
+        child_layout_params.width
+    }
+}
+
+/// The margins of the child before and after it along the main axis.
+fn main_margins(child_layout_params: &LayoutParams, main_axis: MainAxis) -> (f32, f32) {
+    match main_axis {
+        MainAxis::StartToEnd => (child_layout_params.margin_start, child_layout_params.margin_end),
+        MainAxis::EndToStart => (child_layout_params.margin_end, child_layout_params.margin_start),
+        MainAxis::TopToBottom => (child_layout_params.margin_top, child_layout_params.margin_bottom),
+        MainAxis::BottomToTop => (child_layout_params.margin_bottom, child_layout_params.margin_top),
+    }
+}
+
+/// The margins of the child before and after it along the cross axis.
+fn cross_margins(child_layout_params: &LayoutParams, main_axis: MainAxis, cross_axis: CrossAxis) -> (f32, f32) {
+    let (start, end) = if main_axis.is_horizontal() {
+        (child_layout_params.margin_top, child_layout_params.margin_bottom)
+    } else {
+        (child_layout_params.margin_start, child_layout_params.margin_end)
+    };
+    match cross_axis {
+        CrossAxis::Forward => (start, end),
+        CrossAxis::Reverse => (end, start),
+    }
+}
+
+/// The leading offset and the space between items when aligning `count` items in `free_space`.
+fn distribute(align: FlexAlign, free_space: f32, count: usize) -> (f32, f32) {
+    let positive_space = free_space.max(0.0);
+    match align {
+        FlexAlign::Start => (0.0, 0.0),
+        FlexAlign::End => (free_space, 0.0),
+        FlexAlign::Center => (free_space / 2.0, 0.0),
+        FlexAlign::SpaceBetween => {
+            if count > 1 {
+                (0.0, positive_space / (count - 1) as f32)
+            } else {
+                (free_space / 2.0, 0.0)
             }
-        };
+        }
+        FlexAlign::SpaceAround => {
+            let space = positive_space / count.max(1) as f32;
+            (space / 2.0, space)
+        }
+        FlexAlign::SpaceEvenly => {
+            let space = positive_space / (count + 1) as f32;
+            (space, space)
+        }
+    }
+}
+
+/// Convert the offsets of the child from the start of the content along the main and cross axes
+/// into a position relative to the layout.
+fn child_position(layout_params: &LayoutParams, child_layout_params: &LayoutParams, main_axis: MainAxis, cross_axis: CrossAxis, direction: LayoutDirection, main_offset: f32, cross_offset: f32) -> (f32, f32) {
+    let width = layout_params.width;
+    let height = layout_params.height;
+    let child_width = child_layout_params.width;
+    let child_height = child_layout_params.height;
+
+    let (logical_x, y) = match main_axis {
+        MainAxis::StartToEnd | MainAxis::EndToStart => {
+            let logical_x = match main_axis {
+                MainAxis::StartToEnd => layout_params.padding_start + main_offset,
+                _ => width - layout_params.padding_end - main_offset - child_width,
+            };
+            let y = match cross_axis {
+                CrossAxis::Forward => layout_params.padding_top + cross_offset,
+                CrossAxis::Reverse => height - layout_params.padding_bottom - cross_offset - child_height,
+            };
+            (logical_x, y)
+        }
+        MainAxis::TopToBottom | MainAxis::BottomToTop => {
+            let y = match main_axis {
+                MainAxis::TopToBottom => layout_params.padding_top + main_offset,
+                _ => height - layout_params.padding_bottom - main_offset - child_height,
+            };
+            let logical_x = match cross_axis {
+                CrossAxis::Forward => layout_params.padding_start + cross_offset,
+                CrossAxis::Reverse => width - layout_params.padding_end - cross_offset - child_width,
+            };
+            (logical_x, y)
+        }
+    };
 
-    (child_width_measure_mode, child_height_measure_mode)
-}
\ No newline at end of file
+    (LogicalX::new(direction, 0.0, logical_x, width).physical_value(child_width), y)
+}
@@ -1,100 +1,128 @@
-use crate::item::{Item, ItemEvent, measure_child, MeasureMode};
+use crate::app::SharedApp;
+use crate::layout::{children_property, init_child_margins};
 use crate::property::Gettable;
+use crate::ui::{clamp_size, Item, ItemEvent, LogicalX, measure_child, MeasureMode};
 
 #[macro_export]
 macro_rules! flow {
-    ($($child:expr)*) => {
-        $crate::layout::Flow::new(vec![$($child),*])
+    ($($child:expr),+ $(,)?) => {
+        {
+            let children = vec![$($child),*];
+            let app = children.first().unwrap().get_app().clone();
+            $crate::layout::Flow::new(app, children)
+        }
     }
 }
 
+/// Places the children in lines from the start to the end, and starts a new line when a child does not fit.
 pub struct Flow {
     item: Item,
 }
 
 impl Flow {
-    pub fn new(children: Vec<Item>) -> Self {
+    pub fn new(app: SharedApp, children: Vec<Item>) -> Self {
         let mut item = Item::new(
+            app,
             ItemEvent::default()
-                .set_on_measure(
+                .set_measure_event(
                     |item, width_measure_mode, height_measure_mode| {
-                        let mut layout_params = item.get_layout_params_mut().clone();
-
-                        let mut width = 0.0;
-                        let mut height = 0.0_f32;
-
-                        let mut remaining_width = match width_measure_mode {
-                            MeasureMode::Exactly(width) => width,
-                            MeasureMode::AtMost(width) => width,
-                        };
-
-                        item.get_children_mut().iter_mut().for_each(|child| {
-                            let width_measure_mode = match width_measure_mode {
-                                MeasureMode::Exactly(_) => MeasureMode::Exactly(remaining_width),
-                                MeasureMode::AtMost(_) => MeasureMode::AtMost(remaining_width),
-                            };
-
-                            let mut child_occupied_width = 0.0;
-                            let (child_width_measure_mode, child_height_measure_mode) = measure_child(child,layout_params, width_measure_mode, height_measure_mode);
-
-                            let mut child_layout_params = child.get_layout_params().clone();
-                            child_layout_params.padding_start = child.get_margin_start().get();
-                            child_layout_params.padding_top = child.get_margin_top().get();
-                            child_layout_params.padding_end = child.get_margin_end().get();
-                            child_layout_params.padding_bottom = child.get_margin_bottom().get();
-                            child_layout_params.margin_start = child.get_margin_start().get();
-                            child_layout_params.margin_top = child.get_margin_top().get();
-                            child_layout_params.margin_end = child.get_margin_end().get();
-                            child_layout_params.margin_bottom = child.get_margin_bottom().get();
-                            child.set_layout_params(child_layout_params);
+                        let mut layout_params = item.get_layout_params().clone();
+                        layout_params.init_from_item(item);
 
-                            child.measure(child_width_measure_mode, child_height_measure_mode);
-                            child_occupied_width = child_layout_params.width + child_layout_params.margin_start + child_layout_params.margin_end;
-                            height = height.max(child_layout_params.height + child_layout_params.margin_top + child_layout_params.margin_bottom);
+                        let available_width = width_measure_mode.value() - layout_params.padding_start - layout_params.padding_end;
+
+                        let mut content_width = 0.0_f32;
+                        let mut line_width = 0.0_f32;
+                        let mut line_height = 0.0_f32;
+                        let mut lines_height = 0.0_f32;
 
+                        item.get_children().lock().iter_mut().for_each(|child| {
+                            init_child_margins(child);
+                            let (child_width_measure_mode, child_height_measure_mode) = measure_child(child, &layout_params, width_measure_mode, height_measure_mode);
+                            child.measure(child_width_measure_mode, child_height_measure_mode);
 
-                            width += child_occupied_width;
+                            let child_layout_params = child.get_layout_params();
+                            let occupied_width = child_layout_params.width + child_layout_params.margin_start + child_layout_params.margin_end;
+                            let occupied_height = child_layout_params.height + child_layout_params.margin_top + child_layout_params.margin_bottom;
 
-                            if remaining_width - child_occupied_width < 0.0 {
-                                remaining_width = 0.0;
-                            } else {
-                                remaining_width -= child_occupied_width;
+                            if line_width > 0.0 && line_width + occupied_width > available_width {
+                                lines_height += line_height;
+                                line_width = 0.0;
+                                line_height = 0.0;
                             }
+                            line_width += occupied_width;
+                            line_height = line_height.max(occupied_height);
+                            content_width = content_width.max(line_width);
                         });
+                        let content_height = lines_height + line_height;
 
-                        match width_measure_mode {
-                            MeasureMode::Exactly(measured_width) => {
-                                layout_params.width = measured_width;
-                            }
-                            MeasureMode::AtMost(measured_width) => {
-                                layout_params.width = measured_width.min(width);
-                            }
+                        layout_params.width = clamp_size(match width_measure_mode {
+                            MeasureMode::Specified(width) => width,
+                            MeasureMode::Unspecified(width) => width.min(content_width + layout_params.padding_start + layout_params.padding_end),
+                        }, layout_params.min_width, layout_params.max_width);
+
+                        layout_params.height = clamp_size(match height_measure_mode {
+                            MeasureMode::Specified(height) => height,
+                            MeasureMode::Unspecified(height) => height.min(content_height + layout_params.padding_top + layout_params.padding_bottom),
+                        }, layout_params.min_height, layout_params.max_height);
+
+                        if let Some(background) = item.get_background().lock().as_mut() {
+                            background.measure(MeasureMode::Specified(layout_params.width), MeasureMode::Specified(layout_params.height));
                         }
 
-                        match height_measure_mode {
-                            MeasureMode::Exactly(measured_height) => {
-                                layout_params.height = measured_height;
-                            }
-                            MeasureMode::AtMost(measured_height) => {
-                                layout_params.height = measured_height.min(height);
-                            }
+                        if let Some(foreground) = item.get_foreground().lock().as_mut() {
+                            foreground.measure(MeasureMode::Specified(layout_params.width), MeasureMode::Specified(layout_params.height));
                         }
 
-                        item.set_layout_params(layout_params);
+                        item.set_layout_params(&layout_params);
                     }
                 )
-                .set_on_layout(
+                .set_layout_event(
                     |item, x, y| {
-                        let layout_params = item.get_layout_params_mut();
-                        layout_params.x = x;
-                        layout_params.y = y;
-                        item.get_children_mut().iter_mut().for_each(|child| {
-                            child.layout(x, y);
+                        let direction = item.get_layout_direction().get();
+
+                        let mut layout_params = item.get_layout_params().clone();
+                        layout_params.relative_x = x;
+                        layout_params.relative_y = y;
+                        item.set_layout_params(&layout_params);
+
+                        if let Some(background) = item.get_background().lock().as_mut() {
+                            background.layout(x, y);
+                        }
+
+                        if let Some(foreground) = item.get_foreground().lock().as_mut() {
+                            foreground.layout(x, y);
+                        }
+
+                        let available_width = layout_params.width - layout_params.padding_start - layout_params.padding_end;
+
+                        let mut line_x = 0.0_f32;
+                        let mut line_y = 0.0_f32;
+                        let mut line_height = 0.0_f32;
+
+                        item.get_children().lock().iter_mut().for_each(|child| {
+                            let child_layout_params = child.get_layout_params().clone();
+                            let occupied_width = child_layout_params.width + child_layout_params.margin_start + child_layout_params.margin_end;
+                            let occupied_height = child_layout_params.height + child_layout_params.margin_top + child_layout_params.margin_bottom;
+
+                            if line_x > 0.0 && line_x + occupied_width > available_width {
+                                line_y += line_height;
+                                line_x = 0.0;
+                                line_height = 0.0;
+                            }
+
+                            let child_x = LogicalX::new(direction, 0.0, layout_params.padding_start + line_x + child_layout_params.margin_start, layout_params.width)
+                                .physical_value(child_layout_params.width);
+                            let child_y = layout_params.padding_top + line_y + child_layout_params.margin_top;
+                            child.layout(child_x, child_y);
+
+                            line_x += occupied_width;
+                            line_height = line_height.max(occupied_height);
                         });
                     }
                 )
         );
-        item.set_children(children);
+        item.set_children(children_property(children));
 
         Flow {
             item,
@@ -104,4 +132,4 @@ impl Flow {
     pub fn unwrap(self) -> Item {
         self.item
     }
-}
\ No newline at end of file
+}
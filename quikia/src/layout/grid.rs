@@ -0,0 +1,406 @@
+use std::collections::HashSet;
+use std::sync::{Arc, Mutex};
+
+use crate::app::SharedApp;
+use crate::layout::{children_property, gravity_offset, init_child_margins};
+use crate::property::{FloatProperty, Gettable, Size};
+use crate::ui::{clamp_size, Item, ItemEvent, LogicalX, measure_child, MeasureMode};
+use crate::ui::additional_property::GridPlacement;
+
+#[macro_export]
+macro_rules! grid {
+    ($($child:expr),+ $(,)?) => {
+        {
+            let children = vec![$($child),*];
+            let app = children.first().unwrap().get_app().clone();
+            $crate::layout::Grid::new(app, children)
+        }
+    }
+}
+
+/// The size of a row or a column of a [`Grid`].
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum GridTrack {
+    Fixed(f32),
+    /// As large as the largest child in the track.
+    Auto,
+    /// A share of the space left by the other tracks, in proportion to the other fractional tracks.
+    /// When the grid has no bound size, the track behaves as [`GridTrack::Auto`].
+    Fraction(f32),
+}
+
+/// The cells covered by a child.
+#[derive(Clone, Copy, Debug)]
+struct Placement {
+    row: usize,
+    column: usize,
+    row_span: usize,
+    column_span: usize,
+}
+
+struct GridProperties {
+    rows: Vec<GridTrack>,
+    columns: Vec<GridTrack>,
+    row_gap: FloatProperty,
+    column_gap: FloatProperty,
+    placements: Vec<Placement>,
+    row_sizes: Vec<f32>,
+    column_sizes: Vec<f32>,
+}
+
+/// Places the children in the cells of rows and columns, see [`GridPlacement`] to choose the cells of a child.
+/// Rows and columns not defined by [`Grid::rows`] and [`Grid::columns`] are [`GridTrack::Auto`].
+/// Children are aligned in their cells by the gravity of the grid.
+pub struct Grid {
+    item: Item,
+    properties: Arc<Mutex<GridProperties>>,
+}
+
+impl Grid {
+    pub fn new(app: SharedApp, children: Vec<Item>) -> Self {
+        let properties = Arc::new(Mutex::new(GridProperties {
+            rows: Vec::new(),
+            columns: Vec::new(),
+            row_gap: 0.0.into(),
+            column_gap: 0.0.into(),
+            placements: Vec::new(),
+            row_sizes: Vec::new(),
+            column_sizes: Vec::new(),
+        }));
+        let mut item = Item::new(
+            app,
+            ItemEvent::default()
+                .set_measure_event({
+                    let properties = properties.clone();
+                    move |item, width_measure_mode, height_measure_mode| {
+                        let mut layout_params = item.get_layout_params().clone();
+                        layout_params.init_from_item(item);
+                        let horizontal_padding = layout_params.padding_start + layout_params.padding_end;
+                        let vertical_padding = layout_params.padding_top + layout_params.padding_bottom;
+                        let available_width = width_measure_mode.value() - horizontal_padding;
+                        let available_height = height_measure_mode.value() - vertical_padding;
+
+                        let mut properties = properties.lock().unwrap();
+                        let row_gap = properties.row_gap.get();
+                        let column_gap = properties.column_gap.get();
+
+                        let children = item.get_children();
+                        let mut children = children.lock();
+
+                        let column_count = properties.columns.len().max(1);
+                        let placements = place_children(children.iter(), column_count);
+                        let row_count = placements.iter().map(|placement| placement.row + placement.row_span).max().unwrap_or(0).max(properties.rows.len());
+                        let column_count = placements.iter().map(|placement| placement.column + placement.column_span).max().unwrap_or(0).max(column_count);
+                        let rows = tracks(&properties.rows, row_count);
+                        let columns = tracks(&properties.columns, column_count);
+
+                        // Measure the children in the available space to size the columns.
+                        let child_widths: Vec<f32> = children.iter_mut().map(|child| {
+                            init_child_margins(child);
+                            let (child_width_measure_mode, child_height_measure_mode) = measure_child(child, &layout_params, MeasureMode::Unspecified(width_measure_mode.value()), MeasureMode::Unspecified(height_measure_mode.value()));
+                            child.measure(content_measure_mode(child.get_width().get(), child_width_measure_mode), content_measure_mode(child.get_height().get(), child_height_measure_mode));
+                            let child_layout_params = child.get_layout_params();
+                            child_layout_params.width + child_layout_params.margin_start + child_layout_params.margin_end
+                        }).collect();
+                        let column_sizes = track_sizes(
+                            &columns,
+                            placements.iter().zip(child_widths).map(|(placement, width)| (placement.column, placement.column_span, width)),
+                            available_width,
+                            column_gap,
+                        );
+
+                        // Measure them again in the width of their columns to size the rows.
+                        let child_heights: Vec<f32> = children.iter_mut().zip(placements.iter()).map(|(child, placement)| {
+                            let width = span_size(&column_sizes, placement.column, placement.column_span, column_gap);
+                            let (child_width_measure_mode, child_height_measure_mode) = measure_child(child, &layout_params, MeasureMode::Unspecified(width + horizontal_padding), MeasureMode::Unspecified(height_measure_mode.value()));
+                            child.measure(child_width_measure_mode, content_measure_mode(child.get_height().get(), child_height_measure_mode));
+                            let child_layout_params = child.get_layout_params();
+                            child_layout_params.height + child_layout_params.margin_top + child_layout_params.margin_bottom
+                        }).collect();
+                        let row_sizes = track_sizes(
+                            &rows,
+                            placements.iter().zip(child_heights).map(|(placement, height)| (placement.row, placement.row_span, height)),
+                            available_height,
+                            row_gap,
+                        );
+
+                        // Measure them in their cells, so that children filling their cells get the height of their rows.
+                        children.iter_mut().zip(placements.iter()).for_each(|(child, placement)| {
+                            let width = span_size(&column_sizes, placement.column, placement.column_span, column_gap);
+                            let height = span_size(&row_sizes, placement.row, placement.row_span, row_gap);
+                            let (child_width_measure_mode, child_height_measure_mode) = measure_child(child, &layout_params, MeasureMode::Unspecified(width + horizontal_padding), MeasureMode::Unspecified(height + vertical_padding));
+                            child.measure(child_width_measure_mode, child_height_measure_mode);
+                        });
+                        drop(children);
+
+                        let content_width = span_size(&column_sizes, 0, column_sizes.len(), column_gap);
+                        let content_height = span_size(&row_sizes, 0, row_sizes.len(), row_gap);
+                        properties.placements = placements;
+                        properties.column_sizes = column_sizes;
+                        properties.row_sizes = row_sizes;
+                        drop(properties);
+
+                        layout_params.width = clamp_size(match width_measure_mode {
+                            MeasureMode::Specified(width) => width,
+                            MeasureMode::Unspecified(width) => width.min(content_width + horizontal_padding),
+                        }, layout_params.min_width, layout_params.max_width);
+
+                        layout_params.height = clamp_size(match height_measure_mode {
+                            MeasureMode::Specified(height) => height,
+                            MeasureMode::Unspecified(height) => height.min(content_height + vertical_padding),
+                        }, layout_params.min_height, layout_params.max_height);
+
+                        if let Some(background) = item.get_background().lock().as_mut() {
+                            background.measure(MeasureMode::Specified(layout_params.width), MeasureMode::Specified(layout_params.height));
+                        }
+
+                        if let Some(foreground) = item.get_foreground().lock().as_mut() {
+                            foreground.measure(MeasureMode::Specified(layout_params.width), MeasureMode::Specified(layout_params.height));
+                        }
+
+                        item.set_layout_params(&layout_params);
+                    }
+                })
+                .set_layout_event({
+                    let properties = properties.clone();
+                    move |item, x, y| {
+                        let direction = item.get_layout_direction().get();
+                        let horizontal_gravity = item.get_horizontal_gravity().get();
+                        let vertical_gravity = item.get_vertical_gravity().get();
+
+                        let mut layout_params = item.get_layout_params().clone();
+                        layout_params.relative_x = x;
+                        layout_params.relative_y = y;
+                        item.set_layout_params(&layout_params);
+
+                        if let Some(background) = item.get_background().lock().as_mut() {
+                            background.layout(x, y);
+                        }
+
+                        if let Some(foreground) = item.get_foreground().lock().as_mut() {
+                            foreground.layout(x, y);
+                        }
+
+                        let properties = properties.lock().unwrap();
+                        let row_gap = properties.row_gap.get();
+                        let column_gap = properties.column_gap.get();
+
+                        item.get_children().lock().iter_mut().zip(properties.placements.iter()).for_each(|(child, placement)| {
+                            let child_layout_params = child.get_layout_params().clone();
+                            let occupied_width = child_layout_params.width + child_layout_params.margin_start + child_layout_params.margin_end;
+                            let occupied_height = child_layout_params.height + child_layout_params.margin_top + child_layout_params.margin_bottom;
+
+                            let cell_x = span_size(&properties.column_sizes, 0, placement.column, column_gap) + if placement.column > 0 { column_gap } else { 0.0 };
+                            let cell_y = span_size(&properties.row_sizes, 0, placement.row, row_gap) + if placement.row > 0 { row_gap } else { 0.0 };
+                            let cell_width = span_size(&properties.column_sizes, placement.column, placement.column_span, column_gap);
+                            let cell_height = span_size(&properties.row_sizes, placement.row, placement.row_span, row_gap);
+
+                            let child_x = LogicalX::new(
+                                direction,
+                                0.0,
+                                layout_params.padding_start + cell_x + child_layout_params.margin_start + gravity_offset(horizontal_gravity, cell_width - occupied_width),
+                                layout_params.width,
+                            ).physical_value(child_layout_params.width);
+                            let child_y = layout_params.padding_top + cell_y + child_layout_params.margin_top + gravity_offset(vertical_gravity, cell_height - occupied_height);
+                            child.layout(child_x, child_y);
+                        });
+                    }
+                })
+        );
+        item.set_children(children_property(children));
+        Self {
+            item,
+            properties,
+        }
+    }
+
+    pub fn rows(self, rows: Vec<GridTrack>) -> Self {
+        self.properties.lock().unwrap().rows = rows;
+        self.item.get_layout_node().invalidate();
+        self
+    }
+
+    pub fn columns(self, columns: Vec<GridTrack>) -> Self {
+        self.properties.lock().unwrap().columns = columns;
+        self.item.get_layout_node().invalidate();
+        self
+    }
+
+    pub fn row_gap(mut self, row_gap: impl Into<FloatProperty>) -> Self {
+        let row_gap = row_gap.into();
+        let layout_node = self.item.get_layout_node();
+        self.item.subscribe(&row_gap, move || {
+            layout_node.invalidate();
+        });
+        self.properties.lock().unwrap().row_gap = row_gap;
+        self
+    }
+
+    pub fn get_row_gap(&self) -> FloatProperty {
+        self.properties.lock().unwrap().row_gap.clone()
+    }
+
+    pub fn column_gap(mut self, column_gap: impl Into<FloatProperty>) -> Self {
+        let column_gap = column_gap.into();
+        let layout_node = self.item.get_layout_node();
+        self.item.subscribe(&column_gap, move || {
+            layout_node.invalidate();
+        });
+        self.properties.lock().unwrap().column_gap = column_gap;
+        self
+    }
+
+    pub fn get_column_gap(&self) -> FloatProperty {
+        self.properties.lock().unwrap().column_gap.clone()
+    }
+
+    pub fn unwrap(self) -> Item {
+        self.item
+    }
+}
+
+/// Children filling their cells are measured by their content while the tracks are sized.
+fn content_measure_mode(size: Size, measure_mode: MeasureMode) -> MeasureMode {
+    match size {
+        Size::Fill => MeasureMode::Unspecified(measure_mode.value()),
+        _ => measure_mode,
+    }
+}
+
+/// Find the cells of the children. Children with a row and a column keep them,
+/// the others take the next cells that are free, row by row.
+fn place_children<'a>(children: impl Iterator<Item = &'a Item>, column_count: usize) -> Vec<Placement> {
+    let children: Vec<&Item> = children.collect();
+    let mut occupied = HashSet::new();
+
+    let mut placements: Vec<Option<Placement>> = children.iter().map(|child| {
+        match (child.get_grid_row(), child.get_grid_column()) {
+            (Some(row), Some(column)) => Some(Placement {
+                row,
+                column,
+                row_span: child.get_grid_row_span(),
+                column_span: child.get_grid_column_span(),
+            }),
+            _ => None,
+        }
+    }).collect();
+    placements.iter().flatten().for_each(|placement| occupy(&mut occupied, placement));
+
+    let mut cursor = (0, 0);
+    for (index, child) in children.iter().enumerate() {
+        if placements[index].is_some() {
+            continue;
+        }
+        let row_span = child.get_grid_row_span();
+        let column_span = child.get_grid_column_span();
+        let is_free = |occupied: &HashSet<(usize, usize)>, row: usize, column: usize| {
+            (row..row + row_span).all(|row| (column..column + column_span).all(|column| !occupied.contains(&(row, column))))
+        };
+        let (mut row, mut column) = match (child.get_grid_row(), child.get_grid_column()) {
+            (Some(row), None) => (row, 0),
+            (None, Some(column)) => (0, column),
+            _ => cursor,
+        };
+        loop {
+            let fits = column == 0 || column + column_span <= column_count;
+            if fits && is_free(&occupied, row, column) {
+                break;
+            }
+            match (child.get_grid_row(), child.get_grid_column()) {
+                (None, Some(_)) => row += 1,
+                (Some(_), None) => column += 1,
+                _ => {
+                    column += 1;
+                    if column >= column_count {
+                        column = 0;
+                        row += 1;
+                    }
+                }
+            }
+        }
+        let placement = Placement { row, column, row_span, column_span };
+        occupy(&mut occupied, &placement);
+        placements[index] = Some(placement);
+        if child.get_grid_row().is_none() && child.get_grid_column().is_none() {
+            cursor = (row, column + column_span);
+        }
+    }
+    placements.into_iter().map(|placement| placement.unwrap()).collect()
+}
+
+fn occupy(occupied: &mut HashSet<(usize, usize)>, placement: &Placement) {
+    for row in placement.row..placement.row + placement.row_span {
+        for column in placement.column..placement.column + placement.column_span {
+            occupied.insert((row, column));
+        }
+    }
+}
+
+/// The defined tracks, followed by auto tracks up to `count`.
+fn tracks(defined: &[GridTrack], count: usize) -> Vec<GridTrack> {
+    let mut tracks = defined.to_vec();
+    tracks.resize(count.max(defined.len()), GridTrack::Auto);
+    tracks
+}
+
+/// The size of `span` tracks from `start`, with the gaps between them.
+fn span_size(sizes: &[f32], start: usize, span: usize, gap: f32) -> f32 {
+    let end = (start + span).min(sizes.len());
+    if start >= end {
+        return 0.0;
+    }
+    sizes[start..end].iter().sum::<f32>() + gap * (end - start - 1) as f32
+}
+
+/// Size the tracks for children given as (first track, span, size with margins).
+fn track_sizes(tracks: &[GridTrack], children: impl Iterator<Item = (usize, usize, f32)>, available: f32, gap: f32) -> Vec<f32> {
+    let definite = available.is_finite();
+    let is_auto = |track: &GridTrack| match track {
+        GridTrack::Auto => true,
+        GridTrack::Fraction(_) => !definite,
+        GridTrack::Fixed(_) => false,
+    };
+    let mut sizes: Vec<f32> = tracks.iter().map(|track| match track {
+        GridTrack::Fixed(size) => *size,
+        _ => 0.0,
+    }).collect();
+
+    // Children in a single track first, then the ones spanning tracks grow the auto tracks they span if needed.
+    let mut children: Vec<(usize, usize, f32)> = children.collect();
+    children.sort_by_key(|(_, span, _)| *span);
+    children.iter().for_each(|(start, span, size)| {
+        let end = (start + span).min(tracks.len());
+        if *span == 1 {
+            if is_auto(&tracks[*start]) {
+                sizes[*start] = sizes[*start].max(*size);
+            }
+            return;
+        }
+        // Spans over a definite fractional track are satisfied by the fractions.
+        if tracks[*start..end].iter().any(|track| matches!(track, GridTrack::Fraction(_)) && definite) {
+            return;
+        }
+        let auto_tracks: Vec<usize> = (*start..end).filter(|index| is_auto(&tracks[*index])).collect();
+        let missing = size - span_size(&sizes, *start, *span, gap);
+        if missing > 0.0 && !auto_tracks.is_empty() {
+            let share = missing / auto_tracks.len() as f32;
+            auto_tracks.iter().for_each(|index| sizes[*index] += share);
+        }
+    });
+
+    if definite {
+        let total_fraction: f32 = tracks.iter().map(|track| match track {
+            GridTrack::Fraction(fraction) => *fraction,
+            _ => 0.0,
+        }).sum();
+        if total_fraction > 0.0 {
+            let free = (available - span_size(&sizes, 0, sizes.len(), gap)).max(0.0);
+            tracks.iter().enumerate().for_each(|(index, track)| {
+                if let GridTrack::Fraction(fraction) = track {
+                    sizes[index] = free * fraction / total_fraction;
+                }
+            });
+        }
+    }
+    sizes
+}
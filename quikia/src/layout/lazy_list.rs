@@ -0,0 +1,452 @@
+use std::sync::{Arc, Mutex};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Instant;
+
+use skia_safe::Rect;
+
+use crate::app::SharedApp;
+use crate::layout::{Axis, init_child_margins};
+use crate::layout::scroll::{drag_scroller, draw_scrollbar, scrollbar_rect};
+use crate::property::{Gettable, SharedProperty, Size};
+use crate::ui::{clamp_size, Item, ItemEvent, LayoutNode, LogicalX, measure_child, MeasureMode, ScrollConfig, Scroller};
+
+/// The number of off-screen items kept for reuse.
+const MAX_RECYCLED_ITEMS: usize = 16;
+
+struct LazyListProperties {
+    count: SharedProperty<usize>,
+    builder: Box<dyn Fn(SharedApp, usize) -> Item>,
+    rebinder: Option<Box<dyn Fn(&mut Item, usize)>>,
+    fixed_item_height: Option<f32>,
+    estimated_item_height: f32,
+    /// The measured heights of the items, margins included. `None` for items that were never visible.
+    heights: Vec<Option<f32>>,
+    /// The tops of the rows in the content, only the first `valid_tops` are up to date.
+    tops: Vec<f32>,
+    valid_tops: usize,
+    /// The index of each child and the top of its row in the content.
+    visible: Vec<(usize, f32)>,
+    /// Off-screen items with the index they were built or bound for, the oldest first.
+    /// `None` for items that were built before the count changed, which are only reused by binding them again.
+    recycled: Vec<(Option<usize>, Item)>,
+    /// Set when the count changed: items were inserted or removed, so an index may stand for other data now.
+    count_changed: Arc<AtomicBool>,
+    scroller: Scroller,
+    scroll_to_index: Arc<Mutex<Option<usize>>>,
+}
+
+impl LazyListProperties {
+    fn item_height(&self, index: usize) -> f32 {
+        match self.fixed_item_height {
+            Some(height) => height,
+            None => self.heights.get(index).copied().flatten().unwrap_or(self.estimated_item_height),
+        }
+    }
+
+    /// Record the measured height of the item at `index`, the rows below it move if it changed.
+    fn set_item_height(&mut self, index: usize, height: f32) {
+        if self.heights[index] != Some(height) {
+            self.heights[index] = Some(height);
+            self.invalidate_tops(index + 1);
+        }
+    }
+
+    /// Resize the measured heights to `count` items.
+    fn set_count(&mut self, count: usize) {
+        if self.heights.len() != count {
+            self.invalidate_tops(self.heights.len().min(count) + 1);
+            self.heights.resize(count, None);
+        }
+    }
+
+    /// Mark the tops of the rows from `index` on as out of date.
+    fn invalidate_tops(&mut self, index: usize) {
+        self.valid_tops = self.valid_tops.min(index);
+    }
+
+    /// The top of the row of the item at `index` in the content.
+    fn item_top(&mut self, index: usize) -> f32 {
+        if let Some(height) = self.fixed_item_height {
+            return height * index as f32;
+        }
+        // Only the rows below the first height that changed are summed again.
+        while self.valid_tops <= index {
+            let row = self.valid_tops;
+            let top = if row == 0 { 0.0 } else { self.tops[row - 1] + self.item_height(row - 1) };
+            if row < self.tops.len() {
+                self.tops[row] = top;
+            } else {
+                self.tops.push(top);
+            }
+            self.valid_tops += 1;
+        }
+        self.tops[index]
+    }
+
+    fn content_height(&mut self) -> f32 {
+        self.item_top(self.heights.len())
+    }
+
+    /// The index of the row at `offset` in the content, or the number of items if the content is shorter.
+    fn row_at(&mut self, offset: f32) -> usize {
+        let count = self.heights.len();
+        match self.fixed_item_height {
+            Some(height) if height > 0.0 => ((offset / height).floor().max(0.0) as usize).min(count),
+            Some(_) => 0,
+            None => {
+                self.item_top(count);
+                self.tops[..=count].partition_point(|top| *top <= offset).saturating_sub(1)
+            }
+        }
+    }
+
+    /// Forget the index the built items were built or bound for, they are only reused by binding them again.
+    fn unbind_items(&mut self, children: Vec<(usize, Item)>) {
+        if self.rebinder.is_none() {
+            self.recycled.clear();
+            return;
+        }
+        self.recycled.iter_mut().for_each(|(index, _)| *index = None);
+        self.recycled.extend(children.into_iter().map(|(_, item)| (None, item)));
+    }
+
+    /// Take the item built for `index`, or an off-screen item bound again to `index`, or build a new one.
+    fn obtain_item(&mut self, app: &SharedApp, old_children: &mut Vec<(usize, Item)>, index: usize) -> Item {
+        if let Some(position) = old_children.iter().position(|(old_index, _)| *old_index == index) {
+            return old_children.remove(position).1;
+        }
+        if let Some(position) = self.recycled.iter().position(|(old_index, _)| *old_index == Some(index)) {
+            return self.recycled.remove(position).1;
+        }
+        if let Some(rebinder) = &self.rebinder {
+            if !self.recycled.is_empty() {
+                let (_, mut item) = self.recycled.remove(0);
+                rebinder(&mut item, index);
+                return item;
+            }
+        }
+        (self.builder)(app.clone(), index)
+    }
+}
+
+/// Lets the owner of a [`LazyList`] scroll it after it was added to the item tree.
+#[derive(Clone)]
+pub struct LazyListController {
+    layout_node: LayoutNode,
+    scroll_to_index: Arc<Mutex<Option<usize>>>,
+}
+
+impl LazyListController {
+    /// Scroll so that the item at `index` is at the top of the list, or as close as the end of the list allows.
+    pub fn scroll_to_index(&self, index: usize) {
+        *self.scroll_to_index.lock().unwrap() = Some(index);
+        self.layout_node.invalidate();
+    }
+}
+
+/// A vertical list that only builds the items intersecting its viewport, for lists too long to build up front.
+///
+/// Items are built with `builder` when they scroll into view. Items scrolled out of view are kept for a while,
+/// and reused when their index comes back, or bound to another index if [`LazyList::recycle`] is set.
+/// When the count changes, items may have moved to other indices, so the items are built or bound again.
+/// Rows have the height of their item unless [`LazyList::fixed_item_height`] is set,
+/// rows that were never visible are assumed to be [`LazyList::estimated_item_height`] tall.
+pub struct LazyList {
+    item: Item,
+    properties: Arc<Mutex<LazyListProperties>>,
+}
+
+impl LazyList {
+    pub fn new(app: SharedApp, count: impl Into<SharedProperty<usize>>, builder: impl Fn(SharedApp, usize) -> Item + 'static) -> Self {
+        let count = count.into();
+        let count_clone = count.clone();
+        let count_changed = Arc::new(AtomicBool::new(false));
+        let properties = Arc::new(Mutex::new(LazyListProperties {
+            count,
+            builder: Box::new(builder),
+            rebinder: None,
+            fixed_item_height: None,
+            estimated_item_height: 48.0,
+            heights: Vec::new(),
+            tops: Vec::new(),
+            valid_tops: 0,
+            visible: Vec::new(),
+            recycled: Vec::new(),
+            count_changed: count_changed.clone(),
+            scroller: Scroller::default(),
+            scroll_to_index: Arc::new(Mutex::new(None)),
+        }));
+
+        let mut item = Item::new(
+            app,
+            ItemEvent::default()
+                .set_draw_event({
+                    let properties = properties.clone();
+                    move |item, canvas| {
+                        let now = Instant::now();
+                        let layout_params = item.get_layout_params().clone();
+                        let direction = item.get_layout_direction().get();
+
+                        let mut properties_guard = properties.lock().unwrap();
+                        let animating = properties_guard.scroller.advance(now);
+                        let scrollbar_alpha = properties_guard.scroller.scrollbar_alpha(now);
+                        let viewport_height = layout_params.height - layout_params.padding_top - layout_params.padding_bottom;
+                        let content_height = properties_guard.content_height();
+                        let scrollbar = scrollbar_rect(&properties_guard.scroller, content_height, viewport_height, &layout_params, Axis::Vertical, direction);
+                        drop(properties_guard);
+
+                        if animating {
+                            // The visible items depend on the offset, so each frame of a fling needs a new layout.
+                            // The redraw is requested first, a redraw requested after a layout is dropped while drawing.
+                            item.get_app().request_redraw();
+                            item.invalidate_layout();
+                        } else if scrollbar_alpha > 0.0 {
+                            item.get_app().request_redraw();
+                        }
+
+                        if let Some(background) = item.get_background().lock().as_mut() {
+                            let background_layout_params = background.get_layout_params_mut();
+                            background_layout_params.parent_x = layout_params.parent_x;
+                            background_layout_params.parent_y = layout_params.parent_y;
+                            background.draw(canvas);
+                        }
+
+                        item.on_draw(canvas);
+
+                        canvas.save();
+                        if item.get_enable_clipping().get() {
+                            canvas.clip_rect(Rect::from_xywh(layout_params.x(), layout_params.y(), layout_params.width, layout_params.height), None, None);
+                        }
+                        item.get_children().lock().z_ordered_mut().into_iter().for_each(|child| {
+                            let child_layout_params = child.get_layout_params_mut();
+                            child_layout_params.parent_x = layout_params.x();
+                            child_layout_params.parent_y = layout_params.y();
+                            child.draw(canvas);
+                        });
+                        canvas.restore();
+
+                        if let Some(scrollbar) = scrollbar {
+                            draw_scrollbar(canvas, &item.get_app(), scrollbar, scrollbar_alpha);
+                        }
+
+                        if let Some(foreground) = item.get_foreground().lock().as_mut() {
+                            let foreground_layout_params = foreground.get_layout_params_mut();
+                            foreground_layout_params.parent_x = layout_params.parent_x;
+                            foreground_layout_params.parent_y = layout_params.parent_y;
+                            foreground.draw(canvas);
+                        }
+                    }
+                })
+                .set_measure_event({
+                    let properties = properties.clone();
+                    move |item, width_measure_mode, height_measure_mode| {
+                        let app = item.get_app();
+                        let mut layout_params = item.get_layout_params().clone();
+                        layout_params.init_from_item(item);
+                        let vertical_padding = layout_params.padding_top + layout_params.padding_bottom;
+
+                        let mut properties = properties.lock().unwrap();
+                        let count = properties.count.get();
+                        properties.set_count(count);
+                        let count_changed = properties.count_changed.swap(false, Ordering::Relaxed);
+
+                        // The list takes all the width, its items can not be measured all to find the widest one.
+                        // Without a width to take, it is as wide as its widest visible item.
+                        let width_is_bounded = width_measure_mode.value().is_finite();
+                        layout_params.width = if width_is_bounded {
+                            clamp_size(width_measure_mode.value(), layout_params.min_width, layout_params.max_width)
+                        } else {
+                            f32::INFINITY
+                        };
+                        let viewport_height = clamp_size(match height_measure_mode {
+                            MeasureMode::Specified(height) => height,
+                            MeasureMode::Unspecified(height) => height.min(properties.content_height() + vertical_padding),
+                        }, layout_params.min_height, layout_params.max_height) - vertical_padding;
+
+                        let scroll_to_index = properties.scroll_to_index.lock().unwrap().take();
+                        if let Some(index) = scroll_to_index {
+                            let max_offset = properties.content_height() - viewport_height;
+                            properties.scroller.set_max_offset(max_offset);
+                            let top = properties.item_top(index.min(count));
+                            properties.scroller.scroll_to(top, Instant::now());
+                        }
+                        let offset = properties.scroller.offset();
+
+                        // Find the first row that reaches into the viewport.
+                        let mut index = properties.row_at(offset);
+                        let mut top = properties.item_top(index);
+                        while index < count && top + properties.item_height(index) <= offset {
+                            top += properties.item_height(index);
+                            index += 1;
+                        }
+
+                        let mut old_children = Vec::new();
+                        let mut visible = Vec::new();
+                        let mut content_width: f32 = 0.0;
+                        // The children are swapped at once, so that observers of the collection are notified once.
+                        item.get_children().lock().replace_all(|current_children| {
+                            old_children = properties.visible.iter().map(|(index, _)| *index)
+                                .zip(current_children)
+                                .collect();
+                            if count_changed {
+                                properties.unbind_items(std::mem::take(&mut old_children));
+                            }
+                            let mut new_children = Vec::new();
+
+                            while index < count && top < offset + viewport_height {
+                                let mut child = properties.obtain_item(&app, &mut old_children, index);
+                                init_child_margins(&mut child);
+                                let child_width_measure_mode = if width_is_bounded { MeasureMode::Specified(layout_params.width) } else { width_measure_mode };
+                                let (child_width_measure_mode, mut child_height_measure_mode) = measure_child(&child, &layout_params, child_width_measure_mode, MeasureMode::Unspecified(f32::INFINITY));
+                                let child_layout_params = child.get_layout_params();
+                                let vertical_margin = child_layout_params.margin_top + child_layout_params.margin_bottom;
+                                match properties.fixed_item_height {
+                                    Some(height) => child_height_measure_mode = MeasureMode::Specified((height - vertical_margin).max(0.0)),
+                                    None => {
+                                        if let Size::Fill = child.get_height().get() {
+                                            child_height_measure_mode = MeasureMode::Unspecified(f32::INFINITY);
+                                        }
+                                    }
+                                }
+                                child.measure(child_width_measure_mode, child_height_measure_mode);
+
+                                let child_layout_params = child.get_layout_params();
+                                content_width = content_width.max(child_layout_params.width + child_layout_params.margin_start + child_layout_params.margin_end);
+                                if properties.fixed_item_height.is_none() {
+                                    properties.set_item_height(index, child_layout_params.height + vertical_margin);
+                                }
+                                visible.push((index, top));
+                                new_children.push(child);
+                                top += properties.item_height(index);
+                                index += 1;
+                            }
+                            new_children
+                        });
+
+                        if !width_is_bounded {
+                            layout_params.width = clamp_size(content_width + layout_params.padding_start + layout_params.padding_end, layout_params.min_width, layout_params.max_width);
+                        }
+
+                        properties.visible = visible;
+                        let recycled = &mut properties.recycled;
+                        recycled.extend(old_children.into_iter().filter(|(index, _)| *index < count).map(|(index, item)| (Some(index), item)));
+                        if recycled.len() > MAX_RECYCLED_ITEMS {
+                            recycled.drain(..recycled.len() - MAX_RECYCLED_ITEMS);
+                        }
+
+                        let max_offset = properties.content_height() - viewport_height;
+                        properties.scroller.set_max_offset(max_offset);
+                        drop(properties);
+
+                        layout_params.height = viewport_height + vertical_padding;
+
+                        if let Some(background) = item.get_background().lock().as_mut() {
+                            background.measure(MeasureMode::Specified(layout_params.width), MeasureMode::Specified(layout_params.height));
+                        }
+
+                        if let Some(foreground) = item.get_foreground().lock().as_mut() {
+                            foreground.measure(MeasureMode::Specified(layout_params.width), MeasureMode::Specified(layout_params.height));
+                        }
+
+                        item.set_layout_params(&layout_params);
+                    }
+                })
+                .set_layout_event({
+                    let properties = properties.clone();
+                    move |item, x, y| {
+                        let direction = item.get_layout_direction().get();
+
+                        let mut layout_params = item.get_layout_params().clone();
+                        layout_params.relative_x = x;
+                        layout_params.relative_y = y;
+                        item.set_layout_params(&layout_params);
+
+                        if let Some(background) = item.get_background().lock().as_mut() {
+                            background.layout(x, y);
+                        }
+
+                        if let Some(foreground) = item.get_foreground().lock().as_mut() {
+                            foreground.layout(x, y);
+                        }
+
+                        let properties = properties.lock().unwrap();
+                        let offset = properties.scroller.offset();
+                        item.get_children().lock().iter_mut().zip(properties.visible.iter()).for_each(|(child, (_, top))| {
+                            let child_layout_params = child.get_layout_params().clone();
+                            let child_x = LogicalX::new(direction, 0.0, layout_params.padding_start + child_layout_params.margin_start, layout_params.width)
+                                .physical_value(child_layout_params.width);
+                            let child_y = layout_params.padding_top + top - offset + child_layout_params.margin_top;
+                            child.layout(child_x, child_y);
+                        });
+                    }
+                })
+                .set_on_mouse_wheel({
+                    let properties = properties.clone();
+                    move |item, _, delta_y| {
+                        let scrolled = properties.lock().unwrap().scroller.scroll_by(-delta_y, Instant::now());
+                        if scrolled {
+                            item.invalidate_layout();
+                        }
+                        scrolled
+                    }
+                })
+                .set_on_pointer_input({
+                    let properties = properties.clone();
+                    move |item, action| {
+                        let direction = item.get_layout_direction().get();
+                        let handled = drag_scroller(&mut properties.lock().unwrap().scroller, item, action, Axis::Vertical, direction);
+                        if handled {
+                            item.invalidate_layout();
+                        }
+                        handled
+                    }
+                })
+        ).enable_clipping(true);
+        let layout_node = item.get_layout_node();
+        item.subscribe(&count_clone, move || {
+            count_changed.store(true, Ordering::Relaxed);
+            layout_node.invalidate();
+        });
+
+        LazyList {
+            item,
+            properties,
+        }
+    }
+
+    /// Give all rows the same height, the items are not measured to find it.
+    pub fn fixed_item_height(self, height: f32) -> Self {
+        self.properties.lock().unwrap().fixed_item_height = Some(height);
+        self.item.get_layout_node().invalidate();
+        self
+    }
+
+    /// The height assumed for rows that were never visible, when the rows do not have a fixed height.
+    pub fn estimated_item_height(self, height: f32) -> Self {
+        self.properties.lock().unwrap().estimated_item_height = height;
+        self.item.get_layout_node().invalidate();
+        self
+    }
+
+    /// Bind off-screen items to another index instead of building new items.
+    pub fn recycle(self, rebinder: impl Fn(&mut Item, usize) + 'static) -> Self {
+        self.properties.lock().unwrap().rebinder = Some(Box::new(rebinder));
+        self
+    }
+
+    pub fn scroll_config(self, scroll_config: ScrollConfig) -> Self {
+        self.properties.lock().unwrap().scroller.set_config(scroll_config);
+        self
+    }
+
+    pub fn get_controller(&self) -> LazyListController {
+        LazyListController {
+            layout_node: self.item.get_layout_node(),
+            scroll_to_index: self.properties.lock().unwrap().scroll_to_index.clone(),
+        }
+    }
+
+    pub fn unwrap(self) -> Item {
+        self.item
+    }
+}
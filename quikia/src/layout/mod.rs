@@ -1,9 +1,48 @@
-// // mod axis_layout;
-// mod stack;
-// // mod flow;
-// mod flex_layout;
-// 
-// // pub use axis_layout::*;
-// pub use stack::*;
-// // pub use flow::*;
-// pub use flex_layout::*;
\ No newline at end of file
+mod axis_layout;
+mod stack;
+mod flow;
+mod flex_layout;
+mod scroll;
+mod lazy_list;
+mod grid;
+mod constraint_layout;
+mod overlay;
+
+pub use axis_layout::*;
+pub use stack::*;
+pub use flow::*;
+pub use flex_layout::*;
+pub use scroll::*;
+pub use lazy_list::*;
+pub use grid::*;
+pub use constraint_layout::*;
+pub use overlay::*;
+
+use crate::property::{Gettable, ItemCollection, ItemCollectionProperty};
+use crate::ui::{Gravity, Item};
+
+fn children_property(children: Vec<Item>) -> ItemCollectionProperty {
+    let mut item_collection = ItemCollection::new();
+    children.into_iter().for_each(|child| item_collection.add(child));
+    ItemCollectionProperty::from_value(item_collection)
+}
+
+/// Copy the margins of the child into its layout params before it is measured,
+/// [`measure_child`](crate::ui::measure_child) reads them from there.
+fn init_child_margins(child: &mut Item) {
+    let mut layout_params = child.get_layout_params().clone();
+    layout_params.margin_start = child.get_margin_start().get();
+    layout_params.margin_top = child.get_margin_top().get();
+    layout_params.margin_end = child.get_margin_end().get();
+    layout_params.margin_bottom = child.get_margin_bottom().get();
+    child.set_layout_params(&layout_params);
+}
+
+/// The offset of a child inside the free space left along one direction.
+fn gravity_offset(gravity: Gravity, free_space: f32) -> f32 {
+    match gravity {
+        Gravity::Start => 0.0,
+        Gravity::Center => free_space / 2.0,
+        Gravity::End => free_space,
+    }
+}
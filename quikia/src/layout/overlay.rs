@@ -0,0 +1,145 @@
+use crate::app::SharedApp;
+use crate::layout::{children_property, gravity_offset, init_child_margins};
+use crate::property::{Gettable, Size};
+use crate::ui::{clamp_size, Item, ItemEvent, LayoutParams, LogicalX, measure_child, MeasureMode};
+use crate::ui::additional_property::AbsolutePlacement;
+
+#[macro_export]
+macro_rules! overlay {
+    ($($child:expr),+ $(,)?) => {
+        {
+            let children = vec![$($child),*];
+            let app = children.first().unwrap().get_app().clone();
+            $crate::layout::Overlay::new(app, children)
+        }
+    }
+}
+
+/// Places children on top of each other like a [`Stack`](crate::layout::Stack),
+/// except for children with [`AbsolutePlacement`] offsets, such as badges and floating buttons.
+///
+/// Those are positioned from the edges of the overlay, ignoring its padding, and do not affect its size.
+/// On an axis without an offset, they are aligned by the gravity of the overlay.
+pub struct Overlay {
+    item: Item,
+}
+
+impl Overlay {
+    pub fn new(app: SharedApp, children: Vec<Item>) -> Self {
+        let mut item = Item::new(
+            app,
+            ItemEvent::default()
+                .set_measure_event(
+                    |item, width_measure_mode, height_measure_mode| {
+                        let mut layout_params = item.get_layout_params().clone();
+                        layout_params.init_from_item(item);
+
+                        let mut content_width = 0.0_f32;
+                        let mut content_height = 0.0_f32;
+
+                        item.get_children().lock().iter_mut().filter(|child| !child.is_absolute()).for_each(|child| {
+                            init_child_margins(child);
+                            let (child_width_measure_mode, child_height_measure_mode) = measure_child(child, &layout_params, width_measure_mode, height_measure_mode);
+                            child.measure(child_width_measure_mode, child_height_measure_mode);
+
+                            let child_layout_params = child.get_layout_params();
+                            content_width = content_width.max(child_layout_params.width + child_layout_params.margin_start + child_layout_params.margin_end);
+                            content_height = content_height.max(child_layout_params.height + child_layout_params.margin_top + child_layout_params.margin_bottom);
+                        });
+
+                        layout_params.width = clamp_size(match width_measure_mode {
+                            MeasureMode::Specified(width) => width,
+                            MeasureMode::Unspecified(width) => width.min(content_width + layout_params.padding_start + layout_params.padding_end),
+                        }, layout_params.min_width, layout_params.max_width);
+
+                        layout_params.height = clamp_size(match height_measure_mode {
+                            MeasureMode::Specified(height) => height,
+                            MeasureMode::Unspecified(height) => height.min(content_height + layout_params.padding_top + layout_params.padding_bottom),
+                        }, layout_params.min_height, layout_params.max_height);
+
+                        // Absolute children get the space between their offsets, the padding of the overlay does not apply.
+                        let edges = LayoutParams::default();
+                        item.get_children().lock().iter_mut().filter(|child| child.is_absolute()).for_each(|child| {
+                            init_child_margins(child);
+                            let start = child.get_offset_start();
+                            let end = child.get_offset_end();
+                            let top = child.get_offset_top();
+                            let bottom = child.get_offset_bottom();
+                            let available_width = layout_params.width - start.unwrap_or(0.0) - end.unwrap_or(0.0);
+                            let available_height = layout_params.height - top.unwrap_or(0.0) - bottom.unwrap_or(0.0);
+                            let (mut child_width_measure_mode, mut child_height_measure_mode) = measure_child(child, &edges, MeasureMode::Specified(available_width), MeasureMode::Specified(available_height));
+                            if start.is_some() && end.is_some() && matches!(child.get_width().get(), Size::Default) {
+                                child_width_measure_mode = MeasureMode::Specified(child_width_measure_mode.value());
+                            }
+                            if top.is_some() && bottom.is_some() && matches!(child.get_height().get(), Size::Default) {
+                                child_height_measure_mode = MeasureMode::Specified(child_height_measure_mode.value());
+                            }
+                            child.measure(child_width_measure_mode, child_height_measure_mode);
+                        });
+
+                        if let Some(background) = item.get_background().lock().as_mut() {
+                            background.measure(MeasureMode::Specified(layout_params.width), MeasureMode::Specified(layout_params.height));
+                        }
+
+                        if let Some(foreground) = item.get_foreground().lock().as_mut() {
+                            foreground.measure(MeasureMode::Specified(layout_params.width), MeasureMode::Specified(layout_params.height));
+                        }
+
+                        item.set_layout_params(&layout_params);
+                    }
+                )
+                .set_layout_event(
+                    |item, x, y| {
+                        let horizontal_gravity = item.get_horizontal_gravity().get();
+                        let vertical_gravity = item.get_vertical_gravity().get();
+                        let direction = item.get_layout_direction().get();
+
+                        let mut layout_params = item.get_layout_params().clone();
+                        layout_params.relative_x = x;
+                        layout_params.relative_y = y;
+                        item.set_layout_params(&layout_params);
+
+                        if let Some(background) = item.get_background().lock().as_mut() {
+                            background.layout(x, y);
+                        }
+
+                        if let Some(foreground) = item.get_foreground().lock().as_mut() {
+                            foreground.layout(x, y);
+                        }
+
+                        let content_width = layout_params.width - layout_params.padding_start - layout_params.padding_end;
+                        let content_height = layout_params.height - layout_params.padding_top - layout_params.padding_bottom;
+
+                        item.get_children().lock().iter_mut().for_each(|child| {
+                            let child_layout_params = child.get_layout_params().clone();
+                            let occupied_width = child_layout_params.width + child_layout_params.margin_start + child_layout_params.margin_end;
+                            let occupied_height = child_layout_params.height + child_layout_params.margin_top + child_layout_params.margin_bottom;
+
+                            let logical_x = match (child.get_offset_start(), child.get_offset_end()) {
+                                (Some(start), _) => start + child_layout_params.margin_start,
+                                (None, Some(end)) => layout_params.width - end - child_layout_params.margin_end - child_layout_params.width,
+                                (None, None) => layout_params.padding_start + child_layout_params.margin_start + gravity_offset(horizontal_gravity, content_width - occupied_width),
+                            };
+                            let child_x = LogicalX::new(direction, 0.0, logical_x, layout_params.width).physical_value(child_layout_params.width);
+                            let child_y = match (child.get_offset_top(), child.get_offset_bottom()) {
+                                (Some(top), _) => top + child_layout_params.margin_top,
+                                (None, Some(bottom)) => layout_params.height - bottom - child_layout_params.margin_bottom - child_layout_params.height,
+                                (None, None) => layout_params.padding_top + child_layout_params.margin_top + gravity_offset(vertical_gravity, content_height - occupied_height),
+                            };
+
+                            child.layout(child_x, child_y);
+                        });
+                    }
+                )
+        );
+        item.set_children(children_property(children));
+
+        Overlay {
+            item,
+        }
+    }
+
+    pub fn unwrap(self) -> Item {
+        self.item
+    }
+}
@@ -0,0 +1,354 @@
+use std::sync::{Arc, Mutex};
+use std::time::Instant;
+
+use skia_safe::{Canvas, Paint, Rect, RRect};
+
+use crate::app::{SharedApp, ThemeColor};
+use crate::layout::{Axis, AxisProperty, children_property, init_child_margins};
+use crate::property::{Gettable, Size};
+use crate::ui::{clamp_size, Item, ItemEvent, LayoutDirection, LayoutParams, LogicalX, measure_child, MeasureMode, PointerAction, PointerType, ScrollConfig, Scroller};
+
+#[macro_export]
+macro_rules! scroll {
+    ($child:expr) => {
+        {
+            let child = $child;
+            let app = child.get_app().clone();
+            $crate::layout::Scroll::new(app, child)
+        }
+    }
+}
+
+const SCROLLBAR_THICKNESS: f32 = 4.0;
+/// The distance between the scrollbar and the edges of the scroll.
+const SCROLLBAR_MARGIN: f32 = 2.0;
+const SCROLLBAR_MIN_LENGTH: f32 = 24.0;
+
+struct ScrollProperties {
+    axis: AxisProperty,
+    scroller: Scroller,
+    /// The size of the child along the axis, margins included.
+    content_size: f32,
+    /// The size of the scroll along the axis, paddings excluded.
+    viewport_size: f32,
+}
+
+/// Shows a child larger than itself along an axis, and scrolls it with the mouse wheel or by dragging with a touch.
+/// Dragging with the mouse does not scroll on purpose, so mouse drags stay available to the content, e.g. for selecting text.
+///
+/// The child is measured with [`MeasureMode::Unspecified`] along the axis unless its size there is fixed or relative.
+/// Descendants that fill that axis take the size of their content, see [`measure_child`].
+/// A released drag keeps scrolling with a decelerating fling, and the content bounces back when it was pulled past its ends.
+pub struct Scroll {
+    item: Item,
+    properties: Arc<Mutex<ScrollProperties>>,
+}
+
+impl Scroll {
+    pub fn new(app: SharedApp, child: Item) -> Self {
+        let properties = Arc::new(Mutex::new(ScrollProperties {
+            axis: Axis::Vertical.into(),
+            scroller: Scroller::default(),
+            content_size: 0.0,
+            viewport_size: 0.0,
+        }));
+
+        let mut item = Item::new(
+            app,
+            ItemEvent::default()
+                .set_draw_event({
+                    let properties = properties.clone();
+                    move |item, canvas| {
+                        let now = Instant::now();
+                        let layout_params = item.get_layout_params().clone();
+                        let direction = item.get_layout_direction().get();
+
+                        let mut properties_guard = properties.lock().unwrap();
+                        let animating = properties_guard.scroller.advance(now);
+                        let scrollbar_alpha = properties_guard.scroller.scrollbar_alpha(now);
+                        let axis = properties_guard.axis.get();
+                        let offset = properties_guard.scroller.offset();
+                        let scrollbar = scrollbar_rect(&properties_guard.scroller, properties_guard.content_size, properties_guard.viewport_size, &layout_params, axis, direction);
+                        drop(properties_guard);
+
+                        if animating || scrollbar_alpha > 0.0 {
+                            item.get_app().request_redraw();
+                        }
+
+                        if let Some(background) = item.get_background().lock().as_mut() {
+                            let background_layout_params = background.get_layout_params_mut();
+                            background_layout_params.parent_x = layout_params.parent_x;
+                            background_layout_params.parent_y = layout_params.parent_y;
+                            background.draw(canvas);
+                        }
+
+                        item.on_draw(canvas);
+
+                        canvas.save();
+                        if item.get_enable_clipping().get() {
+                            canvas.clip_rect(Rect::from_xywh(layout_params.x(), layout_params.y(), layout_params.width, layout_params.height), None, None);
+                        }
+                        let (offset_x, offset_y) = match (axis, direction) {
+                            (Axis::Horizontal, LayoutDirection::LeftToRight) => (offset, 0.0),
+                            (Axis::Horizontal, LayoutDirection::RightToLeft) => (-offset, 0.0),
+                            (Axis::Vertical, _) => (0.0, offset),
+                        };
+                        item.get_children().lock().z_ordered_mut().into_iter().for_each(|child| {
+                            let child_layout_params = child.get_layout_params_mut();
+                            child_layout_params.parent_x = layout_params.x() - offset_x;
+                            child_layout_params.parent_y = layout_params.y() - offset_y;
+                            child.draw(canvas);
+                        });
+                        canvas.restore();
+
+                        if let Some(scrollbar) = scrollbar {
+                            draw_scrollbar(canvas, &item.get_app(), scrollbar, scrollbar_alpha);
+                        }
+
+                        if let Some(foreground) = item.get_foreground().lock().as_mut() {
+                            let foreground_layout_params = foreground.get_layout_params_mut();
+                            foreground_layout_params.parent_x = layout_params.parent_x;
+                            foreground_layout_params.parent_y = layout_params.parent_y;
+                            foreground.draw(canvas);
+                        }
+                    }
+                })
+                .set_measure_event({
+                    let properties = properties.clone();
+                    move |item, width_measure_mode, height_measure_mode| {
+                        let mut layout_params = item.get_layout_params().clone();
+                        layout_params.init_from_item(item);
+                        let axis = properties.lock().unwrap().axis.get();
+
+                        let mut content_width = 0.0_f32;
+                        let mut content_height = 0.0_f32;
+
+                        item.get_children().lock().iter_mut().for_each(|child| {
+                            init_child_margins(child);
+                            let (mut child_width_measure_mode, mut child_height_measure_mode) = measure_child(child, &layout_params, width_measure_mode, height_measure_mode);
+                            match axis {
+                                Axis::Horizontal => {
+                                    if let Size::Default | Size::Fill = child.get_width().get() {
+                                        child_width_measure_mode = MeasureMode::Unspecified(f32::INFINITY);
+                                    }
+                                }
+                                Axis::Vertical => {
+                                    if let Size::Default | Size::Fill = child.get_height().get() {
+                                        child_height_measure_mode = MeasureMode::Unspecified(f32::INFINITY);
+                                    }
+                                }
+                            }
+                            child.measure(child_width_measure_mode, child_height_measure_mode);
+
+                            let child_layout_params = child.get_layout_params();
+                            content_width = content_width.max(child_layout_params.width + child_layout_params.margin_start + child_layout_params.margin_end);
+                            content_height = content_height.max(child_layout_params.height + child_layout_params.margin_top + child_layout_params.margin_bottom);
+                        });
+
+                        let horizontal_padding = layout_params.padding_start + layout_params.padding_end;
+                        let vertical_padding = layout_params.padding_top + layout_params.padding_bottom;
+
+                        layout_params.width = clamp_size(match width_measure_mode {
+                            MeasureMode::Specified(width) => width,
+                            MeasureMode::Unspecified(width) => width.min(content_width + horizontal_padding),
+                        }, layout_params.min_width, layout_params.max_width);
+
+                        layout_params.height = clamp_size(match height_measure_mode {
+                            MeasureMode::Specified(height) => height,
+                            MeasureMode::Unspecified(height) => height.min(content_height + vertical_padding),
+                        }, layout_params.min_height, layout_params.max_height);
+
+                        let (content_size, viewport_size) = match axis {
+                            Axis::Horizontal => (content_width, layout_params.width - horizontal_padding),
+                            Axis::Vertical => (content_height, layout_params.height - vertical_padding),
+                        };
+                        let mut properties = properties.lock().unwrap();
+                        properties.content_size = content_size;
+                        properties.viewport_size = viewport_size;
+                        properties.scroller.set_max_offset(content_size - viewport_size);
+                        drop(properties);
+
+                        if let Some(background) = item.get_background().lock().as_mut() {
+                            background.measure(MeasureMode::Specified(layout_params.width), MeasureMode::Specified(layout_params.height));
+                        }
+
+                        if let Some(foreground) = item.get_foreground().lock().as_mut() {
+                            foreground.measure(MeasureMode::Specified(layout_params.width), MeasureMode::Specified(layout_params.height));
+                        }
+
+                        item.set_layout_params(&layout_params);
+                    }
+                })
+                .set_layout_event(
+                    |item, x, y| {
+                        let direction = item.get_layout_direction().get();
+
+                        let mut layout_params = item.get_layout_params().clone();
+                        layout_params.relative_x = x;
+                        layout_params.relative_y = y;
+                        item.set_layout_params(&layout_params);
+
+                        if let Some(background) = item.get_background().lock().as_mut() {
+                            background.layout(x, y);
+                        }
+
+                        if let Some(foreground) = item.get_foreground().lock().as_mut() {
+                            foreground.layout(x, y);
+                        }
+
+                        // The scroll offset is applied when drawing, so scrolling does not need a new layout.
+                        item.get_children().lock().iter_mut().for_each(|child| {
+                            let child_layout_params = child.get_layout_params().clone();
+                            let child_x = LogicalX::new(direction, 0.0, layout_params.padding_start + child_layout_params.margin_start, layout_params.width)
+                                .physical_value(child_layout_params.width);
+                            let child_y = layout_params.padding_top + child_layout_params.margin_top;
+                            child.layout(child_x, child_y);
+                        });
+                    }
+                )
+                .set_on_mouse_wheel({
+                    let properties = properties.clone();
+                    move |item, delta_x, delta_y| {
+                        let direction = item.get_layout_direction().get();
+                        let mut properties = properties.lock().unwrap();
+                        // The deltas move the content, the offset moves the viewport.
+                        let delta = match properties.axis.get() {
+                            Axis::Horizontal => {
+                                // A vertical wheel also scrolls horizontally.
+                                let delta = if delta_x != 0.0 { delta_x } else { delta_y };
+                                match direction {
+                                    LayoutDirection::LeftToRight => -delta,
+                                    LayoutDirection::RightToLeft => delta,
+                                }
+                            }
+                            Axis::Vertical => -delta_y,
+                        };
+                        let scrolled = properties.scroller.scroll_by(delta, Instant::now());
+                        drop(properties);
+                        if scrolled {
+                            item.get_app().request_redraw();
+                        }
+                        // When the end is reached, an outer scroll can take over.
+                        scrolled
+                    }
+                })
+                .set_on_pointer_input({
+                    let properties = properties.clone();
+                    move |item, action| {
+                        let mut properties = properties.lock().unwrap();
+                        let axis = properties.axis.get();
+                        let direction = item.get_layout_direction().get();
+                        let handled = drag_scroller(&mut properties.scroller, item, action, axis, direction);
+                        drop(properties);
+                        if handled {
+                            item.get_app().request_redraw();
+                        }
+                        handled
+                    }
+                })
+        ).enable_clipping(true);
+        item.set_children(children_property(vec![child]));
+
+        Scroll {
+            item,
+            properties,
+        }
+    }
+
+    pub fn axis(mut self, axis: impl Into<AxisProperty>) -> Self {
+        let axis = axis.into();
+        let layout_node = self.item.get_layout_node();
+        self.item.subscribe(&axis, move || {
+            layout_node.invalidate();
+        });
+        self.properties.lock().unwrap().axis = axis;
+        self
+    }
+
+    pub fn scroll_config(self, scroll_config: ScrollConfig) -> Self {
+        self.properties.lock().unwrap().scroller.set_config(scroll_config);
+        self
+    }
+
+    pub fn unwrap(self) -> Item {
+        self.item
+    }
+}
+
+/// Drag `scroller` with touches delivered to `item`. Returns whether the action was handled.
+/// Mouse drags are ignored, see [`Scroll`].
+pub(super) fn drag_scroller(scroller: &mut Scroller, item: &Item, action: PointerAction, axis: Axis, direction: LayoutDirection) -> bool {
+    let now = Instant::now();
+    // The position along the axis, growing towards the end of the content.
+    let position = |x: f32, y: f32| match (axis, direction) {
+        (Axis::Horizontal, LayoutDirection::LeftToRight) => x,
+        (Axis::Horizontal, LayoutDirection::RightToLeft) => -x,
+        (Axis::Vertical, _) => y,
+    };
+    match action {
+        // Only touches drag the content, the mouse uses the wheel.
+        PointerAction::Down { x, y, pointer_type: pointer_type @ PointerType::Touch { .. } } => {
+            if scroller.is_dragging() {
+                false
+            } else {
+                scroller.drag_start(pointer_type, position(x, y), now);
+                item.get_app().catch_pointer(pointer_type, item.get_id());
+                true
+            }
+        }
+        PointerAction::Move { x, y, pointer_type } => {
+            scroller.drag_update(pointer_type, position(x, y), now)
+        }
+        PointerAction::Up { pointer_type, .. } => {
+            scroller.drag_end(pointer_type, now)
+        }
+        PointerAction::Cancel => {
+            let dragging = scroller.is_dragging();
+            scroller.drag_cancel(now);
+            dragging
+        }
+        _ => false,
+    }
+}
+
+/// The bounds of the scrollbar thumb, or `None` if the content fits in the viewport.
+pub(super) fn scrollbar_rect(scroller: &Scroller, content_size: f32, viewport_size: f32, layout_params: &LayoutParams, axis: Axis, direction: LayoutDirection) -> Option<Rect> {
+    let max_offset = scroller.max_offset();
+    if max_offset <= 0.0 || content_size <= 0.0 {
+        return None;
+    }
+    let track_length = match axis {
+        Axis::Horizontal => layout_params.width,
+        Axis::Vertical => layout_params.height,
+    } - SCROLLBAR_MARGIN * 2.0;
+    let thumb_length = (track_length * viewport_size / content_size).clamp(SCROLLBAR_MIN_LENGTH.min(track_length), track_length);
+    let progress = (scroller.offset() / max_offset).clamp(0.0, 1.0);
+    let thumb_start = SCROLLBAR_MARGIN + (track_length - thumb_length) * progress;
+
+    let rect = match axis {
+        Axis::Horizontal => {
+            let x = LogicalX::new(direction, 0.0, thumb_start, layout_params.width).physical_value(thumb_length);
+            let y = layout_params.height - SCROLLBAR_MARGIN - SCROLLBAR_THICKNESS;
+            Rect::from_xywh(layout_params.x() + x, layout_params.y() + y, thumb_length, SCROLLBAR_THICKNESS)
+        }
+        Axis::Vertical => {
+            // The scrollbar is on the end side.
+            let x = LogicalX::new(direction, 0.0, layout_params.width - SCROLLBAR_MARGIN - SCROLLBAR_THICKNESS, layout_params.width).physical_value(SCROLLBAR_THICKNESS);
+            Rect::from_xywh(layout_params.x() + x, layout_params.y() + thumb_start, SCROLLBAR_THICKNESS, thumb_length)
+        }
+    };
+    Some(rect)
+}
+
+pub(super) fn draw_scrollbar(canvas: &Canvas, app: &SharedApp, rect: Rect, alpha: f32) {
+    if alpha <= 0.0 {
+        return;
+    }
+    let color = app.lock().unwrap().theme().get_color(ThemeColor::OnSurfaceVariant);
+    let color = color.with_a((color.a() as f32 * alpha) as u8);
+    let mut paint = Paint::default();
+    paint.set_anti_alias(true);
+    paint.set_color(color);
+    let radius = SCROLLBAR_THICKNESS / 2.0;
+    canvas.draw_rrect(RRect::new_rect_xy(rect, radius, radius), &paint);
+}
@@ -1,14 +1,20 @@
 use crate::app::SharedApp;
+use crate::layout::{children_property, gravity_offset, init_child_margins};
 use crate::property::Gettable;
-use crate::ui::{Gravity, Item, ItemEvent, LogicalX, measure_child, MeasureMode};
+use crate::ui::{clamp_size, Item, ItemEvent, LogicalX, measure_child, MeasureMode};
 
 #[macro_export]
 macro_rules! stack {
-    ($($child:expr)*) => {
-        $crate::layout::Stack::new(vec![$($child),*])
+    ($($child:expr),+ $(,)?) => {
+        {
+            let children = vec![$($child),*];
+            let app = children.first().unwrap().get_app().clone();
+            $crate::layout::Stack::new(app, children)
+        }
     }
 }
 
+/// Places all children on top of each other, aligned by the gravity of the stack.
 pub struct Stack {
     item: Item,
 }
@@ -18,80 +24,54 @@ impl Stack {
         let mut item = Item::new(
             app,
             ItemEvent::default()
-                .set_on_measure(
+                .set_measure_event(
                     |item, width_measure_mode, height_measure_mode| {
-                        let mut layout_params = item.get_layout_params_mut().clone();
+                        let mut layout_params = item.get_layout_params().clone();
+                        layout_params.init_from_item(item);
 
-                        let mut width = 0.0;
-                        let mut height = 0.0_f32;
-
-                        let mut remaining_width = match width_measure_mode {
-                            MeasureMode::Specified(width) => width,
-                            MeasureMode::Unspecified(width) => width,
-                        };
-
-                        item.get_children_mut().iter_mut().for_each(|child| {
-                            let width_measure_mode = match width_measure_mode {
-                                MeasureMode::Specified(_) => MeasureMode::Specified(remaining_width),
-                                MeasureMode::Unspecified(_) => MeasureMode::Unspecified(remaining_width),
-                            };
-
-                            let child_occupied_width;
-                            let (child_width_measure_mode, child_height_measure_mode) = measure_child(child,&layout_params, width_measure_mode, height_measure_mode);
-
-                            let mut child_layout_params = child.get_layout_params().clone();
-                            child_layout_params.padding_start = child.get_margin_start().get();
-                            child_layout_params.padding_top = child.get_margin_top().get();
-                            child_layout_params.padding_end = child.get_margin_end().get();
-                            child_layout_params.padding_bottom = child.get_margin_bottom().get();
-                            child_layout_params.margin_start = child.get_margin_start().get();
-                            child_layout_params.margin_top = child.get_margin_top().get();
-                            child_layout_params.margin_end = child.get_margin_end().get();
-                            child_layout_params.margin_bottom = child.get_margin_bottom().get();
-                            child.set_layout_params(&child_layout_params);
+                        let mut content_width = 0.0_f32;
+                        let mut content_height = 0.0_f32;
 
+                        item.get_children().lock().iter_mut().for_each(|child| {
+                            init_child_margins(child);
+                            let (child_width_measure_mode, child_height_measure_mode) = measure_child(child, &layout_params, width_measure_mode, height_measure_mode);
                             child.measure(child_width_measure_mode, child_height_measure_mode);
-                            child_occupied_width = child_layout_params.width + child_layout_params.margin_start + child_layout_params.margin_end;
-                            height = height.max(child_layout_params.height + child_layout_params.margin_top + child_layout_params.margin_bottom);
 
+                            let child_layout_params = child.get_layout_params();
+                            content_width = content_width.max(child_layout_params.width + child_layout_params.margin_start + child_layout_params.margin_end);
+                            content_height = content_height.max(child_layout_params.height + child_layout_params.margin_top + child_layout_params.margin_bottom);
+                        });
 
-                            width += child_occupied_width;
+                        layout_params.width = clamp_size(match width_measure_mode {
+                            MeasureMode::Specified(width) => width,
+                            MeasureMode::Unspecified(width) => width.min(content_width + layout_params.padding_start + layout_params.padding_end),
+                        }, layout_params.min_width, layout_params.max_width);
 
-                            if remaining_width - child_occupied_width < 0.0 {
-                                remaining_width = 0.0;
-                            } else {
-                                remaining_width -= child_occupied_width;
-                            }
-                        });
+                        layout_params.height = clamp_size(match height_measure_mode {
+                            MeasureMode::Specified(height) => height,
+                            MeasureMode::Unspecified(height) => height.min(content_height + layout_params.padding_top + layout_params.padding_bottom),
+                        }, layout_params.min_height, layout_params.max_height);
 
-                        match width_measure_mode {
-                            MeasureMode::Specified(measured_width) => {
-                                layout_params.width = measured_width;
-                            }
-                            MeasureMode::Unspecified(measured_width) => {
-                                layout_params.width = measured_width.min(width);
-                            }
+                        if let Some(background) = item.get_background().lock().as_mut() {
+                            background.measure(MeasureMode::Specified(layout_params.width), MeasureMode::Specified(layout_params.height));
                         }
 
-                        match height_measure_mode {
-                            MeasureMode::Specified(measured_height) => {
-                                layout_params.height = measured_height;
-                            }
-                            MeasureMode::Unspecified(measured_height) => {
-                                layout_params.height = measured_height.min(height);
-                            }
+                        if let Some(foreground) = item.get_foreground().lock().as_mut() {
+                            foreground.measure(MeasureMode::Specified(layout_params.width), MeasureMode::Specified(layout_params.height));
                         }
 
                         item.set_layout_params(&layout_params);
                     }
                 )
-                .set_on_layout(
+                .set_layout_event(
                     |item, x, y| {
-                        let vertical_gravity = item.get_vertical_gravity().get();
                         let horizontal_gravity = item.get_horizontal_gravity().get();
+                        let vertical_gravity = item.get_vertical_gravity().get();
+                        let direction = item.get_layout_direction().get();
+
                         let mut layout_params = item.get_layout_params().clone();
-                        layout_params.x = x;
-                        layout_params.y = y;
+                        layout_params.relative_x = x;
+                        layout_params.relative_y = y;
                         item.set_layout_params(&layout_params);
 
                         if let Some(background) = item.get_background().lock().as_mut() {
@@ -102,43 +82,28 @@ impl Stack {
                             foreground.layout(x, y);
                         }
 
-                        let x = LogicalX::new(item.get_layout_direction().get(), x, x, layout_params.width);
-                        item.get_children_mut().iter_mut().for_each(|child| {
-                            let child_layout_params = child.get_layout_params();
-                            let mut x = x + child_layout_params.margin_start;
-                            let mut y = y + child_layout_params.margin_top;
-                            let remaining_width = layout_params.width - layout_params.padding_start - layout_params.padding_end - child_layout_params.width - child_layout_params.margin_start - child_layout_params.margin_end;
-                            let remaining_height = layout_params.height - layout_params.padding_top - layout_params.padding_bottom - child_layout_params.height - child_layout_params.margin_top - child_layout_params.margin_bottom;
-                            let x= match vertical_gravity {
-                                Gravity::Start => {
-                                    x
-                                }
-                                Gravity::Center => {
-                                    x + remaining_width / 2.0
-                                }
-                                Gravity::End => {
-                                    x + remaining_width - child_layout_params.width
-                                }
-                            };
-
-                            let y = match horizontal_gravity {
-                                Gravity::Start => {
-                                    y
-                                }
-                                Gravity::Center => {
-                                    y + remaining_height / 2.0
-                                }
-                                Gravity::End => {
-                                    y + remaining_height - child_layout_params.height
-                                }
-                            };
-
-                            child.layout(x.physical_value(child_layout_params.width), y);
+                        let content_width = layout_params.width - layout_params.padding_start - layout_params.padding_end;
+                        let content_height = layout_params.height - layout_params.padding_top - layout_params.padding_bottom;
+
+                        item.get_children().lock().iter_mut().for_each(|child| {
+                            let child_layout_params = child.get_layout_params().clone();
+                            let occupied_width = child_layout_params.width + child_layout_params.margin_start + child_layout_params.margin_end;
+                            let occupied_height = child_layout_params.height + child_layout_params.margin_top + child_layout_params.margin_bottom;
+
+                            let child_x = LogicalX::new(
+                                direction,
+                                0.0,
+                                layout_params.padding_start + child_layout_params.margin_start + gravity_offset(horizontal_gravity, content_width - occupied_width),
+                                layout_params.width,
+                            ).physical_value(child_layout_params.width);
+                            let child_y = layout_params.padding_top + child_layout_params.margin_top + gravity_offset(vertical_gravity, content_height - occupied_height);
+
+                            child.layout(child_x, child_y);
                         });
                     }
                 )
         );
-        item.set_children(children);
+        item.set_children(children_property(children));
 
         Stack {
             item,
@@ -148,4 +113,4 @@ impl Stack {
     pub fn unwrap(self) -> Item {
         self.item
     }
-}
\ No newline at end of file
+}
@@ -7,6 +7,7 @@ pub mod theme;
 pub mod component;
 pub mod layout;
 mod test;
+pub mod testing;
 pub mod widget;
 
 pub use winit::*;
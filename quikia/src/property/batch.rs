@@ -0,0 +1,140 @@
+use std::cell::RefCell;
+use std::sync::{Arc, Mutex};
+
+use crate::property::Observer;
+
+#[derive(Default)]
+struct BatchState {
+    /// The number of nested [`batch`] calls running on this thread.
+    depth: usize,
+    /// The properties that changed during the batch, by their id, each listed once.
+    pending: Vec<(usize, Arc<Mutex<Vec<Observer>>>)>,
+    /// The properties whose observers are being notified, the innermost last.
+    notifying: Vec<usize>,
+    /// The notification cycles found since they were last taken.
+    cycles: Vec<Vec<usize>>,
+}
+
+thread_local! {
+    static BATCH_STATE: RefCell<BatchState> = RefCell::new(BatchState::default());
+}
+
+/// Run `f` and defer the notifications of the [`SharedProperty`](crate::property::SharedProperty)s it changes until
+/// it returns.
+///
+/// A property that changes several times is notified once, and a property derived with
+/// [`SharedProperty::observe`](crate::property::SharedProperty::observe) or a tracked generator is evaluated once even
+/// if several of its inputs changed. Other observers run once for every changed property they observe, also when they
+/// share their owner id. Properties derived from the changed ones keep their old value until the batch ends. Batches can be nested,
+/// the notifications are sent when the outermost one ends. If `f` panics, the outermost batch drops the notifications.
+pub fn batch<R>(f: impl FnOnce() -> R) -> R {
+    struct Depth {
+        finished: bool,
+    }
+
+    impl Drop for Depth {
+        fn drop(&mut self) {
+            let pending = BATCH_STATE.with(|state| {
+                let mut state = state.borrow_mut();
+                state.depth -= 1;
+                if state.depth == 0 && !self.finished {
+                    std::mem::take(&mut state.pending)
+                } else {
+                    Vec::new()
+                }
+            });
+            drop(pending);
+        }
+    }
+
+    BATCH_STATE.with(|state| state.borrow_mut().depth += 1);
+    let mut depth = Depth { finished: false };
+    let result = f();
+    depth.finished = true;
+    drop(depth);
+    if BATCH_STATE.with(|state| state.borrow().depth) == 0 {
+        flush();
+    }
+    result
+}
+
+/// Whether a [`batch`] is running on this thread.
+pub fn is_batching() -> bool {
+    BATCH_STATE.with(|state| state.borrow().depth > 0)
+}
+
+/// Notify the observers of the property with the given id, or queue them if a batch is running.
+pub(crate) fn notify_observers(id: usize, observers: &Arc<Mutex<Vec<Observer>>>) {
+    let queued = BATCH_STATE.with(|state| {
+        let mut state = state.borrow_mut();
+        if state.depth == 0 {
+            return false;
+        }
+        if !state.pending.iter().any(|(pending_id, _)| *pending_id == id) {
+            state.pending.push((id, Arc::clone(observers)));
+        }
+        true
+    });
+    if !queued {
+        run_observers(id, observers, None);
+    }
+}
+
+/// Take the notification cycles found on this thread, each as the ids of the properties in the cycle.
+///
+/// A property that is notified again while its observers are running depends on itself, the notification is dropped
+/// instead of recursing forever, and the cycle is logged and kept until it is taken.
+pub fn take_notification_cycles() -> Vec<Vec<usize>> {
+    BATCH_STATE.with(|state| std::mem::take(&mut state.borrow_mut().cycles))
+}
+
+fn flush() {
+    let pending = BATCH_STATE.with(|state| std::mem::take(&mut state.borrow_mut().pending));
+    let mut regenerated = Vec::new();
+    for (id, observers) in pending {
+        run_observers(id, &observers, Some(&mut regenerated));
+    }
+}
+
+/// Does nothing if the property is already notifying further up the stack, which would recurse forever otherwise.
+///
+/// `regenerated` holds the ids of the properties whose regenerating observers already ran during the flush, other
+/// observers always run, even if they share their owner id with another one.
+fn run_observers(id: usize, observers: &Arc<Mutex<Vec<Observer>>>, mut regenerated: Option<&mut Vec<usize>>) {
+    let cycle = BATCH_STATE.with(|state| {
+        let mut state = state.borrow_mut();
+        let cycle = state.notifying.iter().position(|notifying_id| *notifying_id == id)
+            .map(|start| state.notifying[start..].to_vec());
+        match &cycle {
+            Some(cycle) => state.cycles.push(cycle.clone()),
+            None => state.notifying.push(id),
+        }
+        cycle
+    });
+    if let Some(cycle) = cycle {
+        log::warn!("Notification cycle between the properties {:?}, a property depends on itself", cycle);
+        return;
+    }
+    struct Notifying;
+
+    impl Drop for Notifying {
+        fn drop(&mut self) {
+            BATCH_STATE.with(|state| state.borrow_mut().notifying.pop());
+        }
+    }
+
+    let notifying = Notifying;
+    observers.lock().unwrap().iter_mut().for_each(|observer| {
+        if let Some(regenerated) = regenerated.as_mut() {
+            if observer.regenerates() {
+                let owner_id = observer.owner_id();
+                if regenerated.contains(&owner_id) {
+                    return;
+                }
+                regenerated.push(owner_id);
+            }
+        }
+        observer.notify()
+    });
+    drop(notifying);
+}
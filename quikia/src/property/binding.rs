@@ -0,0 +1,142 @@
+use std::cell::Cell;
+use std::rc::Rc;
+use std::sync::Arc;
+
+use crate::property::{get_observable_id, Gettable, Observable, Observer, SharedProperty};
+use crate::text::StyledText;
+
+/// Converts the values of two bound properties into each other, see [`SharedProperty::bind_with`].
+///
+/// A conversion fails with a message, e.g. when a text is not a number.
+pub struct Converter<A, B> {
+    to: Box<dyn Fn(&A) -> Result<B, String>>,
+    from: Box<dyn Fn(&B) -> Result<A, String>>,
+}
+
+impl<A, B> Converter<A, B> {
+    pub fn new(to: impl Fn(&A) -> Result<B, String> + 'static, from: impl Fn(&B) -> Result<A, String> + 'static) -> Self {
+        Self {
+            to: Box::new(to),
+            from: Box::new(from),
+        }
+    }
+
+    /// The conversion in the other direction.
+    pub fn reverse(self) -> Converter<B, A> {
+        Converter {
+            to: self.from,
+            from: self.to,
+        }
+    }
+}
+
+impl Converter<f32, StyledText> {
+    /// Formats the number with [`ToString`] and parses the trimmed text.
+    pub fn float_text() -> Self {
+        Self::new(
+            |value| Ok(StyledText::new(value.to_string())),
+            |text| {
+                text.as_str().trim().parse::<f32>().map_err(|_| format!("\"{}\" is not a number", text.as_str()))
+            },
+        )
+    }
+}
+
+/// The connection between two bound properties, returned by [`SharedProperty::bind`] and [`SharedProperty::bind_with`].
+///
+/// The properties stay bound when the binding is dropped, until [`Binding::unbind`] is called. The observers
+/// only hold weak references, so the bound properties do not keep each other alive.
+pub struct Binding {
+    id: usize,
+    properties: Vec<Box<dyn Observable>>,
+    error: SharedProperty<Option<String>>,
+}
+
+impl Binding {
+    /// The message of the last failed conversion, `None` once a conversion succeeds again.
+    pub fn error(&self) -> SharedProperty<Option<String>> {
+        self.error.clone()
+    }
+
+    /// Stop keeping the properties in sync.
+    pub fn unbind(&self) {
+        self.properties.iter().for_each(|property| property.remove_observer(self.id));
+    }
+}
+
+impl<T: Clone + 'static> SharedProperty<T> {
+    /// Keep the two properties in sync, `other` takes the value of this property first.
+    pub fn bind(&self, other: &SharedProperty<T>) -> Binding {
+        self.bind_with(other, Converter::new(|value: &T| Ok(value.clone()), |value: &T| Ok(value.clone())))
+    }
+
+    /// Keep the two properties in sync through a converter, `other` takes the converted value of this property first.
+    ///
+    /// A value that fails to convert is not copied to the other property, the message is stored in
+    /// [`Binding::error`] instead. A change is never copied back to the property it came from.
+    pub fn bind_with<U: Clone + 'static>(&self, other: &SharedProperty<U>, converter: Converter<T, U>) -> Binding {
+        let id = get_observable_id();
+        let error = SharedProperty::from_value(None);
+        let updating = Rc::new(Cell::new(false));
+        let converter = Rc::new(converter);
+
+        let sync_to_other = {
+            let source = Arc::downgrade(&self.value);
+            let target = Arc::downgrade(&other.value);
+            let error = error.clone();
+            let updating = updating.clone();
+            let converter = converter.clone();
+            move || {
+                let (Some(source), Some(target)) = (source.upgrade(), target.upgrade()) else {
+                    return;
+                };
+                let (source, target) = (SharedProperty { value: source }, SharedProperty { value: target });
+                sync(&source, &target, &converter.to, &error, &updating)
+            }
+        };
+        let sync_to_self = {
+            let source = Arc::downgrade(&other.value);
+            let target = Arc::downgrade(&self.value);
+            let error = error.clone();
+            move || {
+                let (Some(source), Some(target)) = (source.upgrade(), target.upgrade()) else {
+                    return;
+                };
+                let (source, target) = (SharedProperty { value: source }, SharedProperty { value: target });
+                sync(&source, &target, &converter.from, &error, &updating)
+            }
+        };
+        sync_to_other();
+        self.add_observer(Observer::new(sync_to_other, id));
+        other.add_observer(Observer::new(sync_to_self, id));
+
+        Binding {
+            id,
+            properties: vec![Box::new(self.clone()), Box::new(other.clone())],
+            error,
+        }
+    }
+}
+
+fn sync<A: Clone + 'static, B: 'static>(
+    source: &SharedProperty<A>,
+    target: &SharedProperty<B>,
+    convert: &dyn Fn(&A) -> Result<B, String>,
+    error: &SharedProperty<Option<String>>,
+    updating: &Cell<bool>,
+) {
+    if updating.get() {
+        return;
+    }
+    updating.set(true);
+    match convert(&source.get()) {
+        Ok(value) => {
+            target.set_value(value);
+            if error.lock().is_some() {
+                error.set_value(None);
+            }
+        }
+        Err(message) => error.set_value(Some(message)),
+    }
+    updating.set(false);
+}
@@ -0,0 +1,244 @@
+use std::cell::RefCell;
+use std::collections::VecDeque;
+use std::rc::Rc;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use crate::property::{subscribe, BoolProperty, Gettable, SharedProperty, Subscription};
+
+/// A single change of one property.
+struct Change {
+    property_id: usize,
+    /// Consecutive changes of a property tracked with [`History::track_typing`] are merged.
+    mergeable: bool,
+    time: Instant,
+    undo: Box<dyn Fn()>,
+    redo: Box<dyn Fn()>,
+}
+
+struct HistoryState {
+    /// Each entry is undone as a whole, the oldest first.
+    undo_stack: VecDeque<Vec<Change>>,
+    redo_stack: Vec<Vec<Change>>,
+    max_depth: usize,
+    merge_interval: Duration,
+    /// The changes of the running transactions, see [`History::transaction`].
+    transaction: Option<Vec<Change>>,
+    transaction_depth: usize,
+    /// An undo or redo is setting the properties, their notifications are not recorded.
+    applying: bool,
+    /// The last entry is a single typing edit that the next one may be merged into.
+    can_merge: bool,
+}
+
+/// Records the changes of selected properties so that they can be undone and redone.
+///
+/// Only properties passed to [`History::track`] or [`History::track_typing`] are recorded. A change is recorded when
+/// the property notifies its observers, so a [`TextProperty`](crate::property::TextProperty) records the edits of its
+/// text too, once the text is unlocked again. Dropping the history stops recording.
+pub struct History {
+    state: Arc<Mutex<HistoryState>>,
+    can_undo: BoolProperty,
+    can_redo: BoolProperty,
+    subscriptions: Vec<Subscription>,
+}
+
+impl History {
+    /// Keep at most `max_depth` entries, the oldest ones are dropped first.
+    pub fn new(max_depth: usize) -> Self {
+        Self {
+            state: Arc::new(Mutex::new(HistoryState {
+                undo_stack: VecDeque::new(),
+                redo_stack: Vec::new(),
+                max_depth,
+                merge_interval: Duration::from_secs(1),
+                transaction: None,
+                transaction_depth: 0,
+                applying: false,
+                can_merge: false,
+            })),
+            can_undo: false.into(),
+            can_redo: false.into(),
+            subscriptions: Vec::new(),
+        }
+    }
+
+    /// Typing edits that follow each other within the interval are merged into one entry, one second by default.
+    pub fn merge_interval(self, merge_interval: Duration) -> Self {
+        self.state.lock().unwrap().merge_interval = merge_interval;
+        self
+    }
+
+    /// Record every change of the property as an entry of its own.
+    pub fn track<T: Clone + PartialEq + 'static>(&mut self, property: &SharedProperty<T>) {
+        self.track_property(property, false);
+    }
+
+    /// Record the changes of the property, merging consecutive ones like the keystrokes of a text field.
+    pub fn track_typing<T: Clone + PartialEq + 'static>(&mut self, property: &SharedProperty<T>) {
+        self.track_property(property, true);
+    }
+
+    fn track_property<T: Clone + PartialEq + 'static>(&mut self, property: &SharedProperty<T>, mergeable: bool) {
+        let property_id = property.lock().get_id();
+        let last_value = Rc::new(RefCell::new(property.get()));
+        let state = self.state.clone();
+        let can_undo = self.can_undo.clone();
+        let can_redo = self.can_redo.clone();
+        let property_clone = property.clone();
+        let subscription = subscribe(property, move || {
+            let value = property_clone.get();
+            let old_value = last_value.replace(value.clone());
+            if old_value == value || state.lock().unwrap().applying {
+                return;
+            }
+            let change = Change {
+                property_id,
+                mergeable,
+                time: Instant::now(),
+                undo: {
+                    let property = property_clone.clone();
+                    let last_value = last_value.clone();
+                    Box::new(move || {
+                        *last_value.borrow_mut() = old_value.clone();
+                        property.set_value(old_value.clone());
+                    })
+                },
+                redo: {
+                    let property = property_clone.clone();
+                    let last_value = last_value.clone();
+                    Box::new(move || {
+                        *last_value.borrow_mut() = value.clone();
+                        property.set_value(value.clone());
+                    })
+                },
+            };
+            state.lock().unwrap().record(change);
+            update_flags(&state, &can_undo, &can_redo);
+        });
+        self.subscriptions.push(subscription);
+    }
+
+    /// Record all changes made by `f` as a single entry. Transactions can be nested.
+    ///
+    /// Changes are recorded when the properties notify, so the changes deferred by a
+    /// [`batch`](crate::property::batch) inside of `f` are not part of the transaction.
+    pub fn transaction<R>(&self, f: impl FnOnce() -> R) -> R {
+        {
+            let mut state = self.state.lock().unwrap();
+            state.transaction_depth += 1;
+            if state.transaction.is_none() {
+                state.transaction = Some(Vec::new());
+            }
+        }
+        let result = f();
+        {
+            let mut state = self.state.lock().unwrap();
+            state.transaction_depth -= 1;
+            if state.transaction_depth == 0 {
+                let changes = state.transaction.take().unwrap();
+                if !changes.is_empty() {
+                    state.push_entry(changes);
+                    state.can_merge = false;
+                }
+            }
+        }
+        update_flags(&self.state, &self.can_undo, &self.can_redo);
+        result
+    }
+
+    /// Revert the last entry, returns whether there was one.
+    pub fn undo(&self) -> bool {
+        let Some(entry) = self.state.lock().unwrap().undo_stack.pop_back() else {
+            return false;
+        };
+        self.apply(|| entry.iter().rev().for_each(|change| (change.undo)()));
+        self.state.lock().unwrap().redo_stack.push(entry);
+        update_flags(&self.state, &self.can_undo, &self.can_redo);
+        true
+    }
+
+    /// Apply the last undone entry again, returns whether there was one.
+    pub fn redo(&self) -> bool {
+        let Some(entry) = self.state.lock().unwrap().redo_stack.pop() else {
+            return false;
+        };
+        self.apply(|| entry.iter().for_each(|change| (change.redo)()));
+        self.state.lock().unwrap().undo_stack.push_back(entry);
+        update_flags(&self.state, &self.can_undo, &self.can_redo);
+        true
+    }
+
+    fn apply(&self, f: impl FnOnce()) {
+        {
+            let mut state = self.state.lock().unwrap();
+            state.applying = true;
+            state.can_merge = false;
+        }
+        f();
+        self.state.lock().unwrap().applying = false;
+    }
+
+    /// Forget all entries.
+    pub fn clear(&self) {
+        {
+            let mut state = self.state.lock().unwrap();
+            state.undo_stack.clear();
+            state.redo_stack.clear();
+            state.can_merge = false;
+        }
+        update_flags(&self.state, &self.can_undo, &self.can_redo);
+    }
+
+    /// Whether [`History::undo`] has an entry to revert, e.g. for enabling a toolbar button.
+    pub fn can_undo(&self) -> BoolProperty {
+        self.can_undo.clone()
+    }
+
+    /// Whether [`History::redo`] has an entry to apply again.
+    pub fn can_redo(&self) -> BoolProperty {
+        self.can_redo.clone()
+    }
+}
+
+impl HistoryState {
+    fn record(&mut self, change: Change) {
+        if let Some(transaction) = self.transaction.as_mut() {
+            transaction.push(change);
+            return;
+        }
+        if self.can_merge && change.mergeable {
+            if let Some([previous]) = self.undo_stack.back_mut().map(|entry| entry.as_mut_slice()) {
+                if previous.property_id == change.property_id
+                    && change.time.duration_since(previous.time) <= self.merge_interval {
+                    previous.redo = change.redo;
+                    previous.time = change.time;
+                    return;
+                }
+            }
+        }
+        self.can_merge = change.mergeable;
+        self.push_entry(vec![change]);
+    }
+
+    fn push_entry(&mut self, changes: Vec<Change>) {
+        self.redo_stack.clear();
+        self.undo_stack.push_back(changes);
+        while self.undo_stack.len() > self.max_depth {
+            self.undo_stack.pop_front();
+        }
+    }
+}
+
+fn update_flags(state: &Arc<Mutex<HistoryState>>, can_undo: &BoolProperty, can_redo: &BoolProperty) {
+    let (undo, redo) = {
+        let state = state.lock().unwrap();
+        (!state.undo_stack.is_empty(), !state.redo_stack.is_empty())
+    };
+    if can_undo.get() != undo {
+        can_undo.set_value(undo);
+    }
+    if can_redo.get() != redo {
+        can_redo.set_value(redo);
+    }
+}
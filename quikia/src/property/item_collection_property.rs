@@ -1,7 +1,7 @@
 use std::slice::Iter;
 use std::rc::Rc;
 use std::sync::Mutex;
-use crate::property::{Observable, Observer, SharedProperty};
+use crate::property::{Gettable, Observable, Observer, SharedProperty, Subscription};
 use crate::ui::Item;
 
 #[macro_export]
@@ -25,7 +25,9 @@ macro_rules! children {
 
 pub struct ItemCollection{
     items: Vec<Item>,
-    observers: Rc<Mutex<Vec<Observer>>>
+    observers: Rc<Mutex<Vec<Observer>>>,
+    /// Subscriptions that keep the items in sync with their source, removed with the collection.
+    subscriptions: Vec<Subscription>,
 }
 
 impl Observable for ItemCollection{
@@ -51,33 +53,80 @@ impl ItemCollection{
     pub fn new() -> Self{
         Self{
             items: Vec::new(),
-            observers: Rc::new(Mutex::new(Vec::new()))
+            observers: Rc::new(Mutex::new(Vec::new())),
+            subscriptions: Vec::new(),
         }
     }
 
+    /// Keep the subscription for as long as the collection lives.
+    pub fn add_subscription(&mut self, subscription: Subscription){
+        self.subscriptions.push(subscription);
+    }
+
     pub fn add(&mut self, item: Item){
         self.items.push(item);
         self.notify();
     }
 
+    pub fn insert(&mut self, index: usize, item: Item){
+        self.items.insert(index, item);
+        self.notify();
+    }
+
     pub fn remove(&mut self, index: usize){
         self.items.remove(index);
         self.notify();
     }
 
+    /// Move the item at `from` so that it ends up at index `to`.
+    pub fn move_item(&mut self, from: usize, to: usize){
+        let item = self.items.remove(from);
+        self.items.insert(to, item);
+        self.notify();
+    }
+
+    /// Replace the item at `index` and return the previous one.
+    pub fn replace(&mut self, index: usize, item: Item) -> Item{
+        let previous = std::mem::replace(&mut self.items[index], item);
+        self.notify();
+        previous
+    }
+
     pub fn get(&self, index: usize) -> Option<&Item>{
         self.items.get(index)
     }
 
+    pub fn get_mut(&mut self, index: usize) -> Option<&mut Item>{
+        self.items.get_mut(index)
+    }
+
     pub fn len(&self) -> usize{
         self.items.len()
     }
 
+    /// The number of observers of this collection, for finding leaked observers.
+    pub fn observer_count(&self) -> usize{
+        self.observers.lock().unwrap().len()
+    }
+
     pub fn clear(&mut self){
         self.items.clear();
         self.notify();
     }
 
+    /// Remove all items and return them.
+    pub fn take_all(&mut self) -> Vec<Item>{
+        let items = std::mem::take(&mut self.items);
+        self.notify();
+        items
+    }
+
+    /// Replace all items with the ones `f` builds from the current items. Observers are notified once.
+    pub fn replace_all(&mut self, f: impl FnOnce(Vec<Item>) -> Vec<Item>){
+        self.items = f(std::mem::take(&mut self.items));
+        self.notify();
+    }
+
     pub fn iter(&self) -> Iter<Item>{
         self.items.iter()
     }
@@ -85,6 +134,13 @@ impl ItemCollection{
     pub fn iter_mut(&mut self) -> std::slice::IterMut<Item>{
         self.items.iter_mut()
     }
+
+    /// The items in drawing order, bottom-most first: by z index, and in collection order for equal z indices.
+    pub fn z_ordered_mut(&mut self) -> Vec<&mut Item>{
+        let mut items: Vec<&mut Item> = self.items.iter_mut().collect();
+        items.sort_by_key(|item| item.get_z_index().get());
+        items
+    }
 }
 
 pub type ItemCollectionProperty = SharedProperty<ItemCollection>;
@@ -0,0 +1,321 @@
+use std::cell::RefCell;
+use std::rc::Rc;
+use std::sync::{Arc, Mutex};
+
+use crate::property::{batch, get_observable_id, Gettable, ItemCollection, ItemCollectionProperty, Observable, Observer, SharedProperty, Subscription};
+use crate::property::tracking::record_read;
+use crate::ui::Item;
+
+/// A fine-grained change of a [`ListProperty`].
+#[derive(Clone, Debug, PartialEq)]
+pub enum ListChange {
+    /// A value was inserted at the index.
+    Insert(usize),
+    /// The value at the index was removed.
+    Remove(usize),
+    /// The value at `from` was removed and inserted again at `to`.
+    Move { from: usize, to: usize },
+    /// The value at the index was replaced or modified.
+    Update(usize),
+    /// All values were replaced.
+    Reset,
+}
+
+struct ChangeListener {
+    listener: Rc<RefCell<dyn FnMut(&ListChange)>>,
+    owner_id: usize,
+}
+
+struct ListState<T> {
+    id: usize,
+    values: Vec<T>,
+    observers: Arc<Mutex<Vec<Observer>>>,
+    listeners: Arc<Mutex<Vec<ChangeListener>>>,
+}
+
+/// An observable list of values.
+///
+/// Every modification is reported to the change listeners as a [`ListChange`], and to the [`Observer`]s as a plain
+/// notification. Use [`ListProperty::for_each`] to turn the list into the children of a layout.
+pub struct ListProperty<T> {
+    state: Arc<Mutex<ListState<T>>>,
+}
+
+impl<T: 'static> ListProperty<T> {
+    pub fn new() -> Self {
+        Self::from_vec(Vec::new())
+    }
+
+    pub fn from_vec(values: Vec<T>) -> Self {
+        Self {
+            state: Arc::new(Mutex::new(ListState {
+                id: get_observable_id(),
+                values,
+                observers: Arc::new(Mutex::new(Vec::new())),
+                listeners: Arc::new(Mutex::new(Vec::new())),
+            })),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.state.lock().unwrap().values.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// The number of observers and change listeners of this list, for finding leaked observers.
+    pub fn observer_count(&self) -> usize {
+        let state = self.state.lock().unwrap();
+        let count = state.observers.lock().unwrap().len() + state.listeners.lock().unwrap().len();
+        count
+    }
+
+    pub fn push(&self, value: T) {
+        let index = {
+            let mut state = self.state.lock().unwrap();
+            state.values.push(value);
+            state.values.len() - 1
+        };
+        self.emit(ListChange::Insert(index));
+    }
+
+    pub fn insert(&self, index: usize, value: T) {
+        self.state.lock().unwrap().values.insert(index, value);
+        self.emit(ListChange::Insert(index));
+    }
+
+    pub fn remove(&self, index: usize) -> T {
+        let value = self.state.lock().unwrap().values.remove(index);
+        self.emit(ListChange::Remove(index));
+        value
+    }
+
+    /// Move the value at `from` so that it ends up at index `to`.
+    pub fn move_item(&self, from: usize, to: usize) {
+        {
+            let mut state = self.state.lock().unwrap();
+            let value = state.values.remove(from);
+            state.values.insert(to, value);
+        }
+        self.emit(ListChange::Move { from, to });
+    }
+
+    pub fn set(&self, index: usize, value: T) {
+        self.state.lock().unwrap().values[index] = value;
+        self.emit(ListChange::Update(index));
+    }
+
+    pub fn update(&self, index: usize, f: impl FnOnce(&mut T)) {
+        f(&mut self.state.lock().unwrap().values[index]);
+        self.emit(ListChange::Update(index));
+    }
+
+    /// Replace all values, reported as a single [`ListChange::Reset`].
+    pub fn set_all(&self, values: Vec<T>) {
+        self.state.lock().unwrap().values = values;
+        self.emit(ListChange::Reset);
+    }
+
+    pub fn clear(&self) {
+        self.set_all(Vec::new());
+    }
+
+    /// The listener is called after every modification, with the list already unlocked. It must not modify the list,
+    /// but it may add or remove change listeners.
+    pub fn add_change_listener(&self, owner_id: usize, listener: impl FnMut(&ListChange) + 'static) {
+        let listeners = self.state.lock().unwrap().listeners.clone();
+        listeners.lock().unwrap().push(ChangeListener {
+            listener: Rc::new(RefCell::new(listener)),
+            owner_id,
+        });
+    }
+
+    pub fn remove_change_listener(&self, owner_id: usize) {
+        let listeners = self.state.lock().unwrap().listeners.clone();
+        listeners.lock().unwrap().retain(|listener| listener.owner_id != owner_id);
+    }
+
+    /// Change listeners are called right away, the observers are deferred while a [`batch`](crate::property::batch) is running.
+    fn emit(&self, change: ListChange) {
+        let listeners = self.state.lock().unwrap().listeners.clone();
+        let listeners: Vec<_> = listeners.lock().unwrap().iter()
+            .map(|listener| Rc::clone(&listener.listener))
+            .collect();
+        for listener in listeners {
+            (&mut *listener.borrow_mut())(&change);
+        }
+        self.notify();
+    }
+}
+
+impl<T: Clone + 'static> ListProperty<T> {
+    pub fn value_at(&self, index: usize) -> Option<T> {
+        self.state.lock().unwrap().values.get(index).cloned()
+    }
+
+    /// Unlike [`Gettable::get`], the read is not tracked.
+    pub fn to_vec(&self) -> Vec<T> {
+        self.state.lock().unwrap().values.clone()
+    }
+
+    /// Build one [`Item`] per value and keep the items in sync with the list.
+    ///
+    /// Each item is identified by the `key` of its value. Only the items of inserted values or of values whose key
+    /// changed are built, existing items are moved or kept and keep their state. An item receives its value as a
+    /// property, which is updated when the value changes but the key stays the same. On a [`ListChange::Reset`] the
+    /// items are matched to the new values by key.
+    ///
+    /// The returned collection owns the change listener, which is removed when the collection is dropped, e.g. with the
+    /// layout it is the children of.
+    pub fn for_each<K: PartialEq + 'static>(
+        &self,
+        key: impl Fn(&T) -> K + 'static,
+        build_item: impl Fn(SharedProperty<T>) -> Item + 'static,
+    ) -> ItemCollectionProperty {
+        let mut rows = Vec::new();
+        let mut item_collection = ItemCollection::new();
+        for value in self.to_vec() {
+            let (row, item) = new_row(&key, &build_item, value);
+            rows.push(row);
+            item_collection.add(item);
+        }
+        let children = ItemCollectionProperty::from_value(item_collection);
+
+        let weak_children = Arc::downgrade(&children.value);
+        let state = Arc::downgrade(&self.state);
+        let owner_id = get_observable_id();
+        self.add_change_listener(owner_id, move |change| {
+            let (Some(state), Some(children)) = (state.upgrade(), weak_children.upgrade()) else {
+                return;
+            };
+            let list = ListProperty { state };
+            let children_clone = SharedProperty { value: children };
+            match *change {
+                ListChange::Insert(index) => {
+                    let (row, item) = new_row(&key, &build_item, list.value_at(index).unwrap());
+                    rows.insert(index, row);
+                    children_clone.lock().insert(index, item);
+                }
+                ListChange::Remove(index) => {
+                    rows.remove(index);
+                    children_clone.lock().remove(index);
+                }
+                ListChange::Move { from, to } => {
+                    let row = rows.remove(from);
+                    rows.insert(to, row);
+                    children_clone.lock().move_item(from, to);
+                }
+                ListChange::Update(index) => {
+                    let value = list.value_at(index).unwrap();
+                    if rows[index].0 == key(&value) {
+                        rows[index].1.set_value(value);
+                    } else {
+                        let (row, item) = new_row(&key, &build_item, value);
+                        rows[index] = row;
+                        children_clone.lock().replace(index, item);
+                    }
+                }
+                ListChange::Reset => {
+                    let mut old_rows: Vec<Option<(K, SharedProperty<T>)>> = rows.drain(..).map(Some).collect();
+                    // The item of each value, either the position of a reused one or a new one.
+                    let mut new_items: Vec<Result<usize, Item>> = Vec::new();
+                    for value in list.to_vec() {
+                        let value_key = key(&value);
+                        let reused = old_rows.iter()
+                            .position(|row| matches!(row, Some((row_key, _)) if *row_key == value_key));
+                        match reused {
+                            Some(position) => {
+                                let (row_key, row) = old_rows[position].take().unwrap();
+                                row.set_value(value);
+                                rows.push((row_key, row));
+                                new_items.push(Ok(position));
+                            }
+                            None => {
+                                let (row, item) = new_row(&key, &build_item, value);
+                                rows.push(row);
+                                new_items.push(Err(item));
+                            }
+                        }
+                    }
+                    children_clone.lock().replace_all(|old_items| {
+                        let mut old_items: Vec<Option<Item>> = old_items.into_iter().map(Some).collect();
+                        new_items.into_iter()
+                            .map(|item| match item {
+                                Ok(position) => old_items[position].take().unwrap(),
+                                Err(item) => item,
+                            })
+                            .collect()
+                    });
+                }
+            }
+        });
+        let list = self.clone();
+        children.lock().add_subscription(Subscription::new(move || list.remove_change_listener(owner_id)));
+        children
+    }
+}
+
+fn new_row<T: 'static, K>(
+    key: &impl Fn(&T) -> K,
+    build_item: &impl Fn(SharedProperty<T>) -> Item,
+    value: T,
+) -> ((K, SharedProperty<T>), Item) {
+    let row_key = key(&value);
+    let row = SharedProperty::from_value(value);
+    let item = build_item(row.clone());
+    ((row_key, row), item)
+}
+
+impl<T: Clone + 'static> Gettable<Vec<T>> for ListProperty<T> {
+    /// Registers the list as an input of a tracked generator, see [`SharedProperty::from_tracked_generator`].
+    fn get(&self) -> Vec<T> {
+        let state = self.state.lock().unwrap();
+        record_read(state.id, || Box::new(self.clone()));
+        state.values.clone()
+    }
+}
+
+impl<T> Observable for ListProperty<T> {
+    fn add_observer(&self, listener: Observer) {
+        self.state.lock().unwrap().observers.lock().unwrap().push(listener);
+    }
+
+    fn remove_observer(&self, owner_id: usize) {
+        self.state.lock().unwrap().observers.lock().unwrap().retain(|observer| {
+            observer.owner_id() != owner_id
+        });
+    }
+
+    fn clear_observers(&self) {
+        self.state.lock().unwrap().observers.lock().unwrap().clear();
+    }
+
+    fn notify(&self) {
+        let state = self.state.lock().unwrap();
+        let (id, observers) = (state.id, state.observers.clone());
+        drop(state);
+        batch::notify_observers(id, &observers);
+    }
+}
+
+impl<T> Clone for ListProperty<T> {
+    fn clone(&self) -> Self {
+        Self {
+            state: Arc::clone(&self.state),
+        }
+    }
+}
+
+impl<T: 'static> Default for ListProperty<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T: 'static> From<Vec<T>> for ListProperty<T> {
+    fn from(values: Vec<T>) -> Self {
+        Self::from_vec(values)
+    }
+}
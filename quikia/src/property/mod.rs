@@ -1,8 +1,12 @@
+use std::cell::Cell;
 use std::ops::{Deref, DerefMut};
-use std::sync::{Arc, Mutex};
+use std::rc::Rc;
+use std::sync::{Arc, Mutex, MutexGuard, TryLockError, Weak};
 
 use lazy_static::lazy_static;
 
+use crate::property::tracking::{record_read, track_reads};
+
 
 pub use bool_property::*;
 pub use color_property::*;
@@ -21,7 +25,18 @@ mod alignment_property;
 mod text_property;
 mod gravity_property;
 mod item_collection_property;
+mod tracking;
+mod batch;
+mod list_property;
+mod binding;
+mod subscription;
+mod history;
 pub use item_collection_property::*;
+pub use list_property::*;
+pub use binding::*;
+pub use subscription::*;
+pub use history::*;
+pub use batch::{batch, is_batching, take_notification_cycles};
 
 lazy_static!(
     pub(crate) static ref OBSERVABLE_ID: Mutex<usize> = Mutex::new(0);
@@ -36,21 +51,38 @@ pub(crate) fn get_observable_id() -> usize {
 pub struct Observer {
     listener: Box<dyn FnMut()>,
     owner_id: usize,
+    /// Whether the observer updates the property `owner_id` from one of its inputs, see [`batch()`].
+    regenerates: bool,
 }
 
 impl Observer {
     pub fn new(listener: impl FnMut() + 'static, owner_id: usize) -> Self {
+        subscription::observer_created();
         Self {
             listener: Box::new(listener),
             owner_id,
+            regenerates: false,
+        }
+    }
+
+    /// An observer that updates the property with the id `owner_id` when one of its inputs changes. Within a
+    /// [`batch()`] it runs once for all inputs of the property that changed.
+    pub(crate) fn regenerator(listener: impl FnMut() + 'static, owner_id: usize) -> Self {
+        subscription::observer_created();
+        Self {
+            listener: Box::new(listener),
+            owner_id,
+            regenerates: true,
         }
     }
 
     pub fn new_without_id(listener: impl FnMut() + 'static) -> Self {
+        subscription::observer_created();
         let listener = Box::new(listener);
         Self {
             listener,
             owner_id: 0,
+            regenerates: false,
         }
     }
 
@@ -58,11 +90,21 @@ impl Observer {
         self.owner_id
     }
 
+    pub(crate) fn regenerates(&self) -> bool {
+        self.regenerates
+    }
+
     pub fn notify(&mut self) {
         (self.listener)();
     }
 }
 
+impl Drop for Observer {
+    fn drop(&mut self) {
+        subscription::observer_dropped();
+    }
+}
+
 pub trait Observable {
     fn add_observer(&self, listener: Observer);
     fn remove_observer(&self, owner_id: usize);
@@ -73,9 +115,23 @@ pub trait Observable {
 pub struct Property<T> {
     id: usize,
     value: T,
-    value_generator: Option<Box<dyn Fn() -> T>>,
+    /// Shared, so that a tracked generator can run while the property is unlocked.
+    value_generator: Option<Rc<dyn Fn() -> T>>,
     observers: Arc<Mutex<Vec<Observer>>>,
     observed_properties: Vec<Box<dyn Observable>>,
+    /// The inputs read by a tracked generator in its last evaluation, by their id.
+    tracked_properties: Vec<(usize, Box<dyn Observable>)>,
+    /// Forwards the notifications of the value, for a value that is observable itself.
+    value_observer: Option<ValueObserver<T>>,
+}
+
+/// Forwards the notifications of a value that is observable itself to the observers of its property.
+struct ValueObserver<T> {
+    observe: Box<dyn Fn(&T)>,
+    /// Removes the forwarder from a value that was replaced.
+    unobserve: Box<dyn Fn(&T)>,
+    /// Set when the value notified while the property was locked, the notification is sent when it is unlocked.
+    notified_while_locked: Rc<Cell<bool>>,
 }
 
 impl<T> Property<T> {
@@ -86,6 +142,8 @@ impl<T> Property<T> {
             value_generator: None,
             observers: Arc::new(Mutex::new(Vec::new())),
             observed_properties: Vec::new(),
+            tracked_properties: Vec::new(),
+            value_observer: None,
         }
     }
 
@@ -93,9 +151,11 @@ impl<T> Property<T> {
         Self {
             id: get_observable_id(),
             value: value_generator(),
-            value_generator: Some(value_generator),
+            value_generator: Some(Rc::from(value_generator)),
             observers: Arc::new(Mutex::new(Vec::new())),
             observed_properties: Vec::new(),
+            tracked_properties: Vec::new(),
+            value_observer: None,
         }
     }
 
@@ -118,31 +178,45 @@ impl<T> Property<T> {
     }
 
     pub fn notify_observers(&mut self) {
-        self.observers.lock().unwrap().iter_mut().for_each(|observer| {
-            observer.notify()
-        });
+        batch::notify_observers(self.id, &self.observers);
     }
 
-
-    pub fn set_generator(&mut self, value_generator: Box<dyn Fn() -> T>) {
+    /// Stop observing the inputs of the generator, both the observed and the tracked ones.
+    fn clear_inputs(&mut self) {
         self.observed_properties.iter().for_each(|observable| {
             observable.remove_observer(self.id);
         });
         self.observed_properties.clear();
+        self.tracked_properties.iter().for_each(|(_, observable)| {
+            observable.remove_observer(self.id);
+        });
+        self.tracked_properties.clear();
+    }
+
+
+    /// Replace the value, moving the forwarder of an observable value to the new one.
+    fn replace_value(&mut self, value: T) {
+        if let Some(value_observer) = &self.value_observer {
+            (value_observer.unobserve)(&self.value);
+            (value_observer.observe)(&value);
+        }
+        self.value = value;
+    }
+
+    pub fn set_generator(&mut self, value_generator: Box<dyn Fn() -> T>) {
+        self.clear_inputs();
+        let value_generator: Rc<dyn Fn() -> T> = Rc::from(value_generator);
+        self.replace_value(value_generator());
         self.value_generator = Some(value_generator);
-        self.value = self.value_generator.as_ref().unwrap()();
         self.notify_observers();
     }
 
     pub fn set_value<U: Into<T>>(&mut self, value: U) {
-        self.observed_properties.iter().for_each(|observable| {
-            observable.remove_observer(self.id);
-        });
-        self.observed_properties.clear();
+        self.clear_inputs();
 
         let value = value.into();
         self.value_generator = None;
-        self.value = value;
+        self.replace_value(value);
         self.notify_observers();
     }
 }
@@ -174,6 +248,10 @@ impl<T> DerefMut for Property<T> {
     }
 }
 
+/// A [`Property`] shared by its clones.
+///
+/// Unlike [`Property`], it notifies its observers after it is unlocked, so an observer can read the value of the
+/// property it observes. Code that relied on the value staying locked during the notification has to lock it itself.
 pub struct SharedProperty<T> {
     value: Arc<Mutex<Property<T>>>,
 }
@@ -192,54 +270,193 @@ impl<T: 'static> SharedProperty<T> {
         }
     }
 
-    pub fn lock(&self) -> std::sync::MutexGuard<'_, Property<T>> {
-        self.value.lock().unwrap()
+    pub fn lock(&self) -> PropertyGuard<'_, T> {
+        PropertyGuard {
+            guard: Some(self.value.lock().unwrap()),
+        }
+    }
+
+    /// The number of observers of this property, for finding leaked observers.
+    pub fn observer_count(&self) -> usize {
+        let observers = self.value.lock().unwrap().observers.clone();
+        let count = observers.lock().unwrap().len();
+        count
     }
 
+    /// Evaluate the generator again when the observable changes.
+    ///
+    /// The observer only holds a weak reference to the property, so properties observing each other are still dropped.
     pub fn observe<O: 'static + Observable + Clone>(&self, observable: &O) {
-        let self_clone = self.clone();
+        let weak = Arc::downgrade(&self.value);
         let mut value = self.value.lock().unwrap();
         value.observed_properties.push(Box::new(observable.clone()));
         let id = value.id;
         drop(value);
-        observable.add_observer(Observer::new(move || {
-            let mut value = self_clone.lock();
-            if let Some(value_generator) = &value.value_generator {
-                value.value = value_generator();
+        observable.add_observer(Observer::regenerator(move || {
+            let Some(value) = weak.upgrade() else {
+                return;
+            };
+            let property = SharedProperty { value };
+            // The generator runs while the property is unlocked, like a tracked one.
+            let value_generator = property.lock().value_generator.clone();
+            if let Some(value_generator) = value_generator {
+                let value = value_generator();
+                property.lock().replace_value(value);
             }
-            drop(value);
-            self_clone.notify();
+            property.notify();
         }, id))
     }
 
     pub fn set_generator(&self, value_generator: Box<dyn Fn() -> T>) {
-        self.value.lock().unwrap().set_generator(value_generator);
+        let mut value = self.value.lock().unwrap();
+        value.clear_inputs();
+        value.replace_value(value_generator());
+        value.value_generator = Some(Rc::from(value_generator));
+        drop(value);
+        self.notify();
+    }
+
+    /// Create a property whose generator is observed automatically.
+    ///
+    /// Every [`SharedProperty`] the generator reads with [`Gettable::get`] becomes an input of the property. The inputs
+    /// are collected again on every evaluation, so properties the generator no longer reads are unsubscribed.
+    pub fn from_tracked_generator(value_generator: Box<dyn Fn() -> T>) -> Self {
+        let (value, inputs) = track_reads(|| value_generator());
+        let mut property = Property::from_value(value);
+        property.value_generator = Some(Rc::from(value_generator));
+        let property = Self {
+            value: Arc::new(Mutex::new(property)),
+        };
+        property.subscribe_tracked(inputs, None);
+        property
+    }
+
+    /// Replace the generator like [`SharedProperty::set_generator`], tracking the properties it reads as in
+    /// [`SharedProperty::from_tracked_generator`].
+    ///
+    /// The generator runs while the property is unlocked, so it may read the previous value of the property,
+    /// which does not make the property an input of itself.
+    pub fn set_tracked_generator(&self, value_generator: Box<dyn Fn() -> T>) {
+        self.value.lock().unwrap().clear_inputs();
+        let (value, inputs) = track_reads(|| value_generator());
+        let mut property = self.value.lock().unwrap();
+        property.replace_value(value);
+        property.value_generator = Some(Rc::from(value_generator));
+        drop(property);
+        self.subscribe_tracked(inputs, None);
+        self.notify();
+    }
+
+    /// Evaluate the tracked generator again after the input `changed_id` changed and update the subscriptions.
+    fn evaluate_tracked(&self, changed_id: usize) {
+        let Some(value_generator) = self.value.lock().unwrap().value_generator.clone() else {
+            return;
+        };
+        let (value, inputs) = track_reads(|| value_generator());
+        let mut property = self.value.lock().unwrap();
+        // The generator may have replaced itself, e.g. by setting a value, then its result is stale.
+        let current = property.value_generator.as_ref()
+            .is_some_and(|current| Rc::as_ptr(current) as *const () == Rc::as_ptr(&value_generator) as *const ());
+        if !current {
+            return;
+        }
+        property.replace_value(value);
+        let id = property.id;
+        let previous = std::mem::take(&mut property.tracked_properties);
+        drop(property);
+        previous.iter()
+            .filter(|(input_id, _)| *input_id != changed_id && !inputs.iter().any(|(new_id, _)| new_id == input_id))
+            .for_each(|(_, observable)| observable.remove_observer(id));
+        let (kept, added): (Vec<_>, Vec<_>) = inputs.into_iter()
+            .partition(|(input_id, _)| previous.iter().any(|(previous_id, _)| previous_id == input_id));
+        self.value.lock().unwrap().tracked_properties.extend(kept);
+        self.subscribe_tracked(added, Some(changed_id));
+        self.notify();
+    }
+
+    /// Observe the given inputs and remember them as tracked.
+    /// The input that is notifying right now is already observed and can not be modified.
+    fn subscribe_tracked(&self, inputs: Vec<(usize, Box<dyn Observable>)>, notifying_id: Option<usize>) {
+        let id = self.value.lock().unwrap().id;
+        for (input_id, observable) in inputs {
+            // A generator that reads the previous value of its own property does not observe itself.
+            if input_id == id {
+                continue;
+            }
+            if Some(input_id) != notifying_id {
+                // A stale observer may be left from an evaluation that stopped reading the input while it notified.
+                observable.remove_observer(id);
+                let weak = Arc::downgrade(&self.value);
+                observable.add_observer(Observer::regenerator(move || {
+                    let Some(value) = weak.upgrade() else {
+                        return;
+                    };
+                    let property = SharedProperty { value };
+                    let tracked = property.lock().tracked_properties.iter()
+                        .any(|(tracked_id, _)| *tracked_id == input_id);
+                    if tracked {
+                        property.evaluate_tracked(input_id);
+                    }
+                }, id));
+            }
+            self.value.lock().unwrap().tracked_properties.push((input_id, observable));
+        }
     }
 
+    /// Unlike [`Property::set_value`], the observers are notified after the property is unlocked,
+    /// so they can read the new value.
     pub fn set_value<U: Into<T>>(&self, value: U) {
-        self.value.lock().unwrap().set_value(value);
+        let mut property = self.value.lock().unwrap();
+        property.clear_inputs();
+        property.value_generator = None;
+        property.replace_value(value.into());
+        drop(property);
+        self.notify();
     }
 }
 
 impl<T: 'static + Observable> SharedProperty<T> {
+    /// The notifications of the observable are forwarded to the observers of the property, also for the values set
+    /// later on, and the replaced values are no longer observed. An observable modified in place through
+    /// [`SharedProperty::lock`] notifies when the property is unlocked, so the observers can read it.
     pub fn from_observable(observable: T) -> Self {
         let observers: Arc<Mutex<Vec<Observer>>> = Arc::new(Mutex::new(Vec::new()));
         let id = get_observable_id();
-        let observers_clone = Arc::clone(&observers);
-        observable.add_observer(Observer::new(move || {
-            observers_clone.lock().unwrap().iter_mut().for_each(|observer| {
-                observer.notify()
-            });
-        }, id));
-        Self {
-            value: Arc::new(Mutex::new(Property {
+        let notified_while_locked = Rc::new(Cell::new(false));
+        let value = Arc::new_cyclic(|weak: &Weak<Mutex<Property<T>>>| {
+            let weak = weak.clone();
+            let observers_clone = Arc::clone(&observers);
+            let notified_while_locked_clone = notified_while_locked.clone();
+            let observe = move |observable: &T| {
+                let weak = weak.clone();
+                let observers = Arc::clone(&observers_clone);
+                let notified_while_locked = notified_while_locked_clone.clone();
+                observable.add_observer(Observer::new(move || {
+                    let locked = weak.upgrade()
+                        .is_some_and(|property| matches!(property.try_lock(), Err(TryLockError::WouldBlock)));
+                    if locked {
+                        notified_while_locked.set(true);
+                    } else {
+                        batch::notify_observers(id, &observers);
+                    }
+                }, id));
+            };
+            observe(&observable);
+            Mutex::new(Property {
                 id,
                 value: observable,
                 value_generator: None,
                 observers,
                 observed_properties: Vec::new(),
-            })),
-        }
+                tracked_properties: Vec::new(),
+                value_observer: Some(ValueObserver {
+                    observe: Box::new(observe),
+                    unobserve: Box::new(move |observable: &T| observable.remove_observer(id)),
+                    notified_while_locked,
+                }),
+            })
+        });
+        Self { value }
     }
 }
 
@@ -256,8 +473,50 @@ impl<T> Observable for SharedProperty<T> {
         self.value.lock().unwrap().clear_observers();
     }
 
+    /// Deferred while a [`batch`] is running.
     fn notify(&self) {
-        self.value.lock().unwrap().notify_observers();
+        let property = self.value.lock().unwrap();
+        let (id, observers) = (property.id, property.observers.clone());
+        drop(property);
+        batch::notify_observers(id, &observers);
+    }
+}
+
+/// A locked [`SharedProperty`], returned by [`SharedProperty::lock`].
+///
+/// A value that is observable itself and notifies while it is modified through the guard, like a
+/// [`StyledText`](crate::text::StyledText), notifies the observers of the property when the guard is dropped.
+pub struct PropertyGuard<'a, T> {
+    guard: Option<MutexGuard<'a, Property<T>>>,
+}
+
+impl<T> Deref for PropertyGuard<'_, T> {
+    type Target = Property<T>;
+
+    fn deref(&self) -> &Self::Target {
+        self.guard.as_ref().unwrap()
+    }
+}
+
+impl<T> DerefMut for PropertyGuard<'_, T> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        self.guard.as_mut().unwrap()
+    }
+}
+
+impl<T> Drop for PropertyGuard<'_, T> {
+    fn drop(&mut self) {
+        let Some(property) = self.guard.take() else {
+            return;
+        };
+        let notified = property.value_observer.as_ref()
+            .is_some_and(|value_observer| value_observer.notified_while_locked.replace(false));
+        if !notified {
+            return;
+        }
+        let (id, observers) = (property.id, property.observers.clone());
+        drop(property);
+        batch::notify_observers(id, &observers);
     }
 }
 
@@ -271,9 +530,11 @@ impl<T> Clone for SharedProperty<T> {
 
 impl<T> Drop for SharedProperty<T> {
     fn drop(&mut self) {
-        self.value.lock().unwrap().observed_properties.iter().for_each(|observable| {
-            observable.remove_observer(self.value.lock().unwrap().id);
-        });
+        // Clones share the subscriptions, only the last one removes them.
+        if Arc::strong_count(&self.value) > 1 {
+            return;
+        }
+        self.value.lock().unwrap().clear_inputs();
     }
 }
 
@@ -305,9 +566,12 @@ pub trait Gettable<T: Clone> {
     fn get(&self) -> T;
 }
 
-impl<T: Clone> Gettable<T> for SharedProperty<T> {
+impl<T: Clone + 'static> Gettable<T> for SharedProperty<T> {
+    /// Inside a tracked generator, see [`SharedProperty::from_tracked_generator`], the read is registered as an input.
     fn get(&self) -> T {
-        self.value.lock().unwrap().value.clone()
+        let property = self.value.lock().unwrap();
+        record_read(property.id, || Box::new(self.clone()));
+        property.value.clone()
     }
 }
 
@@ -336,7 +600,7 @@ impl<T: Clone + 'static> GeneratorProperty<T> {
     pub fn observe<O: 'static + Observable + Clone>(&mut self, observable: &O) {
         self.observed_properties.push(Box::new(observable.clone()));
         let observers = Arc::clone(&self.observers);
-        observable.add_observer(Observer::new(Box::new(move || {
+        observable.add_observer(Observer::regenerator(Box::new(move || {
             observers.lock().unwrap().iter_mut().for_each(|observer| {
                 observer.notify()
             });
@@ -490,7 +754,7 @@ impl<T> WrapperProperty<T> {
     pub fn observe<O: 'static + Observable + Clone>(&mut self, observable: &O) {
         self.observed_properties.push(Box::new(observable.clone()));
         let observers = Arc::clone(&self.observers);
-        observable.add_observer(Observer::new(Box::new(move || {
+        observable.add_observer(Observer::regenerator(Box::new(move || {
             observers.lock().unwrap().iter_mut().for_each(|observer| {
                 observer.notify()
             });
@@ -0,0 +1,66 @@
+use std::cell::Cell;
+
+use crate::property::{get_observable_id, Observable, Observer};
+
+thread_local! {
+    static LIVE_OBSERVERS: Cell<usize> = Cell::new(0);
+}
+
+pub(crate) fn observer_created() {
+    LIVE_OBSERVERS.with(|count| count.set(count.get() + 1));
+}
+
+pub(crate) fn observer_dropped() {
+    LIVE_OBSERVERS.with(|count| count.set(count.get() - 1));
+}
+
+/// The number of [`Observer`]s created on this thread that were not dropped yet.
+///
+/// Observers can not be sent to other threads, so in a long-running app a count that keeps growing means that
+/// observers are leaked, e.g. added to a property that outlives their owner without a [`Subscription`].
+///
+/// The count is per thread: observers created on other threads, e.g. by the UI of another window thread or by tests
+/// running in parallel, are not included, so it is not the number of observers of the whole process. Change listeners
+/// of a [`ListProperty`](crate::property::ListProperty) are not observers and are not counted either.
+pub fn live_observer_count() -> usize {
+    LIVE_OBSERVERS.with(|count| count.get())
+}
+
+/// Removes an observer when dropped, see [`subscribe`].
+#[must_use = "the observer is removed when the subscription is dropped"]
+pub struct Subscription {
+    unsubscribe: Option<Box<dyn FnOnce()>>,
+}
+
+impl Subscription {
+    /// `unsubscribe` is called once, when the subscription is dropped.
+    pub fn new(unsubscribe: impl FnOnce() + 'static) -> Self {
+        Self {
+            unsubscribe: Some(Box::new(unsubscribe)),
+        }
+    }
+
+    /// Keep the observer for as long as the observable lives.
+    pub fn detach(mut self) {
+        self.unsubscribe = None;
+    }
+}
+
+impl Drop for Subscription {
+    fn drop(&mut self) {
+        if let Some(unsubscribe) = self.unsubscribe.take() {
+            unsubscribe();
+        }
+    }
+}
+
+/// Add an observer with an owner id of its own and return the subscription that removes it again.
+///
+/// Unlike an observer added with [`Observer::new_without_id`], which can only be removed together with all other
+/// observers, the observer and everything it captures are freed when the subscription is dropped.
+pub fn subscribe<O: Observable + Clone + 'static>(observable: &O, listener: impl FnMut() + 'static) -> Subscription {
+    let owner_id = get_observable_id();
+    observable.add_observer(Observer::new(listener, owner_id));
+    let observable = observable.clone();
+    Subscription::new(move || observable.remove_observer(owner_id))
+}
@@ -0,0 +1,41 @@
+use std::cell::RefCell;
+
+use crate::property::Observable;
+
+thread_local! {
+    /// One frame per tracked generator that is running on this thread, the innermost last.
+    static TRACKING_FRAMES: RefCell<Vec<Vec<(usize, Box<dyn Observable>)>>> = RefCell::new(Vec::new());
+}
+
+/// Pops the frame of a tracked generator when dropped, also when the generator panics.
+struct Frame;
+
+impl Drop for Frame {
+    fn drop(&mut self) {
+        // The thread local may already be gone if the thread is exiting.
+        let _ = TRACKING_FRAMES.try_with(|frames| frames.borrow_mut().pop());
+    }
+}
+
+/// Run `f` and collect the properties it reads with [`Gettable::get`](crate::property::Gettable::get), by their id.
+/// Properties read by a nested tracked generator belong to that generator only.
+pub(crate) fn track_reads<T>(f: impl FnOnce() -> T) -> (T, Vec<(usize, Box<dyn Observable>)>) {
+    TRACKING_FRAMES.with(|frames| frames.borrow_mut().push(Vec::new()));
+    let frame = Frame;
+    let value = f();
+    let inputs = TRACKING_FRAMES.with(|frames| std::mem::take(frames.borrow_mut().last_mut().unwrap()));
+    drop(frame);
+    (value, inputs)
+}
+
+/// Register a read of the property with the given id in the innermost running tracked generator, if any.
+/// `observable` is only called the first time the property is read by that generator.
+pub(crate) fn record_read(id: usize, observable: impl FnOnce() -> Box<dyn Observable>) {
+    TRACKING_FRAMES.with(|frames| {
+        if let Some(frame) = frames.borrow_mut().last_mut() {
+            if !frame.iter().any(|(input_id, _)| *input_id == id) {
+                frame.push((id, observable()));
+            }
+        }
+    });
+}
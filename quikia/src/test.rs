@@ -5,4 +5,2353 @@ mod main_test{
     fn test(){
         println!("test");
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod golden_test {
+    use skia_safe::Color;
+
+    use crate::layout::{FlexAlign, FlexLayout};
+    use crate::property::Size;
+    use crate::testing::{assert_golden, GoldenOptions};
+    use crate::theme::material_theme;
+    use crate::widget::RectangleExt;
+
+    fn golden_path(name: &str) -> String {
+        format!("{}/golden/{}.png", env!("CARGO_MANIFEST_DIR"), name)
+    }
+
+    #[test]
+    fn rectangle() {
+        assert_golden(golden_path("rectangle"), GoldenOptions::new(64.0, 48.0), material_theme(Color::BLUE, false), |app| {
+            app.rectangle()
+                .color(Color::RED)
+                .item()
+        });
+    }
+
+    #[test]
+    fn rectangle_radius_scaled() {
+        assert_golden(golden_path("rectangle_radius_scaled"), GoldenOptions::new(64.0, 48.0).scale_factor(2.0), material_theme(Color::BLUE, false), |app| {
+            app.rectangle()
+                .color(Color::BLUE)
+                .radius(12.0)
+                .item()
+                .padding_start(8)
+                .padding_top(8)
+                .padding_end(8)
+                .padding_bottom(8)
+        });
+    }
+
+    #[test]
+    fn flex_layout() {
+        assert_golden(golden_path("flex_layout"), GoldenOptions::new(96.0, 64.0), material_theme(Color::BLUE, false), |app| {
+            FlexLayout::new(app.clone(), vec![
+                app.rectangle().color(Color::RED).item().width(Size::Fixed(40.0)).height(Size::Fixed(20.0)),
+                app.rectangle().color(Color::GREEN).item().width(Size::Fixed(40.0)).height(Size::Fixed(30.0)),
+                app.rectangle().color(Color::BLUE).item().width(Size::Fixed(40.0)).height(Size::Fixed(20.0)),
+            ]).justify_content(FlexAlign::SpaceBetween)
+                .unwrap()
+        });
+    }
+}
+
+#[cfg(test)]
+mod gesture_test {
+    use std::time::{Duration, Instant};
+
+    use winit::event::MouseButton;
+
+    use crate::ui::{GestureEvent, GestureRecognizer, LayoutParams, PointerAction, PointerType};
+
+    const POINTER: PointerType = PointerType::Cursor { mouse_button: Some(MouseButton::Left) };
+
+    fn bounds() -> LayoutParams {
+        let mut layout_params = LayoutParams::default();
+        layout_params.width = 100.0;
+        layout_params.height = 50.0;
+        layout_params
+    }
+
+    fn down(x: f32, y: f32) -> PointerAction {
+        PointerAction::Down { x, y, pointer_type: POINTER }
+    }
+
+    fn moved(x: f32, y: f32) -> PointerAction {
+        PointerAction::Move { x, y, pointer_type: POINTER }
+    }
+
+    fn up(x: f32, y: f32) -> PointerAction {
+        PointerAction::Up { x, y, pointer_type: POINTER }
+    }
+
+    #[test]
+    fn click_and_double_click() {
+        let mut recognizer = GestureRecognizer::default();
+        let now = Instant::now();
+        assert_eq!(recognizer.handle(down(10.0, 10.0), &bounds(), now), vec![GestureEvent::Pressed]);
+        assert_eq!(recognizer.handle(up(10.0, 10.0), &bounds(), now + Duration::from_millis(50)), vec![GestureEvent::Click]);
+        recognizer.handle(down(11.0, 10.0), &bounds(), now + Duration::from_millis(100));
+        assert_eq!(recognizer.handle(up(11.0, 10.0), &bounds(), now + Duration::from_millis(150)), vec![GestureEvent::Click, GestureEvent::DoubleClick]);
+    }
+
+    #[test]
+    fn long_press_suppresses_click() {
+        let mut recognizer = GestureRecognizer::default();
+        let now = Instant::now();
+        recognizer.handle(down(10.0, 10.0), &bounds(), now);
+        assert_eq!(recognizer.check_long_press(now + Duration::from_millis(100)), None);
+        assert_eq!(recognizer.check_long_press(now + Duration::from_millis(600)), Some(GestureEvent::LongPress));
+        assert!(recognizer.handle(up(10.0, 10.0), &bounds(), now + Duration::from_millis(700)).is_empty());
+    }
+
+    #[test]
+    fn leaving_bounds_cancels() {
+        let mut recognizer = GestureRecognizer::default();
+        let now = Instant::now();
+        recognizer.handle(down(10.0, 10.0), &bounds(), now);
+        assert_eq!(recognizer.handle(moved(150.0, 10.0), &bounds(), now), vec![GestureEvent::Cancel]);
+        assert!(recognizer.handle(up(10.0, 10.0), &bounds(), now).is_empty());
+    }
+
+    #[test]
+    fn moving_beyond_slop_prevents_long_press() {
+        let mut recognizer = GestureRecognizer::default();
+        let now = Instant::now();
+        recognizer.handle(down(10.0, 10.0), &bounds(), now);
+        recognizer.handle(moved(40.0, 10.0), &bounds(), now + Duration::from_millis(10));
+        assert_eq!(recognizer.check_long_press(now + Duration::from_millis(600)), None);
+        assert_eq!(recognizer.handle(up(40.0, 10.0), &bounds(), now + Duration::from_millis(700)), vec![GestureEvent::Click]);
+    }
+}
+
+#[cfg(test)]
+mod dispatch_test {
+    use std::cell::{Cell, RefCell};
+    use std::rc::Rc;
+    use std::time::Duration;
+
+    use skia_safe::Color;
+    use winit::event::MouseButton;
+    use winit::window::CursorIcon;
+
+    use crate::app::{Headless, SharedApp};
+    use crate::layout::Stack;
+    use crate::property::Size;
+    use crate::theme::material_theme;
+    use crate::ui::{GestureConfig, Item, ItemEvent, PointerAction, PointerType};
+
+    const POINTER: PointerType = PointerType::Cursor { mouse_button: Some(MouseButton::Left) };
+
+    /// A square item at the start of its parent that logs the pointer downs it receives.
+    fn leaf(app: SharedApp, tag: &str, size: f32, consume: bool, log: &Rc<RefCell<Vec<String>>>) -> Item {
+        let log = log.clone();
+        Item::new(
+            app,
+            ItemEvent::default()
+                .set_measure_event(|item, width_measure_mode, height_measure_mode| {
+                    let mut layout_params = item.get_layout_params().clone();
+                    layout_params.init_from_item(item);
+                    layout_params.width = width_measure_mode.value();
+                    layout_params.height = height_measure_mode.value();
+                    item.set_layout_params(&layout_params);
+                })
+                .set_layout_event(|item, x, y| {
+                    let mut layout_params = item.get_layout_params().clone();
+                    layout_params.relative_x = x;
+                    layout_params.relative_y = y;
+                    item.set_layout_params(&layout_params);
+                })
+                .set_on_pointer_input(move |item, action| {
+                    if let PointerAction::Down { .. } = action {
+                        log.borrow_mut().push(item.get_tag().to_string());
+                    }
+                    consume
+                })
+        ).tag(tag).width(Size::Fixed(size)).height(Size::Fixed(size))
+    }
+
+    /// A 100x100 item below a 50x50 item, both at the top left corner.
+    fn headless(top_consumes: bool) -> (Headless, Rc<RefCell<Vec<String>>>) {
+        let log = Rc::new(RefCell::new(Vec::new()));
+        let mut headless = Headless::new(200.0, 100.0, 1.0, material_theme(Color::BLUE, false), {
+            let log = log.clone();
+            move |app| {
+                Stack::new(app.clone(), vec![
+                    leaf(app.clone(), "bottom", 100.0, false, &log),
+                    leaf(app.clone(), "top", 50.0, top_consumes, &log),
+                ]).unwrap()
+            }
+        });
+        headless.render();
+        (headless, log)
+    }
+
+    fn down(x: f32, y: f32) -> PointerAction {
+        PointerAction::Down { x, y, pointer_type: POINTER }
+    }
+
+    #[test]
+    fn top_most_child_receives_the_event_first() {
+        let (mut headless, log) = headless(false);
+        assert!(!headless.pointer_input(down(25.0, 25.0)));
+        assert_eq!(*log.borrow(), vec!["top", "bottom"]);
+    }
+
+    #[test]
+    fn consumed_event_does_not_propagate() {
+        let (mut headless, log) = headless(true);
+        assert!(headless.pointer_input(down(25.0, 25.0)));
+        assert_eq!(*log.borrow(), vec!["top"]);
+    }
+
+    #[test]
+    fn only_items_under_the_pointer_are_hit() {
+        let (mut headless, log) = headless(true);
+        headless.pointer_input(down(75.0, 75.0));
+        assert_eq!(*log.borrow(), vec!["bottom"]);
+        log.borrow_mut().clear();
+        headless.pointer_input(down(150.0, 50.0));
+        assert!(log.borrow().is_empty());
+    }
+
+    #[test]
+    fn caught_pointer_follows_the_item_when_its_siblings_change() {
+        let log = Rc::new(RefCell::new(Vec::new()));
+        let mut headless = Headless::new(200.0, 100.0, 1.0, material_theme(Color::BLUE, false), {
+            let log = log.clone();
+            move |app| {
+                let clicked = log.clone();
+                Stack::new(app.clone(), vec![
+                    leaf(app.clone(), "button", 50.0, false, &log)
+                        .on_click(move || clicked.borrow_mut().push("clicked".to_string())),
+                ]).unwrap()
+            }
+        });
+        headless.render();
+        headless.pointer_input(down(25.0, 25.0));
+
+        // Growing the children moves the button in memory, and the new item covers it and consumes every event.
+        let app = headless.app();
+        headless.item_mut().get_children().lock().add(leaf(app, "cover", 100.0, true, &log));
+        headless.render();
+        headless.pointer_input(PointerAction::Up { x: 25.0, y: 25.0, pointer_type: POINTER });
+        assert_eq!(*log.borrow(), vec!["button", "clicked"]);
+    }
+
+    #[test]
+    fn long_press_timer_reaches_the_item_after_it_moved() {
+        let log = Rc::new(RefCell::new(Vec::new()));
+        let id = Cell::new(0);
+        let mut headless = Headless::new(200.0, 100.0, 1.0, material_theme(Color::BLUE, false), |app| {
+            let long_pressed = log.clone();
+            let item = leaf(app.clone(), "button", 50.0, false, &log)
+                .gesture_config(GestureConfig { long_press_timeout: Duration::ZERO, ..GestureConfig::default() })
+                .on_long_press(move || long_pressed.borrow_mut().push("long press".to_string()));
+            // The id the timer is started with, taken before the item is moved into the stack.
+            id.set(item.get_id());
+            Stack::new(app.clone(), vec![item]).unwrap()
+        });
+        headless.render();
+        headless.pointer_input(down(25.0, 25.0));
+
+        // What the event loop does when the timer sends its event.
+        headless.item_mut().find_item_mut(id.get(), |item| item.timer_expired("long_press"));
+        assert_eq!(*log.borrow(), vec!["button", "long press"]);
+    }
+
+    /// Entering and leaving the top item is logged, and it requests the pointer cursor.
+    fn hover_headless() -> (Headless, Rc<RefCell<Vec<String>>>) {
+        let log = Rc::new(RefCell::new(Vec::new()));
+        let mut headless = Headless::new(200.0, 100.0, 1.0, material_theme(Color::BLUE, false), {
+            let log = log.clone();
+            move |app| {
+                let entered = log.clone();
+                let exited = log.clone();
+                Stack::new(app.clone(), vec![
+                    leaf(app.clone(), "bottom", 100.0, false, &log),
+                    leaf(app.clone(), "top", 50.0, false, &log)
+                        .cursor_icon(Some(CursorIcon::Pointer))
+                        .on_cursor_entered(move || entered.borrow_mut().push("entered".to_string()))
+                        .on_cursor_exited(move || exited.borrow_mut().push("exited".to_string())),
+                ]).unwrap()
+            }
+        });
+        headless.render();
+        (headless, log)
+    }
+
+    #[test]
+    fn hover_enters_and_leaves() {
+        let (mut headless, log) = hover_headless();
+        headless.cursor_moved(Some((25.0, 25.0)));
+        assert_eq!(*log.borrow(), vec!["entered"]);
+        assert_eq!(headless.app().cursor_icon(), CursorIcon::Pointer);
+
+        headless.cursor_moved(Some((30.0, 30.0)));
+        assert_eq!(*log.borrow(), vec!["entered"]);
+
+        headless.cursor_moved(Some((75.0, 75.0)));
+        assert_eq!(*log.borrow(), vec!["entered", "exited"]);
+        assert_eq!(headless.app().cursor_icon(), CursorIcon::Default);
+    }
+
+    #[test]
+    fn leaving_the_window_exits_hovered_items() {
+        let (mut headless, log) = hover_headless();
+        headless.cursor_moved(Some((25.0, 25.0)));
+        headless.cursor_moved(None);
+        assert_eq!(*log.borrow(), vec!["entered", "exited"]);
+        assert_eq!(headless.app().cursor_icon(), CursorIcon::Default);
+    }
+
+    #[test]
+    fn cursor_moves_without_pressed_buttons_have_no_button() {
+        let hover = PointerAction::from_cursor_moved(&[], 10.0, 10.0);
+        assert_eq!(hover.pointer_type(), Some(PointerType::Cursor { mouse_button: None }));
+        let drag = PointerAction::from_cursor_moved(&[MouseButton::Right, MouseButton::Left], 10.0, 10.0);
+        assert_eq!(drag.pointer_type(), Some(PointerType::Cursor { mouse_button: Some(MouseButton::Right) }));
+    }
+}
+
+#[cfg(test)]
+mod focus_test {
+    use skia_safe::Color;
+
+    use crate::app::Headless;
+    use crate::layout::Stack;
+    use crate::property::BoolProperty;
+    use crate::theme::material_theme;
+    use crate::ui::{Item, ItemEvent, TextBlock};
+
+    /// Three focusable items and one that is not, in tree order.
+    fn headless() -> Headless {
+        let mut headless = Headless::new(200.0, 100.0, 1.0, material_theme(Color::BLUE, false), |app| {
+            Stack::new(app.clone(), vec![
+                Item::new(app.clone(), ItemEvent::default()).tag("a").focusable(true),
+                Item::new(app.clone(), ItemEvent::default()).tag("plain"),
+                Stack::new(app.clone(), vec![
+                    Item::new(app.clone(), ItemEvent::default()).tag("b").focusable(true),
+                ]).unwrap(),
+                Item::new(app.clone(), ItemEvent::default()).tag("c").focusable(true),
+            ]).unwrap()
+        });
+        headless.layout();
+        headless
+    }
+
+    fn focused_tag(headless: &mut Headless) -> Option<String> {
+        let id = headless.app().focused_item_id()?;
+        headless.item_mut().find_item_mut(id, |item| item.get_tag().to_string())
+    }
+
+    #[test]
+    fn tab_visits_focusable_items_in_tree_order() {
+        let mut headless = headless();
+        let mut tags = Vec::new();
+        for _ in 0..3 {
+            assert!(headless.move_focus(false));
+            tags.push(focused_tag(&mut headless).unwrap());
+        }
+        assert_eq!(tags, vec!["a", "b", "c"]);
+    }
+
+    #[test]
+    fn tab_wraps_around() {
+        let mut headless = headless();
+        for _ in 0..3 {
+            headless.move_focus(false);
+        }
+        assert!(headless.move_focus(false));
+        assert_eq!(focused_tag(&mut headless).as_deref(), Some("a"));
+    }
+
+    #[test]
+    fn shift_tab_goes_backward_and_wraps() {
+        let mut headless = headless();
+        assert!(headless.move_focus(true));
+        assert_eq!(focused_tag(&mut headless).as_deref(), Some("c"));
+        headless.move_focus(true);
+        assert_eq!(focused_tag(&mut headless).as_deref(), Some("b"));
+        headless.move_focus(true);
+        headless.move_focus(true);
+        assert_eq!(focused_tag(&mut headless).as_deref(), Some("c"));
+    }
+
+    #[test]
+    fn tab_is_not_consumed_without_another_item_to_focus() {
+        let mut single = Headless::new(200.0, 100.0, 1.0, material_theme(Color::BLUE, false), |app| {
+            Stack::new(app.clone(), vec![
+                Item::new(app.clone(), ItemEvent::default()).tag("only").focusable(true),
+            ]).unwrap()
+        });
+        assert!(single.move_focus(false));
+        assert!(!single.move_focus(false));
+
+        let mut none = Headless::new(200.0, 100.0, 1.0, material_theme(Color::BLUE, false), |app| Item::new(app, ItemEvent::default()));
+        assert!(!none.move_focus(false));
+    }
+
+    #[test]
+    fn focused_property_focuses_the_item_after_it_was_moved() {
+        let focused = BoolProperty::from_value(false);
+        let mut headless = Headless::new(200.0, 100.0, 1.0, material_theme(Color::BLUE, false), {
+            let focused = focused.clone();
+            move |app| {
+                // The item is moved into the vector and then into the stack after `focused` was set up.
+                let item = Item::new(app.clone(), ItemEvent::default()).tag("a").focusable(true).focused(focused);
+                Stack::new(app.clone(), vec![
+                    Item::new(app.clone(), ItemEvent::default()).tag("b").focusable(true),
+                    item,
+                ]).unwrap()
+            }
+        });
+        headless.layout();
+
+        // The observer reads the property it observes, which needs the notification to happen after the unlock.
+        focused.set_value(true);
+        headless.apply_focus_request();
+        assert_eq!(focused_tag(&mut headless).as_deref(), Some("a"));
+
+        focused.set_value(false);
+        headless.apply_focus_request();
+        assert_eq!(focused_tag(&mut headless), None);
+    }
+
+    #[test]
+    fn text_block_is_focusable_without_opting_in() {
+        let mut headless = Headless::new(200.0, 100.0, 1.0, material_theme(Color::BLUE, false), |app| {
+            Stack::new(app.clone(), vec![
+                Item::new(app.clone(), ItemEvent::default()).tag("plain"),
+                TextBlock::new(app.clone()).text("text").unwrap().tag("text"),
+            ]).unwrap()
+        });
+        headless.layout();
+        assert!(headless.move_focus(false));
+        assert_eq!(focused_tag(&mut headless).as_deref(), Some("text"));
+    }
+}
+
+#[cfg(test)]
+mod ime_test {
+    use std::ops::Range;
+
+    use skia_safe::Color;
+
+    use crate::app::SharedApp;
+    use crate::property::{Gettable, SharedProperty, TextProperty};
+    use crate::theme::material_theme;
+    use crate::ui::{ImeAction, Item, MeasureMode, TextBlock};
+
+    fn text_block(text: &TextProperty) -> (Item, SharedProperty<Range<usize>>) {
+        let app = SharedApp::new_headless(material_theme(Color::BLUE, false), 200.0, 100.0, 1.0);
+        let text_block = TextBlock::new(app).text(text.clone());
+        let selection = text_block.get_selection();
+        let mut item = text_block.unwrap();
+        item.measure(MeasureMode::Specified(200.0), MeasureMode::Specified(100.0));
+        (item, selection)
+    }
+
+    #[test]
+    fn preedit_is_inserted_at_the_cursor() {
+        let text = TextProperty::from_str("ab");
+        let (mut item, selection) = text_block(&text);
+        selection.set_value(1..1);
+        assert!(item.ime_input(ImeAction::Preedit("xy".to_string(), Some((1, 1)))));
+        assert_eq!(text.get().as_str(), "axyb");
+        assert_eq!(selection.get(), 2..2);
+
+        // The next preedit text replaces the previous one.
+        item.ime_input(ImeAction::Preedit("xyz".to_string(), None));
+        assert_eq!(text.get().as_str(), "axyzb");
+        assert_eq!(selection.get(), 4..4);
+    }
+
+    #[test]
+    fn preedit_cursor_inside_a_multi_byte_char_is_clamped() {
+        let text = TextProperty::from_str("");
+        let (mut item, selection) = text_block(&text);
+        // Byte 1 and 4 are inside the three byte chars.
+        item.ime_input(ImeAction::Preedit("日本".to_string(), Some((1, 4))));
+        assert_eq!(text.get().as_str(), "日本");
+        assert_eq!(selection.get(), 0..3);
+    }
+
+    #[test]
+    fn commit_replaces_the_preedit() {
+        let text = TextProperty::from_str("");
+        let (mut item, selection) = text_block(&text);
+        item.ime_input(ImeAction::Preedit("にほん".to_string(), None));
+        item.ime_input(ImeAction::Commit("日本".to_string()));
+        assert_eq!(text.get().as_str(), "日本");
+        assert_eq!(selection.get(), 6..6);
+    }
+
+    #[test]
+    fn disabling_the_ime_cancels_the_preedit() {
+        let text = TextProperty::from_str("ab");
+        let (mut item, selection) = text_block(&text);
+        selection.set_value(2..2);
+        item.ime_input(ImeAction::Preedit("にほん".to_string(), None));
+        item.ime_input(ImeAction::Disabled);
+        assert_eq!(text.get().as_str(), "ab");
+        assert_eq!(selection.get(), 2..2);
+    }
+}
+
+#[cfg(test)]
+mod layout_test {
+    use skia_safe::Color;
+
+    use crate::app::{layout_frame, SharedApp};
+    use crate::layout::{Axis, AxisLayout, ChainStyle, ConstraintError, ConstraintLayout, FlexAlign, FlexLayout, FlexWrap, Flow, Grid, GridTrack, ItemAlign, Overlay, Stack};
+    use crate::property::{Gettable, Size};
+    use crate::theme::material_theme;
+    use crate::ui::{Gravity, Item, LayoutDirection, LayoutParams, MeasureMode, TextBlock};
+    use crate::ui::additional_property::{AbsolutePlacement, ConstraintPlacement, FlexItem, GridPlacement, PARENT};
+    use crate::widget::RectangleExt;
+
+    fn app() -> SharedApp {
+        SharedApp::new_headless(material_theme(Color::BLUE, false), 200.0, 100.0, 1.0)
+    }
+
+    fn child(app: &SharedApp, width: impl Into<Size>, height: impl Into<Size>) -> Item {
+        app.rectangle().item().width(width.into()).height(height.into())
+    }
+
+    fn layout(item: &mut Item, width: f32, height: f32) {
+        layout_frame(item, width, height);
+    }
+
+    fn child_params(item: &Item, index: usize) -> LayoutParams {
+        item.get_children().lock().get(index).unwrap().get_layout_params().clone()
+    }
+
+    fn assert_bounds(layout_params: &LayoutParams, x: f32, y: f32, width: f32, height: f32) {
+        assert_eq!(
+            (layout_params.relative_x, layout_params.relative_y, layout_params.width, layout_params.height),
+            (x, y, width, height)
+        );
+    }
+
+    #[test]
+    fn stack_aligns_children_by_gravity() {
+        let app = app();
+        let mut stack = Stack::new(app.clone(), vec![
+            child(&app, 50, 20),
+            child(&app, Size::Fill, Size::Fill),
+        ]).unwrap()
+            .horizontal_gravity(Gravity::Center)
+            .vertical_gravity(Gravity::End);
+        layout(&mut stack, 200.0, 100.0);
+
+        assert_bounds(&child_params(&stack, 0), 75.0, 80.0, 50.0, 20.0);
+        assert_bounds(&child_params(&stack, 1), 0.0, 0.0, 200.0, 100.0);
+    }
+
+    #[test]
+    fn contradicting_min_and_max_do_not_panic() {
+        let app = app();
+        let mut stack = Stack::new(app.clone(), vec![child(&app, 50, 20)]).unwrap()
+            .min_width(80)
+            .max_width(40)
+            .max_height(f32::NAN);
+        stack.measure(MeasureMode::Unspecified(200.0), MeasureMode::Unspecified(100.0));
+
+        // The min wins, and a NaN bound is ignored.
+        assert_eq!((stack.get_layout_params().width, stack.get_layout_params().height), (80.0, 20.0));
+    }
+
+    #[test]
+    fn overlay_positions_absolute_children_from_edges() {
+        let app = app();
+        let mut overlay = Overlay::new(app.clone(), vec![
+            child(&app, 100, 60),
+            child(&app, 16, 16).offset_top(-4.0).offset_end(-4.0),
+            child(&app, Size::Default, 10).offset_start(10.0).offset_end(20.0).offset_bottom(0.0),
+        ]).unwrap();
+        overlay.measure(MeasureMode::Unspecified(200.0), MeasureMode::Unspecified(100.0));
+        overlay.layout(0.0, 0.0);
+
+        // Absolute children do not affect the size of the overlay.
+        assert_eq!((overlay.get_layout_params().width, overlay.get_layout_params().height), (100.0, 60.0));
+        assert_bounds(&child_params(&overlay, 0), 0.0, 0.0, 100.0, 60.0);
+        assert_bounds(&child_params(&overlay, 1), 88.0, -4.0, 16.0, 16.0);
+        // Offsets from both edges stretch a child of the default size.
+        assert_bounds(&child_params(&overlay, 2), 10.0, 50.0, 70.0, 10.0);
+    }
+
+    #[test]
+    fn overlay_mirrors_offsets_in_right_to_left() {
+        let app = app();
+        let mut overlay = Overlay::new(app.clone(), vec![
+            child(&app, 100, 60),
+            child(&app, 16, 16).offset_top(-4.0).offset_end(-4.0),
+        ]).unwrap()
+            .layout_direction(LayoutDirection::RightToLeft);
+        overlay.measure(MeasureMode::Unspecified(200.0), MeasureMode::Unspecified(100.0));
+        overlay.layout(0.0, 0.0);
+
+        assert_bounds(&child_params(&overlay, 1), -4.0, -4.0, 16.0, 16.0);
+    }
+
+    #[test]
+    fn z_index_changes_draw_and_hit_test_order() {
+        let app = app();
+        let mut overlay = Overlay::new(app.clone(), vec![
+            child(&app, 50, 50).tag("raised").z_index(1),
+            child(&app, 50, 50).tag("plain"),
+            child(&app, 50, 50).tag("lowered").z_index(-1),
+        ]).unwrap();
+        layout(&mut overlay, 200.0, 100.0);
+
+        let draw_order: Vec<String> = overlay.get_children().lock().z_ordered_mut().iter().map(|child| child.get_tag().to_string()).collect();
+        assert_eq!(draw_order, vec!["lowered", "plain", "raised"]);
+
+        let mut hit = None;
+        overlay.dispatch_at(25.0, 25.0, &mut |item| {
+            hit = Some(item.get_tag().to_string());
+            true
+        });
+        assert_eq!(hit.as_deref(), Some("raised"));
+        // The collection keeps its order.
+        assert_eq!(overlay.get_children().lock().get(0).unwrap().get_tag(), "raised");
+    }
+
+    #[test]
+    fn intrinsic_size_defaults_to_measure() {
+        let app = app();
+        let mut stack = Stack::new(app.clone(), vec![child(&app, 30, 20)]).unwrap()
+            .padding_start(5)
+            .padding_end(5);
+        layout(&mut stack, 200.0, 100.0);
+
+        assert_eq!(stack.max_intrinsic_width(100.0), 40.0);
+        assert_eq!(stack.min_intrinsic_height(200.0), 20.0);
+        // The query does not change the committed layout.
+        assert_eq!(stack.get_layout_params().width, 200.0);
+
+        let mut fixed = child(&app, 60, Size::Default);
+        assert_eq!(fixed.min_intrinsic_width(100.0), 60.0);
+    }
+
+    #[test]
+    fn intrinsic_size_of_fill_children_is_their_content() {
+        let app = app();
+        let mut stack = Stack::new(app.clone(), vec![
+            Stack::new(app.clone(), vec![child(&app, 30, 20)]).unwrap().width(Size::Fill),
+        ]).unwrap();
+        layout(&mut stack, 200.0, 100.0);
+
+        assert_eq!(stack.max_intrinsic_width(100.0), 30.0);
+        // The descendants keep their committed sizes and the layout they were measured for.
+        assert_eq!(child_params(&stack, 0).width, 200.0);
+        layout(&mut stack, 200.0, 100.0);
+        assert_eq!(child_params(&stack, 0).width, 200.0);
+    }
+
+    #[test]
+    fn text_block_intrinsic_size() {
+        let app = app();
+        let mut text_block = TextBlock::new(app).text("intrinsic sizes of text").unwrap();
+        let min_width = text_block.min_intrinsic_width(f32::MAX);
+        let max_width = text_block.max_intrinsic_width(f32::MAX);
+        assert!(min_width > 1.0);
+        assert!(min_width < max_width);
+        // The longest word fits, but the text wraps into more lines than at its unwrapped width.
+        assert!(text_block.max_intrinsic_height(min_width) > text_block.max_intrinsic_height(max_width));
+    }
+
+    #[test]
+    fn axis_layout_horizontal_fill_takes_remaining_space() {
+        let app = app();
+        let mut axis_layout = AxisLayout::new(app.clone(), vec![
+            child(&app, 50, 20),
+            child(&app, Size::Fill, 10),
+            child(&app, 30, 40),
+        ]).unwrap();
+        layout(&mut axis_layout, 200.0, 100.0);
+
+        assert_bounds(&child_params(&axis_layout, 0), 0.0, 0.0, 50.0, 20.0);
+        assert_bounds(&child_params(&axis_layout, 1), 50.0, 0.0, 120.0, 10.0);
+        assert_bounds(&child_params(&axis_layout, 2), 170.0, 0.0, 30.0, 40.0);
+    }
+
+    #[test]
+    fn axis_layout_vertical() {
+        let app = app();
+        let mut axis_layout = AxisLayout::new(app.clone(), vec![
+            child(&app, 50, 20),
+            child(&app, Size::Fill, Size::Fill),
+        ]).axis(Axis::Vertical)
+            .unwrap()
+            .horizontal_gravity(Gravity::End);
+        layout(&mut axis_layout, 200.0, 100.0);
+
+        assert_bounds(&child_params(&axis_layout, 0), 150.0, 0.0, 50.0, 20.0);
+        assert_bounds(&child_params(&axis_layout, 1), 0.0, 20.0, 200.0, 80.0);
+    }
+
+    #[test]
+    fn axis_layout_right_to_left() {
+        let app = app();
+        let mut axis_layout = AxisLayout::new(app.clone(), vec![
+            child(&app, 50, 20),
+            child(&app, 30, 20),
+        ]).unwrap()
+            .layout_direction(LayoutDirection::RightToLeft);
+        layout(&mut axis_layout, 200.0, 100.0);
+
+        assert_bounds(&child_params(&axis_layout, 0), 150.0, 0.0, 50.0, 20.0);
+        assert_bounds(&child_params(&axis_layout, 1), 120.0, 0.0, 30.0, 20.0);
+    }
+
+    #[test]
+    fn flow_wraps_children() {
+        let app = app();
+        let mut flow = Flow::new(app.clone(), vec![
+            child(&app, 80, 20),
+            child(&app, 80, 30),
+            child(&app, 80, 20),
+            child(&app, Size::Fill, 10),
+        ]).unwrap();
+        layout(&mut flow, 200.0, 100.0);
+
+        assert_bounds(&child_params(&flow, 0), 0.0, 0.0, 80.0, 20.0);
+        assert_bounds(&child_params(&flow, 1), 80.0, 0.0, 80.0, 30.0);
+        assert_bounds(&child_params(&flow, 2), 0.0, 30.0, 80.0, 20.0);
+        assert_bounds(&child_params(&flow, 3), 0.0, 50.0, 200.0, 10.0);
+    }
+
+    #[test]
+    fn flex_layout_wraps_and_justifies() {
+        let app = app();
+        let mut flex_layout = FlexLayout::new(app.clone(), vec![
+            child(&app, 80, 20),
+            child(&app, 80, 20),
+            child(&app, 80, 30),
+        ]).justify_content(FlexAlign::SpaceBetween)
+            .unwrap();
+        layout(&mut flex_layout, 200.0, 100.0);
+
+        assert_bounds(&child_params(&flex_layout, 0), 0.0, 0.0, 80.0, 20.0);
+        assert_bounds(&child_params(&flex_layout, 1), 120.0, 0.0, 80.0, 20.0);
+        assert_bounds(&child_params(&flex_layout, 2), 60.0, 20.0, 80.0, 30.0);
+    }
+
+    #[test]
+    fn flex_layout_no_wrap_fill_and_center() {
+        let app = app();
+        let mut flex_layout = FlexLayout::new(app.clone(), vec![
+            child(&app, 50, 20),
+            child(&app, Size::Fill, 40),
+        ]).flex_wrap(FlexWrap::NoWrap)
+            .align_items(ItemAlign::Center)
+            .unwrap();
+        layout(&mut flex_layout, 200.0, 100.0);
+
+        assert_bounds(&child_params(&flex_layout, 0), 0.0, 40.0, 50.0, 20.0);
+        assert_bounds(&child_params(&flex_layout, 1), 50.0, 30.0, 150.0, 40.0);
+    }
+
+    #[test]
+    fn flex_layout_grows_and_shrinks_children() {
+        let app = app();
+        let mut grow = FlexLayout::new(app.clone(), vec![
+            child(&app, 50, 20).flex_grow(1.0),
+            child(&app, 50, 20).flex_grow(3.0),
+        ]).flex_wrap(FlexWrap::NoWrap)
+            .unwrap();
+        layout(&mut grow, 200.0, 100.0);
+        assert_bounds(&child_params(&grow, 0), 0.0, 0.0, 75.0, 20.0);
+        assert_bounds(&child_params(&grow, 1), 75.0, 0.0, 125.0, 20.0);
+
+        // The shrink factors are weighted by the basis: 3 * 100 and 1 * 200.
+        let mut shrink = FlexLayout::new(app.clone(), vec![
+            child(&app, 100, 20).flex_shrink(3.0),
+            child(&app, 200, 20),
+        ]).flex_wrap(FlexWrap::NoWrap)
+            .unwrap();
+        layout(&mut shrink, 200.0, 100.0);
+        assert_bounds(&child_params(&shrink, 0), 0.0, 0.0, 40.0, 20.0);
+        assert_bounds(&child_params(&shrink, 1), 40.0, 0.0, 160.0, 20.0);
+    }
+
+    #[test]
+    fn flex_layout_grows_wrapped_rows_from_their_basis() {
+        let app = app();
+        let mut flex_layout = FlexLayout::new(app.clone(), vec![
+            child(&app, 10, 20).flex_basis(120.0).flex_grow(1.0),
+            child(&app, 10, 20).flex_basis(50.0),
+            child(&app, 10, 20).flex_basis(50.0).flex_grow(1.0),
+        ]).unwrap();
+        layout(&mut flex_layout, 200.0, 100.0);
+
+        assert_bounds(&child_params(&flex_layout, 0), 0.0, 0.0, 150.0, 20.0);
+        assert_bounds(&child_params(&flex_layout, 1), 150.0, 0.0, 50.0, 20.0);
+        assert_bounds(&child_params(&flex_layout, 2), 0.0, 20.0, 200.0, 20.0);
+    }
+
+    #[test]
+    fn flex_layout_align_self() {
+        let app = app();
+        let mut flex_layout = FlexLayout::new(app.clone(), vec![
+            child(&app, 50, 20),
+            child(&app, 50, 20).align_self(ItemAlign::End),
+            child(&app, 50, Size::Default).align_self(ItemAlign::Stretch),
+        ]).flex_wrap(FlexWrap::NoWrap)
+            .unwrap();
+        layout(&mut flex_layout, 200.0, 100.0);
+
+        assert_bounds(&child_params(&flex_layout, 0), 0.0, 0.0, 50.0, 20.0);
+        assert_bounds(&child_params(&flex_layout, 1), 50.0, 80.0, 50.0, 20.0);
+        assert_bounds(&child_params(&flex_layout, 2), 100.0, 0.0, 50.0, 100.0);
+    }
+
+    #[test]
+    fn grid_sizes_fixed_auto_and_fraction_columns() {
+        let app = app();
+        let mut grid = Grid::new(app.clone(), vec![
+            child(&app, 30, 20),
+            child(&app, 40, 20),
+            child(&app, Size::Fill, 20),
+        ])
+            .columns(vec![GridTrack::Fixed(50.0), GridTrack::Auto, GridTrack::Fraction(1.0)])
+            .column_gap(10.0)
+            .unwrap();
+        layout(&mut grid, 200.0, 100.0);
+
+        assert_bounds(&child_params(&grid, 0), 0.0, 0.0, 30.0, 20.0);
+        assert_bounds(&child_params(&grid, 1), 60.0, 0.0, 40.0, 20.0);
+        assert_bounds(&child_params(&grid, 2), 110.0, 0.0, 90.0, 20.0);
+    }
+
+    #[test]
+    fn grid_spans_and_row_gap() {
+        let app = app();
+        let mut grid = Grid::new(app.clone(), vec![
+            child(&app, Size::Fill, 20).grid_column_span(2),
+            child(&app, Size::Fill, 30),
+            child(&app, Size::Fill, 40),
+        ])
+            .columns(vec![GridTrack::Fraction(1.0), GridTrack::Fraction(1.0)])
+            .row_gap(10.0)
+            .unwrap();
+        layout(&mut grid, 200.0, 100.0);
+
+        assert_bounds(&child_params(&grid, 0), 0.0, 0.0, 200.0, 20.0);
+        assert_bounds(&child_params(&grid, 1), 0.0, 30.0, 100.0, 30.0);
+        assert_bounds(&child_params(&grid, 2), 100.0, 30.0, 100.0, 40.0);
+    }
+
+    #[test]
+    fn grid_explicit_placement() {
+        let app = app();
+        let mut grid = Grid::new(app.clone(), vec![
+            child(&app, 50, 20).grid_row(1).grid_column(1),
+            child(&app, 50, 20),
+            child(&app, 50, 20),
+        ])
+            .columns(vec![GridTrack::Fixed(50.0), GridTrack::Fixed(50.0)])
+            .rows(vec![GridTrack::Fixed(30.0), GridTrack::Fixed(30.0)])
+            .unwrap();
+        layout(&mut grid, 200.0, 100.0);
+
+        assert_bounds(&child_params(&grid, 0), 50.0, 30.0, 50.0, 20.0);
+        assert_bounds(&child_params(&grid, 1), 0.0, 0.0, 50.0, 20.0);
+        assert_bounds(&child_params(&grid, 2), 50.0, 0.0, 50.0, 20.0);
+    }
+
+    #[test]
+    fn grid_right_to_left() {
+        let app = app();
+        let mut grid = Grid::new(app.clone(), vec![
+            child(&app, 50, 20),
+            child(&app, 50, 20),
+        ])
+            .columns(vec![GridTrack::Fixed(50.0), GridTrack::Fixed(50.0)])
+            .unwrap()
+            .layout_direction(LayoutDirection::RightToLeft);
+        layout(&mut grid, 200.0, 100.0);
+
+        assert_bounds(&child_params(&grid, 0), 150.0, 0.0, 50.0, 20.0);
+        assert_bounds(&child_params(&grid, 1), 100.0, 0.0, 50.0, 20.0);
+    }
+
+    #[test]
+    fn constraint_layout_centers_and_fills_between_anchors() {
+        let app = app();
+        let mut constraint_layout = ConstraintLayout::new(app.clone(), vec![
+            child(&app, 50, 20).tag("a").center_horizontally_in(PARENT),
+            child(&app, 40, 20).tag("b").start_to_end_of("a").top_to_bottom_of("a"),
+            child(&app, Size::Fill, 20).start_to_end_of("b").end_to_end_of(PARENT),
+            child(&app, 50, 20).center_horizontally_in(PARENT).horizontal_bias(0.0).bottom_to_bottom_of(PARENT),
+        ]).unwrap();
+        layout(&mut constraint_layout, 200.0, 100.0);
+
+        assert_bounds(&child_params(&constraint_layout, 0), 75.0, 0.0, 50.0, 20.0);
+        assert_bounds(&child_params(&constraint_layout, 1), 125.0, 20.0, 40.0, 20.0);
+        assert_bounds(&child_params(&constraint_layout, 2), 165.0, 0.0, 35.0, 20.0);
+        assert_bounds(&child_params(&constraint_layout, 3), 0.0, 80.0, 50.0, 20.0);
+    }
+
+    #[test]
+    fn constraint_layout_aligns_baselines() {
+        let app = app();
+        let mut constraint_layout = ConstraintLayout::new(app.clone(), vec![
+            child(&app, 50, 30).tag("a").top_to_top_of(PARENT),
+            child(&app, 50, 10).start_to_end_of("a").baseline_to_baseline_of("a"),
+        ]).unwrap();
+        layout(&mut constraint_layout, 200.0, 100.0);
+
+        assert_bounds(&child_params(&constraint_layout, 1), 50.0, 20.0, 50.0, 10.0);
+    }
+
+    #[test]
+    fn constraint_layout_chains() {
+        let app = app();
+        let mut spread = ConstraintLayout::new(app.clone(), vec![
+            child(&app, 20, 20).tag("a"),
+            child(&app, 20, 20).tag("b"),
+            child(&app, 20, 20).tag("c"),
+        ])
+            .horizontal_chain(vec!["a", "b", "c"], ChainStyle::Spread)
+            .unwrap();
+        layout(&mut spread, 200.0, 100.0);
+        assert_eq!((0..3).map(|index| child_params(&spread, index).relative_x).collect::<Vec<_>>(), vec![35.0, 90.0, 145.0]);
+
+        let mut spread_inside = ConstraintLayout::new(app.clone(), vec![
+            child(&app, 20, 20).tag("a"),
+            child(&app, 20, 20).tag("b"),
+            child(&app, 20, 20).tag("c"),
+        ])
+            .horizontal_chain(vec!["a", "b", "c"], ChainStyle::SpreadInside)
+            .unwrap();
+        layout(&mut spread_inside, 200.0, 100.0);
+        assert_eq!((0..3).map(|index| child_params(&spread_inside, index).relative_x).collect::<Vec<_>>(), vec![0.0, 90.0, 180.0]);
+    }
+
+    #[test]
+    fn constraint_layout_reports_cycles_and_unknown_tags() {
+        let app = app();
+        let cycle = ConstraintLayout::new(app.clone(), vec![
+            child(&app, 20, 20).tag("a").start_to_end_of("b"),
+            child(&app, 20, 20).tag("b").start_to_end_of("a"),
+        ]);
+        assert_eq!(cycle.validate(), Err(ConstraintError::Cycle(vec!["a".to_string(), "b".to_string()])));
+
+        let unknown = ConstraintLayout::new(app.clone(), vec![
+            child(&app, 20, 20).tag("a").top_to_bottom_of("missing"),
+        ]);
+        assert_eq!(unknown.validate(), Err(ConstraintError::UnknownTag("missing".to_string())));
+    }
+
+    #[test]
+    fn constraint_layout_reports_children_in_two_chains() {
+        let app = app();
+        let constraint_layout = ConstraintLayout::new(app.clone(), vec![
+            child(&app, 20, 20).tag("a"),
+            child(&app, 20, 20).tag("b"),
+            child(&app, 20, 20).tag("c"),
+        ])
+            .horizontal_chain(vec!["a", "b"], ChainStyle::Spread)
+            .horizontal_chain(vec!["b", "c"], ChainStyle::Spread);
+        assert_eq!(constraint_layout.validate(), Err(ConstraintError::MultipleChains("b".to_string())));
+
+        let error = constraint_layout.get_error();
+        let mut constraint_layout = constraint_layout.unwrap();
+        constraint_layout.measure(MeasureMode::Specified(200.0), MeasureMode::Specified(100.0));
+        // The error is only reported once the layout pass is done.
+        assert_eq!(error.get(), None);
+        layout(&mut constraint_layout, 200.0, 100.0);
+        assert_eq!(error.get(), Some(ConstraintError::MultipleChains("b".to_string())));
+    }
+
+    #[test]
+    fn constraint_layout_reports_cycles_when_measured() {
+        let app = app();
+        let constraint_layout = ConstraintLayout::new(app.clone(), vec![
+            child(&app, 20, 20).tag("a").end_to_end_of(PARENT).top_to_bottom_of("b").margin_top(5),
+            child(&app, 20, 20).tag("b").top_to_bottom_of("a"),
+        ]);
+        let error = constraint_layout.get_error();
+        let mut constraint_layout = constraint_layout.unwrap();
+        layout(&mut constraint_layout, 200.0, 100.0);
+
+        assert_eq!(error.get(), Some(ConstraintError::Cycle(vec!["a".to_string(), "b".to_string()])));
+        // The children are placed at the top, the horizontal constraints are still resolved.
+        assert_bounds(&child_params(&constraint_layout, 0), 180.0, 5.0, 20.0, 20.0);
+        assert_bounds(&child_params(&constraint_layout, 1), 0.0, 0.0, 20.0, 20.0);
+    }
+}
+
+#[cfg(test)]
+mod scroll_test {
+    use std::time::{Duration, Instant};
+
+    use skia_safe::Color;
+
+    use crate::app::Headless;
+    use crate::layout::{Axis, AxisLayout, Scroll};
+    use crate::property::Size;
+    use crate::theme::material_theme;
+    use crate::ui::{PointerType, Scroller};
+    use crate::widget::RectangleExt;
+
+    const TOUCH: PointerType = PointerType::Touch { id: 0 };
+
+    fn headless() -> Headless {
+        Headless::new(200.0, 100.0, 1.0, material_theme(Color::BLUE, false), |app| {
+            let children = (0..5).map(|_| app.rectangle().item().width(100).height(100)).collect();
+            let content = AxisLayout::new(app.clone(), children).axis(Axis::Vertical).unwrap();
+            Scroll::new(app, content).unwrap()
+        })
+    }
+
+    fn content_y(headless: &Headless) -> f32 {
+        headless.item().get_children().lock().get(0).unwrap().get_layout_params().y()
+    }
+
+    #[test]
+    fn content_is_measured_unbounded_along_the_axis() {
+        let mut headless = headless();
+        headless.layout();
+        assert_eq!(headless.item().get_children().lock().get(0).unwrap().get_layout_params().height, 500.0);
+    }
+
+    #[test]
+    fn fill_descendants_take_their_content_size_along_the_axis() {
+        let mut headless = Headless::new(200.0, 100.0, 1.0, material_theme(Color::BLUE, false), |app| {
+            let content = AxisLayout::new(app.clone(), vec![
+                app.rectangle().item().width(100).height(100),
+                app.rectangle().item().width(100).height(Size::Fill),
+            ]).axis(Axis::Vertical).unwrap();
+            Scroll::new(app, content).unwrap()
+        });
+        headless.layout();
+        let content = headless.item().get_children().lock().get(0).unwrap().get_layout_params().height;
+        assert_eq!(content, 100.0);
+    }
+
+    #[test]
+    fn mouse_wheel_scrolls_within_bounds() {
+        let mut headless = headless();
+        headless.render();
+        assert!(headless.mouse_wheel(50.0, 50.0, 0.0, -120.0));
+        headless.render();
+        assert_eq!(content_y(&headless), -120.0);
+
+        assert!(headless.mouse_wheel(50.0, 50.0, 0.0, -1000.0));
+        headless.render();
+        assert_eq!(content_y(&headless), -400.0);
+        assert!(!headless.mouse_wheel(50.0, 50.0, 0.0, -10.0));
+    }
+
+    #[test]
+    fn overscroll_is_resisted_and_springs_back() {
+        let mut scroller = Scroller::default();
+        scroller.set_max_offset(400.0);
+        let now = Instant::now();
+        scroller.drag_start(TOUCH, 100.0, now);
+        scroller.drag_update(TOUCH, 200.0, now + Duration::from_millis(10));
+        assert_eq!(scroller.offset(), -50.0);
+        scroller.drag_cancel(now + Duration::from_millis(20));
+        assert!(scroller.is_animating());
+        scroller.advance(now + Duration::from_millis(20));
+        assert!(!scroller.advance(now + Duration::from_secs(2)));
+        assert_eq!(scroller.offset(), 0.0);
+    }
+
+    #[test]
+    fn fling_decelerates_and_stops() {
+        let mut scroller = Scroller::default();
+        scroller.set_max_offset(10000.0);
+        let now = Instant::now();
+        scroller.drag_start(TOUCH, 100.0, now);
+        scroller.drag_update(TOUCH, 50.0, now + Duration::from_millis(25));
+        scroller.drag_update(TOUCH, 0.0, now + Duration::from_millis(50));
+        scroller.drag_end(TOUCH, now + Duration::from_millis(50));
+        assert!((scroller.velocity() - 2000.0).abs() < 1.0);
+
+        assert!(scroller.advance(now + Duration::from_millis(150)));
+        let offset = scroller.offset();
+        assert!(offset > 100.0);
+        assert!(scroller.velocity() < 2000.0);
+
+        let mut time = now + Duration::from_millis(150);
+        while scroller.advance(time) {
+            time += Duration::from_millis(16);
+        }
+        assert!(scroller.offset() > offset);
+        assert_eq!(scroller.velocity(), 0.0);
+    }
+
+    #[test]
+    fn scrollbar_fades_out() {
+        let mut scroller = Scroller::default();
+        scroller.set_max_offset(400.0);
+        let now = Instant::now();
+        assert_eq!(scroller.scrollbar_alpha(now), 0.0);
+        scroller.scroll_by(10.0, now);
+        assert_eq!(scroller.scrollbar_alpha(now + Duration::from_millis(500)), 1.0);
+        assert!((scroller.scrollbar_alpha(now + Duration::from_millis(1150)) - 0.5).abs() < 0.01);
+        assert_eq!(scroller.scrollbar_alpha(now + Duration::from_millis(2000)), 0.0);
+    }
+}
+
+#[cfg(test)]
+mod lazy_list_test {
+    use std::cell::RefCell;
+    use std::rc::Rc;
+
+    use skia_safe::Color;
+
+    use crate::app::{Headless, SharedApp};
+    use crate::layout::{LazyList, LazyListController};
+    use crate::property::SharedProperty;
+    use crate::theme::material_theme;
+    use crate::ui::MeasureMode;
+    use crate::widget::RectangleExt;
+
+    /// A list of 10000 rows in a 200x100 window, and the indices the builder was called with.
+    fn headless(fixed_item_height: Option<f32>) -> (Headless, LazyListController, Rc<RefCell<Vec<usize>>>) {
+        let built = Rc::new(RefCell::new(Vec::new()));
+        let controller = Rc::new(RefCell::new(None));
+        let headless = Headless::new(200.0, 100.0, 1.0, material_theme(Color::BLUE, false), {
+            let built = built.clone();
+            let controller = controller.clone();
+            move |app| {
+                let built = built.clone();
+                let lazy_list = LazyList::new(app, 10000_usize, move |app, index| {
+                    built.borrow_mut().push(index);
+                    // Variable rows are 10, 20 or 30 tall.
+                    app.rectangle().item().height(10 + (index % 3) as u32 * 10)
+                });
+                let lazy_list = match fixed_item_height {
+                    Some(height) => lazy_list.fixed_item_height(height),
+                    None => lazy_list,
+                };
+                *controller.borrow_mut() = Some(lazy_list.get_controller());
+                lazy_list.unwrap()
+            }
+        });
+        let controller = controller.borrow_mut().take().unwrap();
+        (headless, controller, built)
+    }
+
+    fn children_y(headless: &Headless) -> Vec<f32> {
+        headless.item().get_children().lock().iter().map(|child| child.get_layout_params().relative_y).collect()
+    }
+
+    #[test]
+    fn only_visible_rows_are_built() {
+        let (mut headless, _, built) = headless(Some(20.0));
+        headless.render();
+        assert_eq!(*built.borrow(), vec![0, 1, 2, 3, 4]);
+        assert_eq!(children_y(&headless), vec![0.0, 20.0, 40.0, 60.0, 80.0]);
+
+        headless.mouse_wheel(50.0, 50.0, 0.0, -50.0);
+        headless.render();
+        assert_eq!(*built.borrow(), vec![0, 1, 2, 3, 4, 5, 6, 7]);
+        assert_eq!(children_y(&headless), vec![-10.0, 10.0, 30.0, 50.0, 70.0, 90.0]);
+
+        // Rows scrolled back into view are reused.
+        headless.mouse_wheel(50.0, 50.0, 0.0, 50.0);
+        headless.render();
+        assert_eq!(built.borrow().len(), 8);
+    }
+
+    #[test]
+    fn scroll_to_index() {
+        let (mut headless, controller, built) = headless(Some(20.0));
+        headless.render();
+        controller.scroll_to_index(5000);
+        headless.render();
+        assert_eq!(built.borrow()[5..], [5000, 5001, 5002, 5003, 5004]);
+        assert_eq!(children_y(&headless), vec![0.0, 20.0, 40.0, 60.0, 80.0]);
+
+        controller.scroll_to_index(9999);
+        headless.render();
+        assert_eq!(children_y(&headless), vec![0.0, 20.0, 40.0, 60.0, 80.0]);
+        assert_eq!(*built.borrow().last().unwrap(), 9999);
+    }
+
+    #[test]
+    fn variable_row_heights() {
+        let (mut headless, _, built) = headless(None);
+        headless.render();
+        // 10 + 20 + 30 + 10 + 20 + 30 reaches past the bottom.
+        assert_eq!(*built.borrow(), vec![0, 1, 2, 3, 4, 5]);
+        assert_eq!(children_y(&headless), vec![0.0, 10.0, 30.0, 60.0, 70.0, 90.0]);
+
+        // The rows below the measured ones move with the cached row tops.
+        headless.mouse_wheel(50.0, 50.0, 0.0, -25.0);
+        headless.render();
+        assert_eq!(children_y(&headless), vec![-15.0, 5.0, 35.0, 45.0, 65.0, 95.0]);
+    }
+
+    #[test]
+    fn items_are_built_again_when_the_count_changes() {
+        let data = Rc::new(RefCell::new(vec!["b", "c"]));
+        let count = SharedProperty::from_value(2_usize);
+        let mut headless = Headless::new(200.0, 100.0, 1.0, material_theme(Color::BLUE, false), {
+            let data = data.clone();
+            let count = count.clone();
+            move |app| {
+                let data = data.clone();
+                LazyList::new(app, count.clone(), move |app, index| {
+                    app.rectangle().item().height(10).tag(data.borrow()[index])
+                }).unwrap()
+            }
+        });
+        headless.render();
+
+        // Every row shows other data after the insertion at the start.
+        data.borrow_mut().insert(0, "a");
+        count.set_value(3);
+        headless.render();
+        let tags: Vec<String> = headless.item().get_children().lock().iter().map(|child| child.get_tag().to_string()).collect();
+        assert_eq!(tags, vec!["a", "b", "c"]);
+    }
+
+    #[test]
+    fn unspecified_width_takes_the_widest_visible_item() {
+        let app = SharedApp::new_headless(material_theme(Color::BLUE, false), 200.0, 100.0, 1.0);
+        let mut item = LazyList::new(app, 100_usize, |app, index| {
+            app.rectangle().item().width(10 + (index % 3) as u32 * 10).height(10)
+        }).unwrap();
+        item.measure(MeasureMode::Unspecified(f32::INFINITY), MeasureMode::Specified(50.0));
+        assert_eq!(item.get_layout_params().width, 30.0);
+        assert_eq!(item.get_children().lock().len(), 5);
+    }
+}
+
+#[cfg(test)]
+mod incremental_layout_test {
+    use std::cell::Cell;
+    use std::rc::Rc;
+
+    use skia_safe::Color;
+
+    use crate::app::{Headless, SharedApp};
+    use crate::layout::{FlexAlign, FlexLayout, FlexWrap, Stack};
+    use crate::property::{ColorProperty, FloatProperty, SharedProperty, Size};
+    use crate::theme::material_theme;
+    use crate::ui::{Item, ItemEvent, MeasureMode};
+    use crate::ui::additional_property::FlexItem;
+    use crate::widget::RectangleExt;
+
+    /// A 10x10 item that counts how often its measure event runs.
+    fn counted(app: SharedApp, min_width: &FloatProperty, count: &Rc<Cell<usize>>) -> Item {
+        let count = count.clone();
+        Item::new(
+            app,
+            ItemEvent::default()
+                .set_measure_event(move |item, width_measure_mode, height_measure_mode| {
+                    count.set(count.get() + 1);
+                    let mut layout_params = item.get_layout_params().clone();
+                    layout_params.init_from_item(item);
+                    layout_params.width = match width_measure_mode {
+                        MeasureMode::Specified(width) => width,
+                        MeasureMode::Unspecified(width) => width.min(10.0),
+                    }.max(layout_params.min_width);
+                    layout_params.height = match height_measure_mode {
+                        MeasureMode::Specified(height) => height,
+                        MeasureMode::Unspecified(height) => height.min(10.0),
+                    };
+                    item.set_layout_params(&layout_params);
+                })
+        ).min_width(min_width.clone())
+    }
+
+    /// A stack holding a leaf and a fixed-size stack with another leaf, with the widths of both leaves.
+    fn headless(inner_count: &Rc<Cell<usize>>, outer_count: &Rc<Cell<usize>>) -> (Headless, FloatProperty, FloatProperty) {
+        let inner_min_width = FloatProperty::from(0.0_f32);
+        let outer_min_width = FloatProperty::from(0.0_f32);
+        let headless = Headless::new(200.0, 100.0, 1.0, material_theme(Color::BLUE, false), {
+            let inner_count = inner_count.clone();
+            let outer_count = outer_count.clone();
+            let inner_min_width = inner_min_width.clone();
+            let outer_min_width = outer_min_width.clone();
+            move |app| {
+                let inner = counted(app.clone(), &inner_min_width, &inner_count);
+                let boundary = Stack::new(app.clone(), vec![inner]).unwrap()
+                    .width(Size::Fixed(50.0))
+                    .height(Size::Fixed(50.0));
+                let outer = counted(app.clone(), &outer_min_width, &outer_count);
+                Stack::new(app, vec![boundary, outer]).unwrap()
+            }
+        });
+        (headless, inner_min_width, outer_min_width)
+    }
+
+    #[test]
+    fn clean_tree_is_not_measured_again() {
+        let inner_count = Rc::new(Cell::new(0));
+        let outer_count = Rc::new(Cell::new(0));
+        let (mut headless, _, _) = headless(&inner_count, &outer_count);
+        headless.layout();
+        headless.layout();
+        assert_eq!((inner_count.get(), outer_count.get()), (1, 1));
+
+        // A full layout request discards every cached size.
+        headless.app().request_layout();
+        headless.layout();
+        assert_eq!((inner_count.get(), outer_count.get()), (2, 2));
+    }
+
+    #[test]
+    fn changed_property_measures_its_branch() {
+        let inner_count = Rc::new(Cell::new(0));
+        let outer_count = Rc::new(Cell::new(0));
+        let (mut headless, inner_min_width, outer_min_width) = headless(&inner_count, &outer_count);
+        headless.layout();
+
+        outer_min_width.set_value(30.0_f32);
+        assert!(headless.item().get_layout_node().is_measure_dirty());
+        headless.layout();
+        assert_eq!((inner_count.get(), outer_count.get()), (1, 2));
+        assert_eq!(headless.item().get_children().lock().get(1).unwrap().get_layout_params().width, 30.0);
+
+        // The fixed-size stack stops the invalidation, the root only visits it.
+        inner_min_width.set_value(20.0_f32);
+        assert!(!headless.item().get_layout_node().is_measure_dirty());
+        assert!(headless.item().get_layout_node().is_descendant_dirty());
+        headless.layout();
+        assert_eq!((inner_count.get(), outer_count.get()), (2, 2));
+        let children = headless.item().get_children();
+        let children = children.lock();
+        let boundary = children.get(0).unwrap();
+        assert!(boundary.get_layout_node().is_relayout_boundary());
+        assert_eq!(boundary.get_children().lock().get(0).unwrap().get_layout_params().width, 20.0);
+        assert!(!headless.item().get_layout_node().is_descendant_dirty());
+    }
+
+    #[test]
+    fn paint_property_is_not_measured() {
+        let count = Rc::new(Cell::new(0));
+        let color = ColorProperty::from_value(Color::RED);
+        let mut headless = Headless::new(200.0, 100.0, 1.0, material_theme(Color::BLUE, false), {
+            let count = count.clone();
+            let color = color.clone();
+            move |app| {
+                Stack::new(app.clone(), vec![
+                    counted(app.clone(), &FloatProperty::from(0.0_f32), &count),
+                    app.rectangle().color(color.clone()).item(),
+                ]).unwrap()
+            }
+        });
+        headless.render();
+
+        color.set_value(Color::GREEN);
+        assert!(!headless.item().get_layout_node().is_measure_dirty());
+        headless.render();
+        assert_eq!(count.get(), 1);
+    }
+
+    #[test]
+    fn container_property_does_not_measure_the_siblings() {
+        let inner_count = Rc::new(Cell::new(0));
+        let sibling_count = Rc::new(Cell::new(0));
+        let justify_content = SharedProperty::from_value(FlexAlign::Start);
+        let mut headless = Headless::new(200.0, 100.0, 1.0, material_theme(Color::BLUE, false), {
+            let inner_count = inner_count.clone();
+            let sibling_count = sibling_count.clone();
+            let justify_content = justify_content.clone();
+            move |app| {
+                let flex_layout = FlexLayout::new(app.clone(), vec![
+                    counted(app.clone(), &FloatProperty::from(0.0_f32), &inner_count),
+                ]).justify_content(justify_content.clone()).unwrap().width(Size::Fill);
+                Stack::new(app.clone(), vec![
+                    flex_layout,
+                    counted(app.clone(), &FloatProperty::from(0.0_f32), &sibling_count),
+                ]).unwrap()
+            }
+        });
+        headless.layout();
+
+        // The flex layout places its child again, neither the child nor the sibling of the layout changed size.
+        justify_content.set_value(FlexAlign::Center);
+        headless.layout();
+        assert_eq!((inner_count.get(), sibling_count.get()), (1, 1));
+        let inner_x = headless.item().get_children().lock().get(0).unwrap().get_children().lock().get(0).unwrap().get_layout_params().relative_x;
+        assert_eq!(inner_x, 95.0);
+    }
+
+    #[test]
+    fn grown_flex_child_is_not_measured_again() {
+        let count = Rc::new(Cell::new(0));
+        let mut headless = Headless::new(200.0, 100.0, 1.0, material_theme(Color::BLUE, false), {
+            let count = count.clone();
+            move |app| {
+                FlexLayout::new(app.clone(), vec![
+                    counted(app.clone(), &FloatProperty::from(0.0_f32), &count).flex_grow(1.0),
+                ]).flex_wrap(FlexWrap::NoWrap).unwrap()
+            }
+        });
+        headless.layout();
+        // Once for its base size and once grown.
+        assert_eq!(count.get(), 2);
+
+        headless.item().get_layout_node().invalidate();
+        headless.layout();
+        assert_eq!(count.get(), 2);
+        assert_eq!(headless.item().get_children().lock().get(0).unwrap().get_layout_params().width, 200.0);
+    }
+}
+
+#[cfg(test)]
+mod measure_child_test {
+    use skia_safe::Color;
+
+    use crate::app::SharedApp;
+    use crate::property::Size;
+    use crate::theme::material_theme;
+    use crate::ui::{Item, ItemEvent, LayoutParams, measure_child, MeasureMode};
+
+    fn app() -> SharedApp {
+        SharedApp::new_headless(material_theme(Color::BLUE, false), 200.0, 100.0, 1.0)
+    }
+
+    fn item(width: Size, height: Size) -> Item {
+        Item::new(app(), ItemEvent::default()).width(width).height(height)
+    }
+
+    /// A parent with 10 padding on every side.
+    fn parent() -> LayoutParams {
+        let mut layout_params = LayoutParams::default();
+        layout_params.padding_start = 10.0;
+        layout_params.padding_top = 10.0;
+        layout_params.padding_end = 10.0;
+        layout_params.padding_bottom = 10.0;
+        layout_params
+    }
+
+    fn measure(child: &Item, width_measure_mode: MeasureMode, height_measure_mode: MeasureMode) -> (MeasureMode, MeasureMode) {
+        measure_child(child, &parent(), width_measure_mode, height_measure_mode)
+    }
+
+    #[test]
+    fn default_is_bounded_by_available_space() {
+        let child = item(Size::Default, Size::Default);
+        assert_eq!(measure(&child, MeasureMode::Specified(200.0), MeasureMode::Unspecified(100.0)), (MeasureMode::Unspecified(180.0), MeasureMode::Unspecified(80.0)));
+    }
+
+    #[test]
+    fn fill_takes_available_space() {
+        let child = item(Size::Fill, Size::Fill);
+        assert_eq!(measure(&child, MeasureMode::Specified(200.0), MeasureMode::Unspecified(100.0)), (MeasureMode::Specified(180.0), MeasureMode::Specified(80.0)));
+    }
+
+    #[test]
+    fn fixed_ignores_available_space() {
+        let child = item(Size::Fixed(300.0), Size::Fixed(20.0));
+        assert_eq!(measure(&child, MeasureMode::Specified(200.0), MeasureMode::Specified(100.0)), (MeasureMode::Specified(300.0), MeasureMode::Specified(20.0)));
+    }
+
+    #[test]
+    fn relative_scales_available_space() {
+        let child = item(Size::Relative(0.5), Size::Relative(0.25));
+        assert_eq!(measure(&child, MeasureMode::Specified(200.0), MeasureMode::Specified(100.0)), (MeasureMode::Specified(90.0), MeasureMode::Specified(20.0)));
+    }
+
+    #[test]
+    fn margins_reduce_available_space() {
+        let mut child = item(Size::Fill, Size::Fill);
+        let layout_params = child.get_layout_params_mut();
+        layout_params.margin_start = 5.0;
+        layout_params.margin_end = 15.0;
+        layout_params.margin_top = 20.0;
+        assert_eq!(measure(&child, MeasureMode::Specified(200.0), MeasureMode::Specified(100.0)), (MeasureMode::Specified(160.0), MeasureMode::Specified(60.0)));
+    }
+
+    #[test]
+    fn min_and_max_clamp_the_measure_modes() {
+        let child = item(Size::Fill, Size::Fixed(10.0))
+            .max_width(100)
+            .min_height(30);
+        assert_eq!(measure(&child, MeasureMode::Specified(200.0), MeasureMode::Specified(100.0)), (MeasureMode::Specified(100.0), MeasureMode::Specified(30.0)));
+
+        let child = item(Size::Default, Size::Default).max_width(50);
+        assert_eq!(measure(&child, MeasureMode::Specified(200.0), MeasureMode::Specified(100.0)).0, MeasureMode::Unspecified(50.0));
+    }
+
+    #[test]
+    fn aspect_ratio_derives_the_other_size() {
+        let child = item(Size::Fill, Size::Default).aspect_ratio(Some(2.0));
+        assert_eq!(measure(&child, MeasureMode::Specified(200.0), MeasureMode::Specified(100.0)), (MeasureMode::Specified(180.0), MeasureMode::Specified(90.0)));
+
+        let child = item(Size::Default, Size::Fixed(40.0)).aspect_ratio(Some(2.0));
+        assert_eq!(measure(&child, MeasureMode::Specified(200.0), MeasureMode::Specified(100.0)), (MeasureMode::Specified(80.0), MeasureMode::Specified(40.0)));
+
+        // The derived size is clamped too.
+        let child = item(Size::Fill, Size::Default).aspect_ratio(Some(2.0)).max_height(50);
+        assert_eq!(measure(&child, MeasureMode::Specified(200.0), MeasureMode::Specified(100.0)), (MeasureMode::Specified(180.0), MeasureMode::Specified(50.0)));
+    }
+
+    #[test]
+    fn aspect_ratio_fits_available_space() {
+        let child = item(Size::Default, Size::Default).aspect_ratio(Some(2.0));
+        assert_eq!(measure(&child, MeasureMode::Specified(200.0), MeasureMode::Specified(90.0)), (MeasureMode::Specified(140.0), MeasureMode::Specified(70.0)));
+
+        // Without a bound on either axis the child measures itself.
+        let child = item(Size::Default, Size::Default).aspect_ratio(Some(2.0));
+        assert_eq!(measure(&child, MeasureMode::Unspecified(f32::INFINITY), MeasureMode::Unspecified(f32::INFINITY)), (MeasureMode::Unspecified(f32::MAX), MeasureMode::Unspecified(f32::MAX)));
+    }
+
+    #[test]
+    fn explicit_sizes_win_over_aspect_ratio() {
+        let child = item(Size::Fixed(50.0), Size::Fixed(50.0)).aspect_ratio(Some(2.0));
+        assert_eq!(measure(&child, MeasureMode::Specified(200.0), MeasureMode::Specified(100.0)), (MeasureMode::Specified(50.0), MeasureMode::Specified(50.0)));
+    }
+}
+
+#[cfg(test)]
+mod window_size_class_test {
+    use skia_safe::Color;
+
+    use crate::app::{Headless, WindowSizeClass};
+    use crate::layout::Stack;
+    use crate::property::{Gettable, Size};
+    use crate::theme::material_theme;
+    use crate::widget::RectangleExt;
+
+    #[test]
+    fn size_class_follows_width() {
+        assert_eq!(WindowSizeClass::from_width(599.0), WindowSizeClass::Compact);
+        assert_eq!(WindowSizeClass::from_width(600.0), WindowSizeClass::Medium);
+        assert_eq!(WindowSizeClass::from_width(839.0), WindowSizeClass::Medium);
+        assert_eq!(WindowSizeClass::from_width(840.0), WindowSizeClass::Expanded);
+    }
+
+    #[test]
+    fn responsive_property_switches_on_resize() {
+        let mut headless = Headless::new(400.0, 300.0, 1.0, material_theme(Color::BLUE, false), |app| {
+            let width = app.responsive(Size::Fill, Size::Fixed(500.0), Size::Fixed(600.0));
+            Stack::new(app.clone(), vec![app.rectangle().item().width(width)]).unwrap()
+        });
+        let child_width = |headless: &Headless| headless.item().get_children().lock().get(0).unwrap().get_layout_params().width;
+        let app = headless.app();
+        let padding = app.responsive(8.0_f32, 16.0, 24.0);
+        assert_eq!(app.window_size_class().get(), WindowSizeClass::Compact);
+        headless.layout();
+        assert_eq!(child_width(&headless), 400.0);
+
+        headless.resize(700.0, 300.0, 1.0);
+        assert_eq!(app.window_size_class().get(), WindowSizeClass::Medium);
+        assert_eq!(padding.get(), 16.0);
+        headless.layout();
+        assert_eq!(child_width(&headless), 500.0);
+
+        headless.resize(1000.0, 300.0, 1.0);
+        assert_eq!(padding.get(), 24.0);
+        headless.layout();
+        assert_eq!(child_width(&headless), 600.0);
+    }
+}
+
+#[cfg(test)]
+mod dependency_tracking_test {
+    use std::cell::Cell;
+    use std::panic;
+    use std::rc::Rc;
+
+    use crate::property::{Gettable, SharedProperty};
+
+    #[test]
+    fn tracked_generator_follows_inputs() {
+        let a = SharedProperty::from_value(1);
+        let b = SharedProperty::from_value(2);
+        let sum = {
+            let a = a.clone();
+            let b = b.clone();
+            SharedProperty::from_tracked_generator(Box::new(move || a.get() + b.get()))
+        };
+        assert_eq!(sum.get(), 3);
+        a.set_value(10);
+        assert_eq!(sum.get(), 12);
+        b.set_value(20);
+        assert_eq!(sum.get(), 30);
+    }
+
+    #[test]
+    fn unread_inputs_are_unsubscribed() {
+        let use_a = SharedProperty::from_value(true);
+        let a = SharedProperty::from_value(1);
+        let b = SharedProperty::from_value(2);
+        let evaluations = Rc::new(Cell::new(0));
+        let value = {
+            let use_a = use_a.clone();
+            let a = a.clone();
+            let b = b.clone();
+            let evaluations = evaluations.clone();
+            SharedProperty::from_tracked_generator(Box::new(move || {
+                evaluations.set(evaluations.get() + 1);
+                if use_a.get() { a.get() } else { b.get() }
+            }))
+        };
+        assert_eq!(value.get(), 1);
+        b.set_value(3);
+        assert_eq!(evaluations.get(), 1);
+
+        use_a.set_value(false);
+        assert_eq!(value.get(), 3);
+        assert_eq!(evaluations.get(), 2);
+
+        a.set_value(5);
+        assert_eq!(evaluations.get(), 2);
+        b.set_value(4);
+        assert_eq!(value.get(), 4);
+        assert_eq!(evaluations.get(), 3);
+    }
+
+    #[test]
+    fn set_value_stops_tracking() {
+        let a = SharedProperty::from_value(1);
+        let double = {
+            let a = a.clone();
+            SharedProperty::from_tracked_generator(Box::new(move || a.get() * 2))
+        };
+        double.set_value(7);
+        a.set_value(2);
+        assert_eq!(double.get(), 7);
+    }
+
+    #[test]
+    fn nested_tracked_generators_track_their_own_reads() {
+        let a = SharedProperty::from_value(1);
+        let b = SharedProperty::from_value(2);
+        let inner = {
+            let a = a.clone();
+            SharedProperty::from_tracked_generator(Box::new(move || a.get() + 1))
+        };
+        let outer = {
+            let inner = inner.clone();
+            let b = b.clone();
+            SharedProperty::from_tracked_generator(Box::new(move || inner.get() * b.get()))
+        };
+        assert_eq!(outer.get(), 4);
+        a.set_value(2);
+        assert_eq!(outer.get(), 6);
+        b.set_value(3);
+        assert_eq!(outer.get(), 9);
+    }
+
+    #[test]
+    fn panicking_generator_does_not_take_the_reads_of_the_outer_one() {
+        let a = SharedProperty::from_value(1);
+        let outer = {
+            let a = a.clone();
+            SharedProperty::from_tracked_generator(Box::new(move || {
+                let inner = panic::catch_unwind(|| SharedProperty::<i32>::from_tracked_generator(Box::new(|| panic!("no value"))));
+                assert!(inner.is_err());
+                a.get()
+            }))
+        };
+        a.set_value(2);
+        assert_eq!(outer.get(), 2);
+    }
+
+    #[test]
+    fn generator_may_read_the_previous_value() {
+        let a = SharedProperty::from_value(3);
+        let maximum = SharedProperty::from_value(0);
+        {
+            let a = a.clone();
+            let previous = maximum.clone();
+            maximum.set_tracked_generator(Box::new(move || previous.get().max(a.get())));
+        }
+        assert_eq!(maximum.get(), 3);
+        a.set_value(1);
+        assert_eq!(maximum.get(), 3);
+        a.set_value(5);
+        assert_eq!(maximum.get(), 5);
+
+        // The generator holds the property, setting a value drops it.
+        maximum.set_value(0);
+    }
+}
+
+#[cfg(test)]
+mod batch_test {
+    use std::cell::Cell;
+    use std::panic::{self, AssertUnwindSafe};
+    use std::rc::Rc;
+
+    use crate::property::{batch, get_observable_id, is_batching, take_notification_cycles, Gettable, Observable, Observer, SharedProperty};
+
+    fn count_notifications(property: &SharedProperty<i32>) -> Rc<Cell<usize>> {
+        let count = Rc::new(Cell::new(0));
+        let count_clone = count.clone();
+        property.add_observer(Observer::new_without_id(move || count_clone.set(count_clone.get() + 1)));
+        count
+    }
+
+    #[test]
+    fn batch_coalesces_notifications() {
+        let property = SharedProperty::from_value(0);
+        let count = count_notifications(&property);
+        batch(|| {
+            assert!(is_batching());
+            for i in 1..=10 {
+                property.set_value(i);
+            }
+            assert_eq!(count.get(), 0);
+        });
+        assert!(!is_batching());
+        assert_eq!(count.get(), 1);
+        assert_eq!(property.get(), 10);
+    }
+
+    #[test]
+    fn derived_property_is_evaluated_once() {
+        let a = SharedProperty::from_value(1);
+        let b = SharedProperty::from_value(2);
+        let evaluations = Rc::new(Cell::new(0));
+        let sum = {
+            let a = a.clone();
+            let b = b.clone();
+            let evaluations = evaluations.clone();
+            SharedProperty::from_generator(Box::new(move || {
+                evaluations.set(evaluations.get() + 1);
+                a.get() + b.get()
+            }))
+        };
+        sum.observe(&a);
+        sum.observe(&b);
+        let count = count_notifications(&sum);
+        batch(|| {
+            a.set_value(10);
+            b.set_value(20);
+            assert_eq!(sum.get(), 3);
+        });
+        assert_eq!(sum.get(), 30);
+        assert_eq!(evaluations.get(), 2);
+        assert_eq!(count.get(), 1);
+    }
+
+    #[test]
+    fn observers_sharing_an_owner_all_run() {
+        let a = SharedProperty::from_value(1);
+        let b = SharedProperty::from_value(2);
+        let owner_id = get_observable_id();
+        let runs = Rc::new(Cell::new(0));
+        for property in [&a, &a, &b] {
+            let runs = runs.clone();
+            property.add_observer(Observer::new(move || runs.set(runs.get() + 1), owner_id));
+        }
+        batch(|| {
+            a.set_value(10);
+            b.set_value(20);
+        });
+        assert_eq!(runs.get(), 3);
+    }
+
+    #[test]
+    fn nested_batches_flush_at_the_outermost() {
+        let property = SharedProperty::from_value(0);
+        let count = count_notifications(&property);
+        batch(|| {
+            batch(|| property.set_value(1));
+            assert_eq!(count.get(), 0);
+            property.set_value(2);
+        });
+        assert_eq!(count.get(), 1);
+    }
+
+    #[test]
+    fn notification_cycle_is_detected() {
+        let x = SharedProperty::from_value(1);
+        let a = {
+            let x = x.clone();
+            SharedProperty::from_generator(Box::new(move || x.get()))
+        };
+        a.observe(&x);
+        let b = {
+            let a = a.clone();
+            SharedProperty::from_generator(Box::new(move || a.get() + 1))
+        };
+        b.observe(&a);
+        a.observe(&b);
+        x.set_value(2);
+
+        // The notification stops where it comes back to a property, the values along the way are updated.
+        assert_eq!(take_notification_cycles(), vec![vec![a.lock().get_id(), b.lock().get_id()]]);
+        assert_eq!((a.get(), b.get()), (2, 3));
+        assert!(take_notification_cycles().is_empty());
+    }
+
+    #[test]
+    fn panicking_batch_drops_its_notifications() {
+        let property = SharedProperty::from_value(0);
+        let count = count_notifications(&property);
+        let result = panic::catch_unwind(AssertUnwindSafe(|| batch(|| {
+            property.set_value(1);
+            panic!("the batch failed");
+        })));
+        assert!(result.is_err());
+        assert!(!is_batching());
+        assert_eq!(count.get(), 0);
+
+        property.set_value(2);
+        assert_eq!(count.get(), 1);
+    }
+
+    #[test]
+    fn dropped_generator_property_stops_observing() {
+        let x = SharedProperty::from_value(1);
+        let a = {
+            let x = x.clone();
+            SharedProperty::from_generator(Box::new(move || x.get() * 2))
+        };
+        a.observe(&x);
+        assert_eq!(x.observer_count(), 1);
+        drop(a);
+        assert_eq!(x.observer_count(), 0);
+    }
+}
+
+#[cfg(test)]
+mod list_property_test {
+    use std::cell::{Cell, RefCell};
+    use std::rc::Rc;
+
+    use skia_safe::Color;
+
+    use crate::app::SharedApp;
+    use crate::property::{live_observer_count, Gettable, ItemCollectionProperty, ListChange, ListProperty, Observable, Observer};
+    use crate::theme::material_theme;
+    use crate::ui::{Item, ItemEvent};
+
+    #[derive(Clone)]
+    struct Row {
+        id: u32,
+        name: &'static str,
+    }
+
+    fn row(id: u32, name: &'static str) -> Row {
+        Row { id, name }
+    }
+
+    /// Map the rows to items tagged with their id, counting how many items were built.
+    fn build(list: &ListProperty<Row>) -> (ItemCollectionProperty, Rc<Cell<usize>>) {
+        let app = SharedApp::new_headless(material_theme(Color::BLUE, false), 200.0, 100.0, 1.0);
+        let builds = Rc::new(Cell::new(0));
+        let builds_clone = builds.clone();
+        let children = list.for_each(|row| row.id, move |row| {
+            builds_clone.set(builds_clone.get() + 1);
+            Item::new(app.clone(), ItemEvent::default()).tag(row.get().id.to_string())
+        });
+        (children, builds)
+    }
+
+    fn tags(children: &ItemCollectionProperty) -> Vec<String> {
+        children.lock().iter().map(|item| item.get_tag().to_string()).collect()
+    }
+
+    #[test]
+    fn changes_are_reported() {
+        let list = ListProperty::from_vec(vec![1, 2, 3]);
+        let changes = Rc::new(RefCell::new(Vec::new()));
+        let changes_clone = changes.clone();
+        list.add_change_listener(0, move |change| changes_clone.borrow_mut().push(change.clone()));
+        list.push(4);
+        list.remove(0);
+        list.move_item(0, 2);
+        list.set(1, 5);
+        list.clear();
+        assert_eq!(*changes.borrow(), vec![
+            ListChange::Insert(3),
+            ListChange::Remove(0),
+            ListChange::Move { from: 0, to: 2 },
+            ListChange::Update(1),
+            ListChange::Reset,
+        ]);
+        assert!(list.is_empty());
+    }
+
+    #[test]
+    fn for_each_builds_only_changed_items() {
+        let list = ListProperty::from_vec(vec![row(1, "a"), row(2, "b"), row(3, "c")]);
+        let (children, builds) = build(&list);
+        assert_eq!(tags(&children), vec!["1", "2", "3"]);
+        assert_eq!(builds.get(), 3);
+
+        list.insert(1, row(4, "d"));
+        assert_eq!(tags(&children), vec!["1", "4", "2", "3"]);
+        assert_eq!(builds.get(), 4);
+
+        list.move_item(3, 0);
+        list.remove(2);
+        assert_eq!(tags(&children), vec!["3", "1", "4"]);
+        assert_eq!(builds.get(), 4);
+    }
+
+    #[test]
+    fn update_with_same_key_keeps_the_item() {
+        let list = ListProperty::from_vec(vec![row(1, "a"), row(2, "b")]);
+        let names = Rc::new(RefCell::new(Vec::new()));
+        let names_clone = names.clone();
+        let app = SharedApp::new_headless(material_theme(Color::BLUE, false), 200.0, 100.0, 1.0);
+        let children = list.for_each(|row| row.id, move |row| {
+            let names = names_clone.clone();
+            let row_clone = row.clone();
+            row.add_observer(Observer::new_without_id(move || {
+                names.borrow_mut().push(row_clone.get().name);
+            }));
+            Item::new(app.clone(), ItemEvent::default()).tag(row.get().id.to_string())
+        });
+        list.update(1, |row| row.name = "c");
+        assert_eq!(*names.borrow(), vec!["c"]);
+        list.set(0, row(5, "e"));
+        assert_eq!(tags(&children), vec!["5", "2"]);
+        assert_eq!(*names.borrow(), vec!["c"]);
+    }
+
+    #[test]
+    fn reset_matches_items_by_key() {
+        let list = ListProperty::from_vec(vec![row(1, "a"), row(2, "b"), row(3, "c")]);
+        let (children, builds) = build(&list);
+        let notifications = Rc::new(Cell::new(0));
+        let notifications_clone = notifications.clone();
+        children.lock().add_observer(Observer::new_without_id(move || {
+            notifications_clone.set(notifications_clone.get() + 1);
+        }));
+        list.set_all(vec![row(3, "c"), row(5, "e"), row(1, "a")]);
+        assert_eq!(tags(&children), vec!["3", "5", "1"]);
+        assert_eq!(builds.get(), 4);
+        assert_eq!(notifications.get(), 1);
+        assert_eq!(list.get().len(), 3);
+    }
+
+    #[test]
+    fn change_listener_may_remove_itself() {
+        let list = ListProperty::from_vec(vec![1]);
+        let calls = Rc::new(Cell::new(0));
+        let calls_clone = calls.clone();
+        let list_clone = list.clone();
+        list.add_change_listener(7, move |_| {
+            calls_clone.set(calls_clone.get() + 1);
+            list_clone.remove_change_listener(7);
+        });
+        list.push(2);
+        list.push(3);
+        assert_eq!(calls.get(), 1);
+        assert_eq!(list.observer_count(), 0);
+    }
+    #[test]
+    fn dropping_the_ui_removes_the_change_listener() {
+        let app = SharedApp::new_headless(material_theme(Color::BLUE, false), 200.0, 100.0, 1.0);
+        let list = ListProperty::from_vec(vec![row(1, "a"), row(2, "b")]);
+        let before = live_observer_count();
+        let mut parent = Item::new(app.clone(), ItemEvent::default());
+        parent.set_children(list.for_each(|row| row.id, move |row| {
+            Item::new(app.clone(), ItemEvent::default()).tag(row.get().id.to_string())
+        }));
+        assert_eq!(list.observer_count(), 1);
+
+        drop(parent);
+        assert_eq!(list.observer_count(), 0);
+        assert_eq!(live_observer_count(), before);
+        // Changes after the UI is gone are ignored.
+        list.push(row(3, "c"));
+    }
+}
+
+#[cfg(test)]
+mod binding_test {
+    use std::cell::Cell;
+    use std::rc::Rc;
+
+    use crate::property::{live_observer_count, Converter, FloatProperty, Gettable, Observable, Observer, SharedProperty, TextProperty};
+
+    fn count_notifications<T: 'static>(property: &SharedProperty<T>) -> Rc<Cell<usize>> {
+        let count = Rc::new(Cell::new(0));
+        let count_clone = count.clone();
+        property.add_observer(Observer::new_without_id(move || count_clone.set(count_clone.get() + 1)));
+        count
+    }
+
+    #[test]
+    fn bind_syncs_both_ways_without_echo() {
+        let a = SharedProperty::from_value(1);
+        let b = SharedProperty::from_value(2);
+        let _binding = a.bind(&b);
+        assert_eq!(b.get(), 1);
+
+        let a_count = count_notifications(&a);
+        let b_count = count_notifications(&b);
+        a.set_value(3);
+        assert_eq!(b.get(), 3);
+        assert_eq!((a_count.get(), b_count.get()), (1, 1));
+
+        b.set_value(4);
+        assert_eq!(a.get(), 4);
+        assert_eq!((a_count.get(), b_count.get()), (2, 2));
+    }
+
+    #[test]
+    fn unbind_stops_syncing() {
+        let a = SharedProperty::from_value(1);
+        let b = SharedProperty::from_value(2);
+        let binding = a.bind(&b);
+        binding.unbind();
+        a.set_value(3);
+        assert_eq!(b.get(), 1);
+        b.set_value(4);
+        assert_eq!(a.get(), 3);
+    }
+
+    #[test]
+    fn float_text_surfaces_parse_errors() {
+        let value = FloatProperty::from_value(1.5);
+        let text = TextProperty::from_str("");
+        let binding = value.bind_with(&text, Converter::float_text());
+        assert_eq!(text.get().as_str(), "1.5");
+
+        text.set_value("2.25");
+        assert_eq!(value.get(), 2.25);
+        assert_eq!(binding.error().get(), None);
+
+        text.set_value("abc");
+        assert_eq!(value.get(), 2.25);
+        assert_eq!(binding.error().get(), Some("\"abc\" is not a number".to_string()));
+
+        value.set_value(3.0);
+        assert_eq!(text.get().as_str(), "3");
+        assert_eq!(binding.error().get(), None);
+    }
+
+    #[test]
+    fn text_edited_in_place_updates_the_binding() {
+        let value = FloatProperty::from_value(1.0);
+        let text = TextProperty::from_str("");
+        let _binding = value.bind_with(&text, Converter::float_text());
+        // The value set by the binding replaced the observable text, edits of the new text are still observed.
+        // The binding reads the text when it is unlocked again.
+        text.lock().as_mut().append("2");
+        assert_eq!(text.get().as_str(), "12");
+        assert_eq!(value.get(), 12.0);
+    }
+
+    #[test]
+    fn replaced_observable_value_is_no_longer_observed() {
+        let first = SharedProperty::from_value(1);
+        let property = SharedProperty::from_observable(first.clone());
+        let count = count_notifications(&property);
+        let second = SharedProperty::from_value(2);
+        property.set_value(second.clone());
+        assert_eq!(first.observer_count(), 0);
+        first.set_value(3);
+        assert_eq!(count.get(), 1);
+        second.set_value(4);
+        assert_eq!(count.get(), 2);
+    }
+
+    #[test]
+    fn bound_properties_do_not_keep_each_other_alive() {
+        let before = live_observer_count();
+        let a = SharedProperty::from_value(1);
+        let b = SharedProperty::from_value(2);
+        let binding = a.bind(&b);
+        assert_eq!(live_observer_count(), before + 2);
+        drop(binding);
+        drop(a);
+        drop(b);
+        assert_eq!(live_observer_count(), before);
+    }
+}
+
+#[cfg(test)]
+mod subscription_test {
+    use std::cell::Cell;
+    use std::rc::Rc;
+
+    use skia_safe::Color;
+
+    use crate::app::SharedApp;
+    use crate::property::{live_observer_count, subscribe, ItemCollectionProperty, SharedProperty, Size, SizeProperty};
+    use crate::theme::material_theme;
+    use crate::ui::{Item, ItemEvent};
+
+    #[test]
+    fn dropped_subscription_removes_the_observer() {
+        let property = SharedProperty::from_value(0);
+        let count = Rc::new(Cell::new(0));
+        let subscription = {
+            let count = count.clone();
+            subscribe(&property, move || count.set(count.get() + 1))
+        };
+        property.set_value(1);
+        assert_eq!(property.observer_count(), 1);
+        drop(subscription);
+        property.set_value(2);
+        assert_eq!(count.get(), 1);
+        assert_eq!(property.observer_count(), 0);
+        assert_eq!(Rc::strong_count(&count), 1);
+    }
+
+    #[test]
+    fn detached_subscription_keeps_the_observer() {
+        let property = SharedProperty::from_value(0);
+        subscribe(&property, || {}).detach();
+        assert_eq!(property.observer_count(), 1);
+    }
+
+    #[test]
+    fn live_observers_are_counted() {
+        let before = live_observer_count();
+        let property = SharedProperty::from_value(0);
+        let subscription = subscribe(&property, || {});
+        assert_eq!(live_observer_count(), before + 1);
+        drop(subscription);
+        assert_eq!(live_observer_count(), before);
+    }
+
+    #[test]
+    fn dropped_item_removes_its_observers() {
+        let app = SharedApp::new_headless(material_theme(Color::BLUE, false), 200.0, 100.0, 1.0);
+        let width = SizeProperty::from_value(Size::Fill);
+        let before = live_observer_count();
+        let item = Item::new(app, ItemEvent::default()).width(width.clone());
+        assert_eq!(width.observer_count(), 1);
+        drop(item);
+        assert_eq!(width.observer_count(), 0);
+        assert_eq!(live_observer_count(), before);
+    }
+    #[test]
+    fn replaced_children_are_no_longer_observed() {
+        let app = SharedApp::new_headless(material_theme(Color::BLUE, false), 200.0, 100.0, 1.0);
+        let mut item = Item::new(app, ItemEvent::default());
+        let first = ItemCollectionProperty::new();
+        item.set_children(first.clone());
+        assert_eq!(first.observer_count(), 1);
+
+        let before = live_observer_count();
+        let second = ItemCollectionProperty::new();
+        item.set_children(second.clone());
+        assert_eq!(first.observer_count(), 0);
+        assert_eq!(first.lock().observer_count(), 0);
+        assert_eq!(second.observer_count(), 1);
+        assert_eq!(live_observer_count(), before);
+    }
+}
+
+#[cfg(test)]
+mod ui_thread_test {
+    use std::rc::Rc;
+    use std::thread;
+
+    use skia_safe::Color;
+
+    use crate::app::{Headless, UiHandle};
+    use crate::property::{Gettable, SharedProperty};
+    use crate::theme::material_theme;
+    use crate::ui::{Item, ItemEvent};
+
+    fn headless() -> Headless {
+        Headless::new(200.0, 100.0, 1.0, material_theme(Color::BLUE, false), |app| Item::new(app, ItemEvent::default()))
+    }
+
+    #[test]
+    fn handle_updates_the_value_on_the_ui_thread() {
+        let mut headless = headless();
+        let property = SharedProperty::from_value(0);
+        let handle = headless.app().ui_handle(property.clone());
+        thread::spawn(move || handle.update(|property| property.set_value(42))).join().unwrap();
+        assert_eq!(property.get(), 0);
+
+        headless.layout();
+        assert_eq!(property.get(), 42);
+    }
+
+    #[test]
+    fn posted_tasks_request_a_layout() {
+        let mut headless = headless();
+        headless.layout();
+        headless.app().re_layout_done();
+        let ui_sender = headless.app().ui_sender();
+        thread::spawn(move || ui_sender.request_layout()).join().unwrap();
+        headless.app().run_ui_tasks();
+        assert!(headless.app().lock().unwrap().need_layout);
+    }
+
+    #[test]
+    fn value_is_dropped_with_the_last_handle() {
+        let mut headless = headless();
+        let value = Rc::new(0);
+        let weak = Rc::downgrade(&value);
+        let handle = headless.app().ui_handle(value);
+        let handle_clone = handle.clone();
+        drop(handle);
+        thread::spawn(move || drop(handle_clone)).join().unwrap();
+        assert!(weak.upgrade().is_some());
+
+        headless.layout();
+        assert!(weak.upgrade().is_none());
+    }
+    #[test]
+    #[should_panic(expected = "create it on the event loop thread")]
+    fn handle_created_on_another_thread_is_reported() {
+        let mut headless = headless();
+        let ui_sender = headless.app().ui_sender();
+        let handle = thread::spawn(move || UiHandle::new(ui_sender, 0)).join().unwrap();
+        handle.update(|_| {});
+        headless.layout();
+    }
+}
+
+#[cfg(test)]
+mod image_test {
+    use skia_safe::Color;
+
+    use crate::app::Headless;
+    use crate::theme::material_theme;
+    use crate::ui::{Drawable, Item, ItemEvent, NetworkImage};
+
+    #[test]
+    fn loaded_network_image_requests_a_layout() {
+        let mut headless = Headless::new(4.0, 3.0, 1.0, material_theme(Color::BLUE, false), |app| Item::new(app, ItemEvent::default()));
+        let png = headless.render_png().unwrap().as_bytes().to_vec();
+        headless.app().re_layout_done();
+
+        let (image, loading) = NetworkImage::from_fetch(&headless.app(), move || Some(png));
+        loading.join().unwrap();
+        assert_eq!((image.get_intrinsic_width(), image.get_intrinsic_height()), (4.0, 3.0));
+        assert!(!headless.app().lock().unwrap().need_layout);
+
+        headless.app().run_ui_tasks();
+        assert!(headless.app().lock().unwrap().need_layout);
+    }
+}
+
+#[cfg(test)]
+mod history_test {
+    use std::time::Duration;
+
+    use crate::property::{Gettable, History, SharedProperty, TextProperty};
+
+    #[test]
+    fn undo_and_redo_restore_values() {
+        let mut history = History::new(10);
+        let property = SharedProperty::from_value(1);
+        history.track(&property);
+        assert!(!history.can_undo().get());
+
+        property.set_value(2);
+        property.set_value(3);
+        assert!(history.can_undo().get());
+        assert!(!history.can_redo().get());
+
+        assert!(history.undo());
+        assert_eq!(property.get(), 2);
+        assert!(history.can_redo().get());
+        assert!(history.undo());
+        assert_eq!(property.get(), 1);
+        assert!(!history.can_undo().get());
+        assert!(!history.undo());
+
+        assert!(history.redo());
+        assert!(history.redo());
+        assert_eq!(property.get(), 3);
+        assert!(!history.can_redo().get());
+        assert!(!history.redo());
+    }
+
+    #[test]
+    fn transaction_is_undone_as_a_whole() {
+        let mut history = History::new(10);
+        let a = SharedProperty::from_value(1);
+        let b = SharedProperty::from_value(1);
+        history.track(&a);
+        history.track(&b);
+
+        history.transaction(|| {
+            a.set_value(2);
+            history.transaction(|| b.set_value(2));
+        });
+        history.undo();
+        assert_eq!((a.get(), b.get()), (1, 1));
+        assert!(!history.can_undo().get());
+
+        history.redo();
+        assert_eq!((a.get(), b.get()), (2, 2));
+    }
+
+    #[test]
+    fn typing_edits_are_merged() {
+        let mut history = History::new(10);
+        let text = TextProperty::from_value("".into());
+        history.track_typing(&text);
+
+        text.set_value("a".into());
+        text.set_value("ab".into());
+        history.undo();
+        assert_eq!(text.get().as_str(), "");
+        assert!(!history.can_undo().get());
+
+        history.redo();
+        assert_eq!(text.get().as_str(), "ab");
+    }
+
+    #[test]
+    fn text_edited_in_place_is_undone() {
+        let mut history = History::new(10);
+        let text = TextProperty::from_str("a");
+        history.track(&text);
+        // The text notifies while it is locked, the history reads it once it is unlocked.
+        text.lock().as_mut().append("b");
+        assert_eq!(text.get().as_str(), "ab");
+        assert!(history.undo());
+        assert_eq!(text.get().as_str(), "a");
+
+        // The text set by the undo is observed too.
+        text.lock().as_mut().insert(0, "c");
+        assert!(history.undo());
+        assert_eq!(text.get().as_str(), "a");
+        assert!(!history.can_undo().get());
+    }
+
+    #[test]
+    fn typing_edits_after_the_interval_are_not_merged() {
+        let mut history = History::new(10).merge_interval(Duration::ZERO);
+        let text = TextProperty::from_value("".into());
+        history.track_typing(&text);
+
+        text.set_value("a".into());
+        std::thread::sleep(Duration::from_millis(1));
+        text.set_value("ab".into());
+        history.undo();
+        assert_eq!(text.get().as_str(), "a");
+    }
+
+    #[test]
+    fn oldest_entries_are_dropped() {
+        let mut history = History::new(2);
+        let property = SharedProperty::from_value(0);
+        history.track(&property);
+        (1..=3).for_each(|value| property.set_value(value));
+
+        assert!(history.undo());
+        assert!(history.undo());
+        assert!(!history.undo());
+        assert_eq!(property.get(), 1);
+    }
+
+    #[test]
+    fn new_change_clears_redo() {
+        let mut history = History::new(10);
+        let property = SharedProperty::from_value(0);
+        history.track(&property);
+        property.set_value(1);
+        history.undo();
+        assert!(history.can_redo().get());
+
+        property.set_value(2);
+        assert!(!history.can_redo().get());
+        assert!(!history.redo());
+        history.undo();
+        assert_eq!(property.get(), 0);
+    }
+}
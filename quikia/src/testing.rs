@@ -0,0 +1,175 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use skia_safe::{AlphaType, CachingHint, ColorType, Data, EncodedImageFormat, Image, ImageInfo, images};
+
+use crate::app::{Headless, SharedApp, Theme};
+use crate::ui::Item;
+
+/// Set this environment variable to `1` to write golden files from the current rendering.
+/// Without it, a missing golden file fails the comparison.
+pub const UPDATE_GOLDENS_ENV: &str = "QUIKIA_UPDATE_GOLDENS";
+
+fn update_goldens() -> bool {
+    std::env::var(UPDATE_GOLDENS_ENV).map(|value| value == "1").unwrap_or(false)
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct GoldenOptions {
+    pub width: f32,
+    pub height: f32,
+    pub scale_factor: f32,
+    /// The maximum difference allowed on each channel of a pixel.
+    pub tolerance: u8,
+    /// The number of pixels that may exceed the tolerance before the comparison fails.
+    pub max_mismatched_pixels: usize,
+}
+
+impl GoldenOptions {
+    pub fn new(width: f32, height: f32) -> Self {
+        Self {
+            width,
+            height,
+            scale_factor: 1.0,
+            tolerance: 2,
+            max_mismatched_pixels: 0,
+        }
+    }
+
+    pub fn scale_factor(mut self, scale_factor: f32) -> Self {
+        self.scale_factor = scale_factor;
+        self
+    }
+
+    pub fn tolerance(mut self, tolerance: u8) -> Self {
+        self.tolerance = tolerance;
+        self
+    }
+
+    pub fn max_mismatched_pixels(mut self, max_mismatched_pixels: usize) -> Self {
+        self.max_mismatched_pixels = max_mismatched_pixels;
+        self
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum GoldenComparison {
+    /// The rendering matches the golden file.
+    Match,
+    /// Updating was requested with [`UPDATE_GOLDENS_ENV`], so the golden file was written.
+    Written,
+    /// The golden file does not exist and updating was not requested.
+    Missing,
+    /// The rendering has a different size than the golden file.
+    SizeMismatch { expected: (i32, i32), actual: (i32, i32) },
+    /// Too many pixels differ. A diff image was written next to the golden file.
+    Mismatch { mismatched_pixels: usize, diff_path: PathBuf },
+}
+
+/// Render the item tree built by `ui` and encode it as PNG.
+pub fn render_to_png(options: GoldenOptions, theme: Theme, ui: impl Fn(SharedApp) -> Item) -> Data {
+    let mut headless = Headless::new(options.width, options.height, options.scale_factor, theme, ui);
+    headless.render_png().expect("Could not encode the rendering as PNG")
+}
+
+/// Render the item tree built by `ui` and compare it with the PNG at `golden_path`.
+pub fn compare_golden(golden_path: impl AsRef<Path>, options: GoldenOptions, theme: Theme, ui: impl Fn(SharedApp) -> Item) -> GoldenComparison {
+    let mut headless = Headless::new(options.width, options.height, options.scale_factor, theme, ui);
+    let actual = headless.render();
+    compare_image_with_golden(golden_path, &actual, options)
+}
+
+/// Same as [`compare_golden`], but panics unless the rendering matches or the golden file was written on request.
+pub fn assert_golden(golden_path: impl AsRef<Path>, options: GoldenOptions, theme: Theme, ui: impl Fn(SharedApp) -> Item) {
+    let golden_path = golden_path.as_ref();
+    match compare_golden(golden_path, options, theme, ui) {
+        GoldenComparison::Match | GoldenComparison::Written => {}
+        GoldenComparison::Missing => {
+            panic!("{}: the golden file does not exist, run the test with {}=1 to write it", golden_path.display(), UPDATE_GOLDENS_ENV);
+        }
+        GoldenComparison::SizeMismatch { expected, actual } => {
+            panic!("{}: expected a {}x{} image, got {}x{}", golden_path.display(), expected.0, expected.1, actual.0, actual.1);
+        }
+        GoldenComparison::Mismatch { mismatched_pixels, diff_path } => {
+            panic!("{}: {} pixels differ, see {}", golden_path.display(), mismatched_pixels, diff_path.display());
+        }
+    }
+}
+
+pub fn compare_image_with_golden(golden_path: impl AsRef<Path>, actual: &Image, options: GoldenOptions) -> GoldenComparison {
+    let golden_path = golden_path.as_ref();
+    if update_goldens() {
+        write_png(golden_path, actual);
+        return GoldenComparison::Written;
+    }
+    if !golden_path.exists() {
+        return GoldenComparison::Missing;
+    }
+
+    let golden_data = Data::new_copy(&fs::read(golden_path).expect("Could not read the golden file"));
+    let expected = Image::from_encoded(golden_data).expect("Could not decode the golden file");
+
+    let expected_size = (expected.width(), expected.height());
+    let actual_size = (actual.width(), actual.height());
+    if expected_size != actual_size {
+        return GoldenComparison::SizeMismatch { expected: expected_size, actual: actual_size };
+    }
+
+    let expected_pixels = read_rgba(&expected);
+    let actual_pixels = read_rgba(actual);
+
+    let mut mismatched_pixels = 0;
+    let mut diff_pixels = vec![0_u8; actual_pixels.len()];
+    for ((expected, actual), diff) in expected_pixels.chunks(4).zip(actual_pixels.chunks(4)).zip(diff_pixels.chunks_mut(4)) {
+        let max_delta = expected.iter().zip(actual.iter()).map(|(e, a)| e.abs_diff(*a)).max().unwrap_or(0);
+        if max_delta > options.tolerance {
+            mismatched_pixels += 1;
+            diff.copy_from_slice(&[255, 0, 0, 255]);
+        } else {
+            // Keep a faded copy of the expected pixel so the mismatches can be located.
+            diff.copy_from_slice(&[expected[0] / 4, expected[1] / 4, expected[2] / 4, 255]);
+        }
+    }
+
+    if mismatched_pixels <= options.max_mismatched_pixels {
+        return GoldenComparison::Match;
+    }
+
+    let diff_path = sibling_path(golden_path, "diff");
+    let info = rgba_info(actual.width(), actual.height());
+    let row_bytes = info.min_row_bytes();
+    let diff_image = images::raster_from_data(&info, Data::new_copy(&diff_pixels), row_bytes).expect("Could not create the diff image");
+    write_png(&diff_path, &diff_image);
+    write_png(sibling_path(golden_path, "actual"), actual);
+
+    GoldenComparison::Mismatch { mismatched_pixels, diff_path }
+}
+
+fn rgba_info(width: i32, height: i32) -> ImageInfo {
+    ImageInfo::new((width, height), ColorType::RGBA8888, AlphaType::Unpremul, None)
+}
+
+fn read_rgba(image: &Image) -> Vec<u8> {
+    let info = rgba_info(image.width(), image.height());
+    let row_bytes = info.min_row_bytes();
+    let mut pixels = vec![0_u8; row_bytes * image.height() as usize];
+    if !image.read_pixels(&info, &mut pixels, row_bytes, (0, 0), CachingHint::Allow) {
+        panic!("Could not read the pixels of the image");
+    }
+    pixels
+}
+
+fn write_png(path: impl AsRef<Path>, image: &Image) {
+    let path = path.as_ref();
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).expect("Could not create the golden directory");
+    }
+    let data = image.encode(None, EncodedImageFormat::PNG, None).expect("Could not encode the image as PNG");
+    fs::write(path, data.as_bytes()).expect("Could not write the image");
+}
+
+/// `golden/rectangle.png` -> `golden/rectangle.<suffix>.png`
+fn sibling_path(path: &Path, suffix: &str) -> PathBuf {
+    let stem = path.file_stem().map(|stem| stem.to_string_lossy().to_string()).unwrap_or_default();
+    path.with_file_name(format!("{}.{}.png", stem, suffix))
+}
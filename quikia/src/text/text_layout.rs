@@ -131,6 +131,16 @@ impl ParagraphWrapper {
         self.paragraph.height()
     }
 
+    /// The width of the longest word, the paragraph can not get narrower without breaking it.
+    pub fn min_intrinsic_width(&self) -> f32 {
+        self.paragraph.min_intrinsic_width()
+    }
+
+    /// The width of the longest line when no line is wrapped.
+    pub fn max_intrinsic_width(&self) -> f32 {
+        self.paragraph.max_intrinsic_width()
+    }
+
     pub fn base_line(&self) -> f32 {
         if let Some(line_metrics) = self.paragraph.get_line_metrics_at(0) {
             line_metrics.baseline as f32
@@ -0,0 +1,67 @@
+use crate::ui::{AdditionalProperty, Item};
+
+/// The offsets of a child of an [`Overlay`](crate::layout::Overlay) from the edges of the overlay.
+/// A child with an offset on an axis is positioned absolutely on that axis, start and end follow the layout direction.
+/// A child with offsets from both edges of an axis and the default size stretches between them.
+pub trait AbsolutePlacement {
+    fn offset_start(self, offset: f32) -> Self;
+    fn get_offset_start(&self) -> Option<f32>;
+    fn offset_top(self, offset: f32) -> Self;
+    fn get_offset_top(&self) -> Option<f32>;
+    fn offset_end(self, offset: f32) -> Self;
+    fn get_offset_end(&self) -> Option<f32>;
+    fn offset_bottom(self, offset: f32) -> Self;
+    fn get_offset_bottom(&self) -> Option<f32>;
+    /// Whether the child has an offset on any axis, so it does not take part in sizing the overlay.
+    fn is_absolute(&self) -> bool;
+}
+
+fn get_f32(item: &Item, key: &str) -> Option<f32> {
+    if let Some(AdditionalProperty::F32(value)) = item.get_additional_property(key) {
+        Some(*value)
+    } else {
+        None
+    }
+}
+
+impl AbsolutePlacement for Item {
+    fn offset_start(mut self, offset: f32) -> Self {
+        self.set_additional_property("offset_start", AdditionalProperty::F32(offset));
+        self
+    }
+
+    fn get_offset_start(&self) -> Option<f32> {
+        get_f32(self, "offset_start")
+    }
+
+    fn offset_top(mut self, offset: f32) -> Self {
+        self.set_additional_property("offset_top", AdditionalProperty::F32(offset));
+        self
+    }
+
+    fn get_offset_top(&self) -> Option<f32> {
+        get_f32(self, "offset_top")
+    }
+
+    fn offset_end(mut self, offset: f32) -> Self {
+        self.set_additional_property("offset_end", AdditionalProperty::F32(offset));
+        self
+    }
+
+    fn get_offset_end(&self) -> Option<f32> {
+        get_f32(self, "offset_end")
+    }
+
+    fn offset_bottom(mut self, offset: f32) -> Self {
+        self.set_additional_property("offset_bottom", AdditionalProperty::F32(offset));
+        self
+    }
+
+    fn get_offset_bottom(&self) -> Option<f32> {
+        get_f32(self, "offset_bottom")
+    }
+
+    fn is_absolute(&self) -> bool {
+        self.get_offset_start().is_some() || self.get_offset_top().is_some() || self.get_offset_end().is_some() || self.get_offset_bottom().is_some()
+    }
+}
@@ -0,0 +1,165 @@
+use crate::ui::{AdditionalProperty, Item};
+
+/// The tag a child of a [`ConstraintLayout`](crate::layout::ConstraintLayout) uses to refer to the layout itself.
+pub const PARENT: &str = "parent";
+
+/// An edge of an item that a constraint is attached to.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ConstraintAnchor {
+    Start,
+    End,
+    Top,
+    Bottom,
+}
+
+/// Constraints of a child of a [`ConstraintLayout`](crate::layout::ConstraintLayout).
+/// Siblings are referred to by their tag, and the layout by [`PARENT`].
+///
+/// A child constrained on both sides of an axis is placed between the two anchors according to its bias,
+/// or stretched between them if its size along the axis is [`Size::Fill`](crate::property::Size::Fill).
+pub trait ConstraintPlacement {
+    fn start_to_start_of(self, tag: impl Into<String>) -> Self;
+    fn start_to_end_of(self, tag: impl Into<String>) -> Self;
+    fn end_to_start_of(self, tag: impl Into<String>) -> Self;
+    fn end_to_end_of(self, tag: impl Into<String>) -> Self;
+    fn top_to_top_of(self, tag: impl Into<String>) -> Self;
+    fn top_to_bottom_of(self, tag: impl Into<String>) -> Self;
+    fn bottom_to_top_of(self, tag: impl Into<String>) -> Self;
+    fn bottom_to_bottom_of(self, tag: impl Into<String>) -> Self;
+    /// Align the baseline with the baseline of a sibling, the top and bottom constraints are ignored.
+    fn baseline_to_baseline_of(self, tag: impl Into<String>) -> Self;
+    fn center_horizontally_in(self, tag: impl Into<String>) -> Self;
+    fn center_vertically_in(self, tag: impl Into<String>) -> Self;
+    /// Where the child is placed between its start and end anchors, from 0 at the start to 1 at the end. 0.5 by default.
+    fn horizontal_bias(self, bias: f32) -> Self;
+    /// Where the child is placed between its top and bottom anchors, from 0 at the top to 1 at the bottom. 0.5 by default.
+    fn vertical_bias(self, bias: f32) -> Self;
+
+    fn get_constraint(&self, anchor: ConstraintAnchor) -> Option<(String, ConstraintAnchor)>;
+    fn get_baseline_constraint(&self) -> Option<String>;
+    fn get_horizontal_bias(&self) -> f32;
+    fn get_vertical_bias(&self) -> f32;
+}
+
+fn anchor_name(anchor: ConstraintAnchor) -> &'static str {
+    match anchor {
+        ConstraintAnchor::Start => "start",
+        ConstraintAnchor::End => "end",
+        ConstraintAnchor::Top => "top",
+        ConstraintAnchor::Bottom => "bottom",
+    }
+}
+
+/// The anchors of the target that an anchor can be attached to.
+fn target_anchors(anchor: ConstraintAnchor) -> [ConstraintAnchor; 2] {
+    match anchor {
+        ConstraintAnchor::Start | ConstraintAnchor::End => [ConstraintAnchor::Start, ConstraintAnchor::End],
+        ConstraintAnchor::Top | ConstraintAnchor::Bottom => [ConstraintAnchor::Top, ConstraintAnchor::Bottom],
+    }
+}
+
+fn constraint_key(anchor: ConstraintAnchor, target_anchor: ConstraintAnchor) -> String {
+    format!("constraint_{}_to_{}", anchor_name(anchor), anchor_name(target_anchor))
+}
+
+/// Attach `anchor` to `target_anchor` of `tag`, replacing the previous constraint of `anchor`.
+fn constrain(mut item: Item, anchor: ConstraintAnchor, tag: String, target_anchor: ConstraintAnchor) -> Item {
+    for other in target_anchors(anchor) {
+        item.remove_additional_property(constraint_key(anchor, other));
+    }
+    item.set_additional_property(constraint_key(anchor, target_anchor), AdditionalProperty::String(tag));
+    item
+}
+
+fn get_f32(item: &Item, key: &str) -> Option<f32> {
+    if let Some(AdditionalProperty::F32(value)) = item.get_additional_property(key) {
+        Some(*value)
+    } else {
+        None
+    }
+}
+
+impl ConstraintPlacement for Item {
+    fn start_to_start_of(self, tag: impl Into<String>) -> Self {
+        constrain(self, ConstraintAnchor::Start, tag.into(), ConstraintAnchor::Start)
+    }
+
+    fn start_to_end_of(self, tag: impl Into<String>) -> Self {
+        constrain(self, ConstraintAnchor::Start, tag.into(), ConstraintAnchor::End)
+    }
+
+    fn end_to_start_of(self, tag: impl Into<String>) -> Self {
+        constrain(self, ConstraintAnchor::End, tag.into(), ConstraintAnchor::Start)
+    }
+
+    fn end_to_end_of(self, tag: impl Into<String>) -> Self {
+        constrain(self, ConstraintAnchor::End, tag.into(), ConstraintAnchor::End)
+    }
+
+    fn top_to_top_of(self, tag: impl Into<String>) -> Self {
+        constrain(self, ConstraintAnchor::Top, tag.into(), ConstraintAnchor::Top)
+    }
+
+    fn top_to_bottom_of(self, tag: impl Into<String>) -> Self {
+        constrain(self, ConstraintAnchor::Top, tag.into(), ConstraintAnchor::Bottom)
+    }
+
+    fn bottom_to_top_of(self, tag: impl Into<String>) -> Self {
+        constrain(self, ConstraintAnchor::Bottom, tag.into(), ConstraintAnchor::Top)
+    }
+
+    fn bottom_to_bottom_of(self, tag: impl Into<String>) -> Self {
+        constrain(self, ConstraintAnchor::Bottom, tag.into(), ConstraintAnchor::Bottom)
+    }
+
+    fn baseline_to_baseline_of(mut self, tag: impl Into<String>) -> Self {
+        self.set_additional_property("constraint_baseline_to_baseline", AdditionalProperty::String(tag.into()));
+        self
+    }
+
+    fn center_horizontally_in(self, tag: impl Into<String>) -> Self {
+        let tag = tag.into();
+        self.start_to_start_of(tag.clone()).end_to_end_of(tag)
+    }
+
+    fn center_vertically_in(self, tag: impl Into<String>) -> Self {
+        let tag = tag.into();
+        self.top_to_top_of(tag.clone()).bottom_to_bottom_of(tag)
+    }
+
+    fn horizontal_bias(mut self, bias: f32) -> Self {
+        self.set_additional_property("horizontal_bias", AdditionalProperty::F32(bias));
+        self
+    }
+
+    fn vertical_bias(mut self, bias: f32) -> Self {
+        self.set_additional_property("vertical_bias", AdditionalProperty::F32(bias));
+        self
+    }
+
+    fn get_constraint(&self, anchor: ConstraintAnchor) -> Option<(String, ConstraintAnchor)> {
+        target_anchors(anchor).into_iter().find_map(|target_anchor| {
+            if let Some(AdditionalProperty::String(tag)) = self.get_additional_property(constraint_key(anchor, target_anchor)) {
+                Some((tag.clone(), target_anchor))
+            } else {
+                None
+            }
+        })
+    }
+
+    fn get_baseline_constraint(&self) -> Option<String> {
+        if let Some(AdditionalProperty::String(tag)) = self.get_additional_property("constraint_baseline_to_baseline") {
+            Some(tag.clone())
+        } else {
+            None
+        }
+    }
+
+    fn get_horizontal_bias(&self) -> f32 {
+        get_f32(self, "horizontal_bias").unwrap_or(0.5)
+    }
+
+    fn get_vertical_bias(&self) -> f32 {
+        get_f32(self, "vertical_bias").unwrap_or(0.5)
+    }
+}
@@ -0,0 +1,82 @@
+use crate::layout::ItemAlign;
+use crate::ui::{AdditionalProperty, Item};
+
+/// How a child of a [`FlexLayout`](crate::layout::FlexLayout) takes part in sharing the space of its row, as in CSS flexbox.
+pub trait FlexItem {
+    /// The share of the free space of the row the child grows by, 0 by default.
+    fn flex_grow(self, flex_grow: f32) -> Self;
+    fn get_flex_grow(&self) -> f32;
+    /// How much the child shrinks, relative to the other children and weighted by their basis, when the row overflows. 1 by default.
+    fn flex_shrink(self, flex_shrink: f32) -> Self;
+    fn get_flex_shrink(&self) -> f32;
+    /// The size along the main axis before growing or shrinking. The measured size of the child by default.
+    fn flex_basis(self, flex_basis: f32) -> Self;
+    fn get_flex_basis(&self) -> Option<f32>;
+    /// Override the `align_items` of the layout for this child.
+    fn align_self(self, align_self: ItemAlign) -> Self;
+    fn get_align_self(&self) -> Option<ItemAlign>;
+}
+
+fn get_f32(item: &Item, key: &str) -> Option<f32> {
+    if let Some(AdditionalProperty::F32(value)) = item.get_additional_property(key) {
+        Some(*value)
+    } else {
+        None
+    }
+}
+
+impl FlexItem for Item {
+    fn flex_grow(mut self, flex_grow: f32) -> Self {
+        self.set_additional_property("flex_grow", AdditionalProperty::F32(flex_grow.max(0.0)));
+        self
+    }
+
+    fn get_flex_grow(&self) -> f32 {
+        get_f32(self, "flex_grow").unwrap_or(0.0)
+    }
+
+    fn flex_shrink(mut self, flex_shrink: f32) -> Self {
+        self.set_additional_property("flex_shrink", AdditionalProperty::F32(flex_shrink.max(0.0)));
+        self
+    }
+
+    fn get_flex_shrink(&self) -> f32 {
+        get_f32(self, "flex_shrink").unwrap_or(1.0)
+    }
+
+    fn flex_basis(mut self, flex_basis: f32) -> Self {
+        self.set_additional_property("flex_basis", AdditionalProperty::F32(flex_basis.max(0.0)));
+        self
+    }
+
+    fn get_flex_basis(&self) -> Option<f32> {
+        get_f32(self, "flex_basis")
+    }
+
+    fn align_self(mut self, align_self: ItemAlign) -> Self {
+        let value: u8 = match align_self {
+            ItemAlign::Start => 0,
+            ItemAlign::End => 1,
+            ItemAlign::Center => 2,
+            ItemAlign::Baseline => 3,
+            ItemAlign::Stretch => 4,
+        };
+        self.set_additional_property("align_self", AdditionalProperty::U8(value));
+        self
+    }
+
+    fn get_align_self(&self) -> Option<ItemAlign> {
+        if let Some(AdditionalProperty::U8(value)) = self.get_additional_property("align_self") {
+            match value {
+                0 => Some(ItemAlign::Start),
+                1 => Some(ItemAlign::End),
+                2 => Some(ItemAlign::Center),
+                3 => Some(ItemAlign::Baseline),
+                4 => Some(ItemAlign::Stretch),
+                _ => None,
+            }
+        } else {
+            None
+        }
+    }
+}
@@ -0,0 +1,62 @@
+use crate::ui::{AdditionalProperty, Item};
+
+/// The cell of a child of a [`Grid`](crate::layout::Grid).
+/// A child without a row or a column is placed in the next free cell, row by row.
+pub trait GridPlacement {
+    fn grid_row(self, row: usize) -> Self;
+    fn get_grid_row(&self) -> Option<usize>;
+    fn grid_column(self, column: usize) -> Self;
+    fn get_grid_column(&self) -> Option<usize>;
+    /// The number of rows the child covers, 1 by default.
+    fn grid_row_span(self, span: usize) -> Self;
+    fn get_grid_row_span(&self) -> usize;
+    /// The number of columns the child covers, 1 by default.
+    fn grid_column_span(self, span: usize) -> Self;
+    fn get_grid_column_span(&self) -> usize;
+}
+
+fn get_usize(item: &Item, key: &str) -> Option<usize> {
+    if let Some(AdditionalProperty::Usize(value)) = item.get_additional_property(key) {
+        Some(*value)
+    } else {
+        None
+    }
+}
+
+impl GridPlacement for Item {
+    fn grid_row(mut self, row: usize) -> Self {
+        self.set_additional_property("grid_row", AdditionalProperty::Usize(row));
+        self
+    }
+
+    fn get_grid_row(&self) -> Option<usize> {
+        get_usize(self, "grid_row")
+    }
+
+    fn grid_column(mut self, column: usize) -> Self {
+        self.set_additional_property("grid_column", AdditionalProperty::Usize(column));
+        self
+    }
+
+    fn get_grid_column(&self) -> Option<usize> {
+        get_usize(self, "grid_column")
+    }
+
+    fn grid_row_span(mut self, span: usize) -> Self {
+        self.set_additional_property("grid_row_span", AdditionalProperty::Usize(span.max(1)));
+        self
+    }
+
+    fn get_grid_row_span(&self) -> usize {
+        get_usize(self, "grid_row_span").unwrap_or(1)
+    }
+
+    fn grid_column_span(mut self, span: usize) -> Self {
+        self.set_additional_property("grid_column_span", AdditionalProperty::Usize(span.max(1)));
+        self
+    }
+
+    fn get_grid_column_span(&self) -> usize {
+        get_usize(self, "grid_column_span").unwrap_or(1)
+    }
+}
@@ -1,5 +1,13 @@
 mod baseline;
 mod shadow;
+mod grid_placement;
+mod constraint;
+mod flex_item;
+mod absolute_placement;
 
 pub use baseline::*;
-pub use shadow::*;
\ No newline at end of file
+pub use shadow::*;
+pub use grid_placement::*;
+pub use constraint::*;
+pub use flex_item::*;
+pub use absolute_placement::*;
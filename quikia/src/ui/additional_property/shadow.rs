@@ -1,6 +1,6 @@
 use skia_safe::Color;
 use crate::ui::{AdditionalProperty, Item};
-use crate::property::{Observable, Observer, SharedProperty};
+use crate::property::SharedProperty;
 
 pub trait ShadowColor {
     fn get_shadow_color(&self) -> Option<SharedProperty<Color>>;
@@ -21,14 +21,11 @@ impl ShadowColor for Item {
     }
 
     fn shadow_color(mut self, color: impl Into<SharedProperty<Color>>) -> Self {
-        if let Some(color) = self.get_shadow_color() {
-            color.clear_observers();
-        }
         let color = color.into();
         let app = self.get_app().clone();
-        color.add_observer(Observer::new_without_id(move || {
+        self.subscribe(&color, move || {
             app.request_redraw();
-        }));
+        });
         self.set_additional_property("shadow_color", AdditionalProperty::SharedColor(color));
         self
     }
@@ -53,14 +50,11 @@ impl ShadowOffsetX for Item {
     }
 
     fn shadow_offset_x(mut self, offset: impl Into<SharedProperty<f32>>) -> Self {
-        if let Some(offset) = self.get_shadow_offset_x() {
-            offset.clear_observers();
-        }
         let offset = offset.into();
         let app = self.get_app().clone();
-        offset.add_observer(Observer::new_without_id(move || {
+        self.subscribe(&offset, move || {
             app.request_redraw();
-        }));
+        });
         self.set_additional_property("shadow_offset_x", AdditionalProperty::SharedF32(offset));
         self
     }
@@ -85,14 +79,11 @@ impl ShadowOffsetY for Item {
     }
 
     fn shadow_offset_y(mut self, offset: impl Into<SharedProperty<f32>>) -> Self {
-        if let Some(offset) = self.get_shadow_offset_y() {
-            offset.clear_observers();
-        }
         let offset = offset.into();
         let app = self.get_app().clone();
-        offset.add_observer(Observer::new_without_id(move || {
+        self.subscribe(&offset, move || {
             app.request_redraw();
-        }));
+        });
         self.set_additional_property("shadow_offset_y", AdditionalProperty::SharedF32(offset));
         self
     }
@@ -117,14 +108,11 @@ impl ShadowBlur for Item {
     }
 
     fn shadow_blur(mut self, blur: impl Into<SharedProperty<f32>>) -> Self {
-        if let Some(blur) = self.get_shadow_blur() {
-            blur.clear_observers();
-        }
         let blur = blur.into();
         let app = self.get_app().clone();
-        blur.add_observer(Observer::new_without_id(move || {
+        self.subscribe(&blur, move || {
             app.request_redraw();
-        }));
+        });
         self.set_additional_property("shadow_blur", AdditionalProperty::SharedF32(blur));
         self
     }
@@ -0,0 +1,176 @@
+use std::time::{Duration, Instant};
+
+use crate::ui::{LayoutParams, PointerAction, PointerType};
+
+/// Thresholds used by [`GestureRecognizer`].
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct GestureConfig {
+    /// The distance in logical pixels a pointer may move before it is no longer considered a press in place.
+    pub touch_slop: f32,
+    /// How long a pointer must stay down before a long press is recognized.
+    pub long_press_timeout: Duration,
+    /// The maximum time between two clicks that form a double click.
+    pub double_click_timeout: Duration,
+}
+
+impl Default for GestureConfig {
+    fn default() -> Self {
+        Self {
+            touch_slop: 8.0,
+            long_press_timeout: Duration::from_millis(500),
+            double_click_timeout: Duration::from_millis(300),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum GestureEvent {
+    /// A pointer went down inside the bounds.
+    Pressed,
+    Click,
+    DoubleClick,
+    LongPress,
+    /// The pointer left the bounds or was cancelled, no gesture will be recognized for this press.
+    Cancel,
+}
+
+#[derive(Clone, Copy, Debug)]
+struct Press {
+    pointer_type: PointerType,
+    x: f32,
+    y: f32,
+    time: Instant,
+    beyond_slop: bool,
+    long_pressed: bool,
+}
+
+/// Turns a stream of [`PointerAction`]s into clicks, double clicks and long presses.
+pub struct GestureRecognizer {
+    config: GestureConfig,
+    press: Option<Press>,
+    last_click: Option<(Instant, f32, f32)>,
+}
+
+impl GestureRecognizer {
+    pub fn new(config: GestureConfig) -> Self {
+        Self {
+            config,
+            press: None,
+            last_click: None,
+        }
+    }
+
+    pub fn config(&self) -> GestureConfig {
+        self.config
+    }
+
+    pub fn set_config(&mut self, config: GestureConfig) {
+        self.config = config;
+    }
+
+    pub fn is_pressed(&self) -> bool {
+        self.press.is_some()
+    }
+
+    /// Feed a pointer action. `bounds` are the layout params of the item the gestures belong to.
+    pub fn handle(&mut self, action: PointerAction, bounds: &LayoutParams, now: Instant) -> Vec<GestureEvent> {
+        let mut events = Vec::new();
+        match action {
+            PointerAction::Down { x, y, pointer_type } => {
+                if self.press.is_some() || !bounds.contains(x, y) {
+                    return events;
+                }
+                self.press = Some(Press {
+                    pointer_type,
+                    x,
+                    y,
+                    time: now,
+                    beyond_slop: false,
+                    long_pressed: false,
+                });
+                events.push(GestureEvent::Pressed);
+            }
+            PointerAction::Move { x, y, pointer_type } => {
+                let Some(press) = self.press.as_mut() else { return events; };
+                if press.pointer_type != pointer_type {
+                    return events;
+                }
+                if !bounds.contains(x, y) {
+                    self.press = None;
+                    events.push(GestureEvent::Cancel);
+                    return events;
+                }
+                if distance(press.x, press.y, x, y) > self.config.touch_slop {
+                    press.beyond_slop = true;
+                }
+                if let Some(event) = self.check_long_press(now) {
+                    events.push(event);
+                }
+            }
+            PointerAction::Up { x, y, pointer_type } => {
+                let Some(press) = self.press else { return events; };
+                if press.pointer_type != pointer_type {
+                    return events;
+                }
+                if !bounds.contains(x, y) {
+                    self.press = None;
+                    events.push(GestureEvent::Cancel);
+                    return events;
+                }
+                if let Some(event) = self.check_long_press(now) {
+                    events.push(event);
+                }
+                let press = self.press.take().unwrap();
+                if press.long_pressed {
+                    self.last_click = None;
+                    return events;
+                }
+
+                events.push(GestureEvent::Click);
+                match self.last_click {
+                    Some((time, last_x, last_y)) if now.duration_since(time) <= self.config.double_click_timeout
+                        && distance(last_x, last_y, x, y) <= self.config.touch_slop => {
+                        events.push(GestureEvent::DoubleClick);
+                        self.last_click = None;
+                    }
+                    _ => {
+                        self.last_click = Some((now, x, y));
+                    }
+                }
+            }
+            PointerAction::Cancel => {
+                if self.press.take().is_some() {
+                    events.push(GestureEvent::Cancel);
+                }
+            }
+        }
+        events
+    }
+
+    /// Recognize a long press if the pointer has been held long enough without moving beyond the touch slop.
+    /// Call this when the long press timer expires.
+    pub fn check_long_press(&mut self, now: Instant) -> Option<GestureEvent> {
+        let config = self.config;
+        let press = self.press.as_mut()?;
+        if press.long_pressed || press.beyond_slop || now.duration_since(press.time) < config.long_press_timeout {
+            return None;
+        }
+        press.long_pressed = true;
+        Some(GestureEvent::LongPress)
+    }
+
+    pub fn reset(&mut self) {
+        self.press = None;
+        self.last_click = None;
+    }
+}
+
+impl Default for GestureRecognizer {
+    fn default() -> Self {
+        Self::new(GestureConfig::default())
+    }
+}
+
+fn distance(x1: f32, y1: f32, x2: f32, y2: f32) -> f32 {
+    ((x2 - x1).powi(2) + (y2 - y1).powi(2)).sqrt()
+}
@@ -1,17 +1,17 @@
 use std::collections::HashMap;
 use std::fs;
-use std::io::Read;
 use std::path::PathBuf;
 use std::sync::{Arc, RwLock};
 use std::sync::Mutex;
+use std::thread::JoinHandle;
 use lazy_static::lazy_static;
-use skia_safe::{BlendMode, Canvas, Color, Data, FontMgr, M44, Matrix, Paint, Rect, SamplingOptions};
+use skia_safe::{BlendMode, Canvas, Color, Data, FontMgr, Paint, Rect, SamplingOptions};
 use skia_safe::canvas::SaveLayerRec;
-use crate::ui::{Gravity, Item, ItemEvent, LayoutDirection, MeasureMode};
+use crate::ui::{clamp_size, Gravity, Item, ItemEvent, LayoutDirection, MeasureMode};
 use skia_safe::Image as SkImage;
 use skia_safe::svg::Dom;
 use skia_safe::wrapper::PointerWrapper;
-use crate::app::{SharedApp, UserEvent};
+use crate::app::SharedApp;
 use crate::{FilterMode, MipmapMode};
 use crate::property::{BoolProperty, Gettable, SharedProperty};
 
@@ -37,7 +37,7 @@ impl Svg {
         let path = path.into();
         let data = Data::new_copy(fs::read(&path).unwrap().as_slice());
         let font_mgr = FontMgr::new();
-        let dom = Dom::from_bytes(&data, &font_mgr).unwrap();
+        let dom = Dom::from_bytes(&data, font_mgr).unwrap();
         let width = dom.inner().fContainerSize.fWidth;
         let height = dom.inner().fContainerSize.fHeight;
         Self {
@@ -200,28 +200,26 @@ pub struct NetworkImage {
 
 impl NetworkImage {
     pub fn from_url(url: &PathBuf, app: &SharedApp) -> Self {
+        let url = url.clone();
+        let (network_image, _) = Self::from_fetch(app, move || {
+            let response = reqwest::blocking::get(url.to_str().unwrap()).ok()?;
+            response.bytes().ok().map(|bytes| bytes.to_vec())
+        });
+        network_image
+    }
+
+    /// Decode the bytes returned by `fetch` on a worker thread, then request a layout from the event loop thread.
+    pub(crate) fn from_fetch(app: &SharedApp, fetch: impl FnOnce() -> Option<Vec<u8>> + Send + 'static) -> (Self, JoinHandle<()>) {
         let image = Arc::new(RwLock::new(None));
         let image_clone = image.clone();
-        let url = url.clone();
-        let app = app.clone();
-        std::thread::spawn(move || {
-            let response = reqwest::blocking::get(url.to_str().unwrap());
-            if let Ok(response) = response {
-                let bytes = response.bytes();
-                if let Ok(bytes) = bytes {
-                    let bytes = bytes.as_ref();
-                    let image = ImageDrawable::from_bytes(bytes);
-                    let mut image_guard = image_clone.write().unwrap();
-                    *image_guard = Some(image);
-                    // app.request_layout();
-                    // app.send_event(UserEvent::Empty);
-                    //TODO: Maybe request_layout() not working outside the main thread.
-                }
+        let ui_sender = app.ui_sender();
+        let loading = std::thread::spawn(move || {
+            if let Some(bytes) = fetch() {
+                *image_clone.write().unwrap() = Some(ImageDrawable::from_bytes(&bytes));
+                ui_sender.request_layout();
             }
         });
-        Self {
-            image,
-        }
+        (Self { image }, loading)
     }
 }
 
@@ -315,7 +313,7 @@ impl Image {
             oversize_scale_mode: ScaleMode::FitLongerSide.into(),
             undersize_scale_mode: ScaleMode::FitLongerSide.into(),
         }));
-        let mut item = Item::new(
+        let item = Item::new(
             app,
             ItemEvent::default()
                 .set_on_draw(
@@ -323,8 +321,8 @@ impl Image {
                         let properties = properties.clone();
                         move |item, canvas| {
                             let layout_params = item.get_layout_params();
-                            let mut x = layout_params.x;
-                            let mut y = layout_params.y;
+                            let mut x = layout_params.x();
+                            let mut y = layout_params.y();
                             let width = layout_params.width - layout_params.padding_start - layout_params.padding_end;
                             let height = layout_params.height - layout_params.padding_top - layout_params.padding_bottom;
 
@@ -349,46 +347,22 @@ impl Image {
                             let properties = properties.lock().unwrap();
                             let image = &properties.image;
                             if let Some(drawable) = image.lock().as_ref() {
-                                let mut x = 0.0;
-                                let mut y = 0.0;
-
-                                match item.get_vertical_gravity().get() {
-                                    Gravity::Start => {
-                                        match item.get_layout_direction().get() {
-                                            LayoutDirection::LeftToRight => {
-                                                x = layout_params.x + layout_params.padding_start;
-                                            }
-                                            LayoutDirection::RightToLeft => {
-                                                x = layout_params.x + layout_params.width - layout_params.padding_end - drawable.read().unwrap().width();
-                                            }
-                                        }
+                                let drawable_width = drawable.read().unwrap().width();
+                                let drawable_height = drawable.read().unwrap().height();
+                                let x = match (item.get_horizontal_gravity().get(), item.get_layout_direction().get()) {
+                                    (Gravity::Start, LayoutDirection::LeftToRight) | (Gravity::End, LayoutDirection::RightToLeft) => {
+                                        layout_params.x() + layout_params.padding_start
                                     }
-                                    Gravity::Center => {
-                                        x = layout_params.x + (layout_params.width - drawable.read().unwrap().width()) / 2.0;
+                                    (Gravity::Start, LayoutDirection::RightToLeft) | (Gravity::End, LayoutDirection::LeftToRight) => {
+                                        layout_params.x() + layout_params.width - layout_params.padding_end - drawable_width
                                     }
-                                    Gravity::End => {
-                                        match item.get_layout_direction().get() {
-                                            LayoutDirection::LeftToRight => {
-                                                x = layout_params.x + layout_params.width - layout_params.padding_end - drawable.read().unwrap().width();
-                                            }
-                                            LayoutDirection::RightToLeft => {
-                                                x = layout_params.x + layout_params.padding_start;
-                                            }
-                                        }
-                                    }
-                                }
-
-                                match item.get_horizontal_gravity().get() {
-                                    Gravity::Start => {
-                                        y = layout_params.y + layout_params.padding_top;
-                                    }
-                                    Gravity::Center => {
-                                        y = layout_params.y + (layout_params.height - drawable.read().unwrap().height()) / 2.0;
-                                    }
-                                    Gravity::End => {
-                                        y = layout_params.y + layout_params.height - layout_params.padding_bottom - drawable.read().unwrap().height();
-                                    }
-                                }
+                                    (Gravity::Center, _) => layout_params.x() + (layout_params.width - drawable_width) / 2.0,
+                                };
+                                let y = match item.get_vertical_gravity().get() {
+                                    Gravity::Start => layout_params.y() + layout_params.padding_top,
+                                    Gravity::Center => layout_params.y() + (layout_params.height - drawable_height) / 2.0,
+                                    Gravity::End => layout_params.y() + layout_params.height - layout_params.padding_bottom - drawable_height,
+                                };
 
                                 if properties.dpi_sensitive.get() {
                                     drawable.read().unwrap().draw(canvas, x, y);
@@ -412,7 +386,7 @@ impl Image {
                         }
                     }
                 )
-                .set_on_measure(
+                .set_measure_event(
                     {
                         let properties = properties.clone();
                         move |item, width_measure_mode, height_measure_mode| {
@@ -429,23 +403,19 @@ impl Image {
 
 
                             if let Some(image) = image.lock().as_ref() {
-                                let mut image_width = 0.0;
-                                let mut image_height = 0.0;
-
-                                if properties_guard.dpi_sensitive.get() {
-                                    image_width = image.read().unwrap().width();
-                                    image_height = image.read().unwrap().height();
+                                let (image_width, image_height) = if properties_guard.dpi_sensitive.get() {
+                                    (image.read().unwrap().width(), image.read().unwrap().height())
                                 } else {
-                                    image_width = image.read().unwrap().width() / item.get_app().scale_factor();
-                                    image_height = image.read().unwrap().height() / item.get_app().scale_factor();
-                                }
+                                    let scale_factor = item.get_app().scale_factor();
+                                    (image.read().unwrap().width() / scale_factor, image.read().unwrap().height() / scale_factor)
+                                };
 
                                 match width_measure_mode {
                                     MeasureMode::Specified(width) => {
                                         match height_measure_mode {
                                             MeasureMode::Specified(height) => {
-                                                layout_params.width = width.clamp(min_width, max_width);
-                                                layout_params.height = height.clamp(min_height, max_height);
+                                                layout_params.width = clamp_size(width, min_width, max_width);
+                                                layout_params.height = clamp_size(height, min_height, max_height);
                                                 let is_undersize = image_width < layout_params.width && image_height < layout_params.height;
 
                                                 let scale_mode = if is_undersize {
@@ -491,7 +461,7 @@ impl Image {
                                                 }
                                             }
                                             MeasureMode::Unspecified(height) => {
-                                                layout_params.width = width.clamp(min_width, max_width);
+                                                layout_params.width = clamp_size(width, min_width, max_width);
 
                                                 let is_undersize = image_width < layout_params.width && image_height < layout_params.height;
                                                 let scale_mode = if is_undersize {
@@ -502,7 +472,7 @@ impl Image {
 
                                                 match scale_mode {
                                                     ScaleMode::NoScale => {
-                                                        layout_params.height = image_height.clamp(min_height, height);
+                                                        layout_params.height = clamp_size(image_height, min_height, height);
                                                     }
                                                     _ => {
                                                         let expected_image_width = layout_params.width - layout_params.padding_start - layout_params.padding_end;
@@ -518,7 +488,7 @@ impl Image {
                                     MeasureMode::Unspecified(width) => {
                                         match height_measure_mode {
                                             MeasureMode::Specified(height) => {
-                                                layout_params.height = height.clamp(min_height, max_height);
+                                                layout_params.height = clamp_size(height, min_height, max_height);
 
                                                 let is_undersize = image_width < layout_params.width && image_height < layout_params.height;
                                                 let scale_mode = if is_undersize {
@@ -529,7 +499,7 @@ impl Image {
 
                                                 match scale_mode {
                                                     ScaleMode::NoScale => {
-                                                        layout_params.width = image_width.clamp(min_width, width);
+                                                        layout_params.width = clamp_size(image_width, min_width, width);
                                                     }
                                                     _ => {
                                                         let expected_image_height = layout_params.height - layout_params.padding_top - layout_params.padding_bottom;
@@ -550,12 +520,12 @@ impl Image {
 
                                                 match scale_mode {
                                                     ScaleMode::NoScale => {
-                                                        layout_params.width = image_width.clamp(min_width, width);
-                                                        layout_params.height = image_height.clamp(min_height, height);
+                                                        layout_params.width = clamp_size(image_width, min_width, width);
+                                                        layout_params.height = clamp_size(image_height, min_height, height);
                                                     }
                                                     _ => {
-                                                        let expected_image_width = (image_width + layout_params.padding_start + layout_params.padding_end).clamp(min_width, max_width);
-                                                        let expected_image_height = (image_height + layout_params.padding_top + layout_params.padding_bottom).clamp(min_height, max_height);
+                                                        let expected_image_width = clamp_size(image_width + layout_params.padding_start + layout_params.padding_end, min_width, max_width);
+                                                        let expected_image_height = clamp_size(image_height + layout_params.padding_top + layout_params.padding_bottom, min_height, max_height);
                                                         image.write().unwrap().set_width(expected_image_width);
                                                         image.write().unwrap().set_height(expected_image_height);
                                                         layout_params.width = expected_image_width;
@@ -587,31 +557,31 @@ impl Image {
         }
     }
 
-    pub fn source(mut self, source: impl Into<PathBuf>) -> Self {
+    pub fn source(self, source: impl Into<PathBuf>) -> Self {
         let source = source.into();
         let mut drawables = DRAWABLES.lock().unwrap();
         if let Some(drawable) = drawables.get(&source) {
-            let mut properties = self.properties.lock().unwrap();
+            let properties = self.properties.lock().unwrap();
             properties.image.set_value(Some(drawable.clone()));
         } else {
             if source.starts_with("http://") || source.starts_with("https://") {
                 let drawable = NetworkImage::from_url(&source, &self.item.get_app());
                 let drawable = Arc::new(RwLock::new(Box::new(drawable) as DrawableImpl));
                 drawables.insert(source.clone(), drawable.clone());
-                let mut properties = self.properties.lock().unwrap();
+                let properties = self.properties.lock().unwrap();
                 properties.image.set_value(Some(drawable.clone()));
             } else if let Some(extension) = source.extension() {
                 if extension == "svg" {
                     let drawable = Svg::from_file(source.clone());
                     let drawable = Arc::new(RwLock::new(Box::new(drawable) as DrawableImpl));
                     drawables.insert(source.clone(), drawable.clone());
-                    let mut properties = self.properties.lock().unwrap();
+                    let properties = self.properties.lock().unwrap();
                     properties.image.set_value(Some(drawable));
                 } else {
                     let drawable = ImageDrawable::from_file(source.clone());
                     let drawable = Arc::new(RwLock::new(Box::new(drawable) as DrawableImpl));
                     drawables.insert(source.clone(), drawable.clone());
-                    let mut properties = self.properties.lock().unwrap();
+                    let properties = self.properties.lock().unwrap();
                     properties.image.set_value(Some(drawable));
                 }
             }
@@ -1,14 +1,30 @@
 use std::collections::HashMap;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::Instant;
 
 use skia_safe::Canvas;
 use winit::event::{DeviceId, KeyEvent, MouseButton};
+use winit::window::CursorIcon;
 
-use crate::app::SharedApp;
+use crate::app::{SharedApp, Timer, UserEvent};
 use crate::{children, impl_item_property};
-use crate::property::{BoolProperty, FloatProperty, Gettable, GravityProperty, ItemCollectionProperty, ItemProperty, Observable, Observer, SharedProperty, Size, SizeProperty};
-use crate::ui::{AdditionalProperty, ButtonState, Gravity, ImeAction, ItemEvent, LayoutDirection, LayoutParams, MeasureMode, PointerAction};
+use crate::property::{get_observable_id, subscribe, BoolProperty, FloatProperty, Gettable, GravityProperty, ItemCollectionProperty, ItemProperty, Observable, Observer, SharedProperty, Size, SizeProperty, Subscription};
+use crate::ui::{AdditionalProperty, ButtonState, GestureConfig, GestureEvent, GestureRecognizer, Gravity, ImeAction, IntrinsicSize, ItemEvent, LayoutDirection, LayoutNode, LayoutParams, MeasureMode, PointerAction};
+
+const LONG_PRESS_TIMER: &str = "long_press";
+
+/// The id of the next item, `0` is never used so that it can stand for "no item".
+static NEXT_ITEM_ID: AtomicUsize = AtomicUsize::new(1);
+
+/// The measure result of an item, see [`Item::save_measure_state`].
+pub(crate) struct MeasureState {
+    layout_params: LayoutParams,
+    measure_cache: Option<(MeasureMode, MeasureMode, usize)>,
+    measure_dirty: bool,
+}
 
 pub struct Item {
+    id: usize,
     app: SharedApp,
     tag: String,
     children: ItemCollectionProperty,
@@ -18,13 +34,18 @@ pub struct Item {
     layout_direction: SharedProperty<LayoutDirection>,
     horizontal_gravity: GravityProperty,
     vertical_gravity: GravityProperty,
+    /// Whether Tab traversal visits the item. Items are not focusable unless they opt in with `focusable(true)`,
+    /// so Tab skips containers and decorations; before the focus manager, every item was focusable by default.
     focusable: BoolProperty,
     focused: BoolProperty,
     focusable_when_clicked: BoolProperty,
+    ime_enabled: BoolProperty,
     min_width: FloatProperty,
     min_height: FloatProperty,
     max_width: FloatProperty,
     max_height: FloatProperty,
+    /// Width divided by height, see [`measure_child`](crate::ui::measure_child).
+    aspect_ratio: SharedProperty<Option<f32>>,
     padding_start: FloatProperty,
     padding_top: FloatProperty,
     padding_end: FloatProperty,
@@ -34,11 +55,26 @@ pub struct Item {
     margin_end: FloatProperty,
     margin_bottom: FloatProperty,
     layout_params: LayoutParams,
+    layout_node: LayoutNode,
+    /// The measure modes and layout generation of the last measure event.
+    measure_cache: Option<(MeasureMode, MeasureMode, usize)>,
+    /// The position and layout generation of the last layout event.
+    layout_cache: Option<(f32, f32, usize)>,
     background: ItemProperty,
     foreground: ItemProperty,
     enable_clipping: BoolProperty,
+    /// Children with a higher z index are drawn above and receive events before their siblings.
+    z_index: SharedProperty<i32>,
     additional_properties: HashMap<String, AdditionalProperty>,
+    /// The observers the item added to its properties, removed when the item is dropped.
+    subscriptions: Vec<Subscription>,
+    /// The observers of the current children, replaced when the children are.
+    children_subscriptions: Vec<Subscription>,
     on_click: Option<Box<dyn Fn()>>,
+    on_long_press: Option<Box<dyn Fn()>>,
+    on_double_click: Option<Box<dyn Fn()>>,
+    gesture_recognizer: GestureRecognizer,
+    long_press_timer: Timer,
     on_blur: Option<Box<dyn Fn()>>,
     on_focus: Option<Box<dyn Fn()>>,
     on_cursor_entered: Box<dyn Fn()>,
@@ -49,17 +85,21 @@ pub struct Item {
     
     measure_event: Box<dyn Fn(&mut Item, MeasureMode, MeasureMode)>,
     layout_event: Box<dyn Fn(&mut Item, f32, f32, )>,
+    intrinsic_size_event: Box<dyn Fn(&mut Item, IntrinsicSize) -> f32>,
     
-    // on_mouse_input: Box<dyn Fn(&mut Item, DeviceId, ButtonState, MouseButton, f32, f32) -> bool>,
-    // 
-    // on_cursor_moved: Box<dyn Fn(&mut Item, f32, f32) -> bool>,
-    // on_cursor_entered_event: Box<dyn Fn(&mut Item)>,
-    // on_cursor_exited_event: Box<dyn Fn(&mut Item)>,
-    // is_cursor_inside: bool,
-    // 
-    // on_pointer_input: Box<dyn Fn(&mut Item, PointerAction) -> bool>,
-    // on_ime_input: Box<dyn Fn(&mut Item, ImeAction) -> bool>,
-    // on_keyboard_input: Box<dyn Fn(&mut Item, DeviceId, KeyEvent, bool) -> bool>,
+    on_mouse_input: Box<dyn Fn(&mut Item, DeviceId, ButtonState, MouseButton, f32, f32) -> bool>,
+
+    on_cursor_moved: Box<dyn Fn(&mut Item, f32, f32) -> bool>,
+    /// item, delta_x, delta_y
+    on_mouse_wheel: Box<dyn Fn(&mut Item, f32, f32) -> bool>,
+    on_cursor_entered_event: Box<dyn Fn(&mut Item)>,
+    on_cursor_exited_event: Box<dyn Fn(&mut Item)>,
+    is_cursor_inside: bool,
+    cursor_icon: SharedProperty<Option<CursorIcon>>,
+
+    on_pointer_input: Box<dyn Fn(&mut Item, PointerAction) -> bool>,
+    on_ime_input: Box<dyn Fn(&mut Item, ImeAction) -> bool>,
+    on_keyboard_input: Box<dyn Fn(&mut Item, DeviceId, KeyEvent, bool) -> bool>,
 }
 
 
@@ -71,10 +111,12 @@ impl_item_property!(Item, horizontal_gravity, get_horizontal_gravity, GravityPro
 impl_item_property!(Item, vertical_gravity, get_vertical_gravity, GravityProperty);
 impl_item_property!(Item, focusable, get_focusable, BoolProperty);
 impl_item_property!(Item, focusable_when_clicked, get_focusable_when_clicked, BoolProperty);
+impl_item_property!(Item, ime_enabled, get_ime_enabled, BoolProperty);
 impl_item_property!(Item, min_width, get_min_width, FloatProperty);
 impl_item_property!(Item, min_height, get_min_height, FloatProperty);
 impl_item_property!(Item, max_width, get_max_width, FloatProperty);
 impl_item_property!(Item, max_height, get_max_height, FloatProperty);
+impl_item_property!(Item, aspect_ratio, get_aspect_ratio, SharedProperty<Option<f32>>);
 impl_item_property!(Item, padding_start, get_padding_start, FloatProperty);
 impl_item_property!(Item, padding_top, get_padding_top, FloatProperty);
 impl_item_property!(Item, padding_end, get_padding_end, FloatProperty);
@@ -86,12 +128,16 @@ impl_item_property!(Item, margin_bottom, get_margin_bottom, FloatProperty);
 impl_item_property!(Item, background, get_background, ItemProperty);
 impl_item_property!(Item, foreground, get_foreground, ItemProperty);
 impl_item_property!(Item, enable_clipping, get_enable_clipping, BoolProperty);
+impl_item_property!(Item, z_index, get_z_index, SharedProperty<i32>);
+impl_item_property!(Item, cursor_icon, get_cursor_icon, SharedProperty<Option<CursorIcon>>);
 
 
 impl Item {
     pub fn new(app: SharedApp, item_events: ItemEvent) -> Self {
         let layout_direction = app.layout_direction();
+        let layout_node = LayoutNode::new(app.clone());
         Item {
+            id: NEXT_ITEM_ID.fetch_add(1, Ordering::Relaxed),
             app,
             tag: String::new(),
             children: children!(),
@@ -101,14 +147,17 @@ impl Item {
             layout_direction: layout_direction.into(),
             horizontal_gravity: Gravity::Start.into(),
             vertical_gravity: Gravity::Start.into(),
-            focusable: true.into(),
+            focusable: false.into(),
             focused: false.into(),
             focusable_when_clicked: true.into(),
-            // is_cursor_inside: false,
+            ime_enabled: false.into(),
+            is_cursor_inside: false,
+            cursor_icon: None.into(),
             min_width: 0.into(),
             min_height: 0.into(),
             max_width: FloatProperty::from_value(f32::MAX),
             max_height: FloatProperty::from_value(f32::MAX),
+            aspect_ratio: None.into(),
             padding_start: 0.into(),
             padding_top: 0.into(),
             padding_end: 0.into(),
@@ -118,11 +167,21 @@ impl Item {
             margin_end: 0.into(),
             margin_bottom: 0.into(),
             layout_params: LayoutParams::default(),
+            layout_node,
+            measure_cache: None,
+            layout_cache: None,
             background: None.into(),
             foreground: None.into(),
             enable_clipping: false.into(),
+            z_index: 0.into(),
             additional_properties: HashMap::new(),
+            subscriptions: Vec::new(),
+            children_subscriptions: Vec::new(),
             on_click: None,
+            on_long_press: None,
+            on_double_click: None,
+            gesture_recognizer: GestureRecognizer::default(),
+            long_press_timer: Timer::new(),
             on_blur: None,
             on_focus: None,
             on_cursor_entered: Box::new(|| {}),
@@ -131,13 +190,15 @@ impl Item {
             on_draw: item_events.on_draw,
             measure_event: item_events.measure_event,
             layout_event: item_events.layout_event,
-            // on_mouse_input: item_events.on_mouse_input,
-            // on_cursor_moved: item_events.on_cursor_moved,
-            // on_cursor_entered_event: item_events.on_cursor_entered,
-            // on_cursor_exited_event: item_events.on_cursor_exited,
-            // on_pointer_input: item_events.on_pointer_input,
-            // on_ime_input: item_events.on_ime_input,
-            // on_keyboard_input: item_events.on_keyboard_input,
+            intrinsic_size_event: item_events.intrinsic_size_event,
+            on_mouse_input: item_events.on_mouse_input,
+            on_cursor_moved: item_events.on_cursor_moved,
+            on_mouse_wheel: item_events.on_mouse_wheel,
+            on_cursor_entered_event: item_events.on_cursor_entered,
+            on_cursor_exited_event: item_events.on_cursor_exited,
+            on_pointer_input: item_events.on_pointer_input,
+            on_ime_input: item_events.on_ime_input,
+            on_keyboard_input: item_events.on_keyboard_input,
         }
     }
 
@@ -145,8 +206,9 @@ impl Item {
         self.app.clone()
     }
 
+    /// An id that is unique among all items and stays the same when the item is moved, e.g. into its parent.
     pub fn get_id(&self) -> usize {
-        self as *const Item as usize
+        self.id
     }
 
     pub fn get_tag(&self) -> &str {
@@ -160,6 +222,31 @@ impl Item {
 
     pub fn set_children(&mut self, children: ItemCollectionProperty) {
         self.children = children;
+        let children = self.children.clone();
+        let collection_subscription = {
+            let layout_node = self.layout_node.clone();
+            subscribe(&children, move || {
+                layout_node.invalidate();
+            })
+        };
+        let items_subscription = {
+            let layout_node = self.layout_node.clone();
+            let owner_id = get_observable_id();
+            children.lock().as_ref().add_observer(
+                Observer::new(move || {
+                    layout_node.invalidate();
+                }, owner_id)
+            );
+            Subscription::new(move || {
+                children.lock().as_ref().remove_observer(owner_id);
+            })
+        };
+        self.children_subscriptions = vec![collection_subscription, items_subscription];
+    }
+
+    /// Observe the observable for as long as the item lives, see [`subscribe`].
+    pub fn subscribe<O: Observable + Clone + 'static>(&mut self, observable: &O, listener: impl FnMut() + 'static) {
+        self.subscriptions.push(subscribe(observable, listener));
     }
 
     pub fn get_children(&self) -> ItemCollectionProperty {
@@ -174,6 +261,10 @@ impl Item {
         self.additional_properties.get(&key.into())
     }
 
+    pub fn remove_additional_property(&mut self, key: impl Into<String>) -> Option<AdditionalProperty> {
+        self.additional_properties.remove(&key.into())
+    }
+
     pub fn focus(&mut self) {
         self.focused.set_value(true);
     }
@@ -204,111 +295,427 @@ impl Item {
         }
     }
 
+    pub fn get_layout_node(&self) -> LayoutNode {
+        self.layout_node.clone()
+    }
+
+    /// Measure and layout this item and its ancestors again with the next frame, see [`LayoutNode::invalidate`].
+    pub fn invalidate_layout(&self) {
+        self.layout_node.invalidate();
+    }
+
+    /// Run the measure event, unless the item is clean and was already measured with the same measure modes.
     pub fn measure(&mut self, width_measure_mode: MeasureMode, height_measure_mode: MeasureMode) {
+        let generation = self.app.layout_generation();
+        if !self.layout_node.is_measure_dirty() && self.measure_cache == Some((width_measure_mode, height_measure_mode, generation)) {
+            if self.layout_node.is_descendant_dirty() {
+                self.for_each_child_mut(|child| {
+                    if child.layout_node.is_measure_dirty() || child.layout_node.is_descendant_dirty() {
+                        if let Some((width_measure_mode, height_measure_mode, _)) = child.measure_cache {
+                            child.measure(width_measure_mode, height_measure_mode);
+                        }
+                    }
+                });
+            }
+            return;
+        }
+
+        let layout_node = self.layout_node.clone();
+        layout_node.begin_measure();
         unsafe {
             let s = self as *const Item;
             let on_measure = &(*s).measure_event;
             on_measure(self, width_measure_mode, height_measure_mode);
         }
+        // Children may have been added by the measure event itself.
+        self.for_each_child_mut(|child| child.layout_node.set_parent(&layout_node));
+        let relayout_boundary = matches!((self.width.get(), self.height.get()), (Size::Fixed(_), Size::Fixed(_)));
+        layout_node.end_measure(relayout_boundary);
+        self.measure_cache = Some((width_measure_mode, height_measure_mode, generation));
+    }
+
+    /// The size the item would like to have along one axis, without committing a layout.
+    ///
+    /// A fixed width or height is returned as is, other sizes come from the intrinsic size event,
+    /// which measures the item by default, see [`measure_intrinsic_size`](crate::ui::measure_intrinsic_size).
+    /// Containers call it from their measure event, before measuring the item for real.
+    pub fn intrinsic_size(&mut self, intrinsic_size: IntrinsicSize) -> f32 {
+        match intrinsic_size {
+            IntrinsicSize::MinWidth(_) | IntrinsicSize::MaxWidth(_) => {
+                if let Size::Fixed(width) = self.width.get() {
+                    return width;
+                }
+            }
+            IntrinsicSize::MinHeight(_) | IntrinsicSize::MaxHeight(_) => {
+                if let Size::Fixed(height) = self.height.get() {
+                    return height;
+                }
+            }
+        }
+        unsafe {
+            let s = self as *const Item;
+            let intrinsic_size_event = &(*s).intrinsic_size_event;
+            intrinsic_size_event(self, intrinsic_size)
+        }
+    }
+
+    pub fn min_intrinsic_width(&mut self, height: f32) -> f32 {
+        self.intrinsic_size(IntrinsicSize::MinWidth(height))
+    }
+
+    pub fn max_intrinsic_width(&mut self, height: f32) -> f32 {
+        self.intrinsic_size(IntrinsicSize::MaxWidth(height))
+    }
+
+    pub fn min_intrinsic_height(&mut self, width: f32) -> f32 {
+        self.intrinsic_size(IntrinsicSize::MinHeight(width))
+    }
+
+    pub fn max_intrinsic_height(&mut self, width: f32) -> f32 {
+        self.intrinsic_size(IntrinsicSize::MaxHeight(width))
+    }
+
+    /// Save the measure results of the item and its descendants by item id, see [`Item::restore_measure_state`].
+    pub(crate) fn save_measure_state(&mut self, states: &mut HashMap<usize, MeasureState>) {
+        states.insert(self.id, MeasureState {
+            layout_params: self.layout_params.clone(),
+            measure_cache: self.measure_cache,
+            measure_dirty: self.layout_node.is_measure_dirty(),
+        });
+        self.for_each_child_mut(|child| child.save_measure_state(states));
+    }
+
+    /// Undo a measure pass that was only a query. Items that were not saved, e.g. built by the query,
+    /// run their measure event again on the next measure call.
+    pub(crate) fn restore_measure_state(&mut self, states: &mut HashMap<usize, MeasureState>) {
+        match states.remove(&self.id) {
+            Some(state) => {
+                self.layout_params = state.layout_params;
+                self.measure_cache = state.measure_cache;
+                if state.measure_dirty {
+                    self.layout_node.mark_measure_dirty();
+                }
+            }
+            None => self.measure_cache = None,
+        }
+        self.for_each_child_mut(|child| child.restore_measure_state(states));
     }
 
+    /// Run the layout event, unless the item is clean and was already laid out at the same position.
     pub fn layout(&mut self, x: f32, y: f32) {
+        let generation = self.app.layout_generation();
+        if !self.layout_node.is_layout_dirty() && self.layout_cache == Some((x, y, generation)) {
+            if self.layout_node.is_descendant_dirty() {
+                self.for_each_child_mut(|child| {
+                    if child.layout_node.is_layout_dirty() || child.layout_node.is_descendant_dirty() {
+                        if let Some((x, y, _)) = child.layout_cache {
+                            child.layout(x, y);
+                        }
+                    }
+                });
+                self.layout_node.layout_done();
+            }
+            return;
+        }
+
         unsafe {
             let s = self as *const Item;
             let on_layout = &(*s).layout_event;
             on_layout(self, x, y);
         }
+        self.layout_node.layout_done();
+        self.layout_cache = Some((x, y, generation));
+    }
+
+    /// Visit the children, the background and the foreground of this item.
+    fn for_each_child_mut(&mut self, mut f: impl FnMut(&mut Item)) {
+        self.children.lock().iter_mut().for_each(&mut f);
+        if let Some(background) = self.background.lock().as_mut() {
+            f(background);
+        }
+        if let Some(foreground) = self.foreground.lock().as_mut() {
+            f(foreground);
+        }
     }
 
-    // pub fn mouse_input(&mut self, device_id: DeviceId, state: ButtonState, button: MouseButton, x: f32, y: f32) -> bool
-    // {
-    //     unsafe {
-    //         let s = self as *const Item;
-    //         let on_mouse_input = &(*s).on_mouse_input;
-    //         on_mouse_input(self, device_id, state, button, x, y)
-    //     }
-    // }
-    // 
-    // pub fn cursor_moved(&mut self, x: f32, y: f32) -> bool
-    // {
-    //     if self.get_layout_params().contains(x, y){
-    //         if !self.is_cursor_inside {
-    //             self.is_cursor_inside = true;
-    //             unsafe {
-    //                 let s = self as *const Item;
-    //                 let on_cursor_entered_event = &(*s).on_cursor_entered_event;
-    //                 on_cursor_entered_event(self);
-    //                 let on_cursor_entered = &(*s).on_cursor_entered;
-    //                 on_cursor_entered();
-    //             }
-    //         }
-    //     } else {
-    //         if self.is_cursor_inside {
-    //             self.is_cursor_inside = false;
-    //             unsafe {
-    //                 let s = self as *const Item;
-    //                 let on_cursor_exited_event = &(*s).on_cursor_exited_event;
-    //                 on_cursor_exited_event(self);
-    //                 let on_cursor_exited = &(*s).on_cursor_exited;
-    //                 on_cursor_exited();
-    //             }
-    //         }
-    //     }
-    //     unsafe {
-    //         let s = self as *const Item;
-    //         let on_cursor_moved = &(*s).on_cursor_moved;
-    //         let handled = on_cursor_moved(self, x, y);
-    //         if !handled {
-    //             for child in self.get_children_mut() {
-    //                 if child.cursor_moved(x, y) {
-    //                     return true;
-    //                 }
-    //             }
-    //         }
-    //         handled
-    //     }
-    // }
-    // 
-    // pub fn pointer_input(&mut self, action: PointerAction) -> bool
-    // {
-    //     unsafe {
-    //         let s = self as *const Item;
-    //         let on_pointer_input = &(*s).on_pointer_input;
-    //         on_pointer_input(self, action)
-    //     }
-    // }
-    // 
-    // pub fn ime_input(&mut self, action: ImeAction) -> bool {
-    //     unsafe {
-    //         let s = self as *const Item;
-    //         let on_ime_input = &(*s).on_ime_input;
-    //         let handled = on_ime_input(self, action.clone());
-    //         if !handled {
-    //             for child in self.get_children_mut() {
-    //                 if child.ime_input(action.clone()) {
-    //                     return true;
-    //                 }
-    //             }
-    //         }
-    //         handled
-    //     }
-    // }
-    // 
-    // pub fn keyboard_input(&mut self, device_id: DeviceId, event: KeyEvent, is_synthetic: bool) -> bool  {
-    //     unsafe {
-    //         let s = self as *const Item;
-    //         let on_keyboard_input = &(*s).on_keyboard_input;
-    //         let handled = on_keyboard_input(self, device_id, event.clone(), is_synthetic);
-    //         if !handled {
-    //             for child in self.get_children_mut() {
-    //                 if child.keyboard_input(device_id, event.clone(), is_synthetic) {
-    //                     return true;
-    //                 }
-    //             }
-    //         }
-    //         handled
-    //     }
-    // }
+    pub fn mouse_input(&mut self, device_id: DeviceId, state: ButtonState, button: MouseButton, x: f32, y: f32) -> bool
+    {
+        unsafe {
+            let s = self as *const Item;
+            let on_mouse_input = &(*s).on_mouse_input;
+            on_mouse_input(self, device_id, state, button, x, y)
+        }
+    }
+
+    pub fn cursor_moved(&mut self, x: f32, y: f32) -> bool
+    {
+        unsafe {
+            let s = self as *const Item;
+            let on_cursor_moved = &(*s).on_cursor_moved;
+            on_cursor_moved(self, x, y)
+        }
+    }
+
+    /// The deltas are in logical pixels, positive when the content should move right or down.
+    pub fn mouse_wheel(&mut self, delta_x: f32, delta_y: f32) -> bool
+    {
+        unsafe {
+            let s = self as *const Item;
+            let on_mouse_wheel = &(*s).on_mouse_wheel;
+            on_mouse_wheel(self, delta_x, delta_y)
+        }
+    }
+
+    pub fn pointer_input(&mut self, action: PointerAction) -> bool
+    {
+        let handled = unsafe {
+            let s = self as *const Item;
+            let on_pointer_input = &(*s).on_pointer_input;
+            on_pointer_input(self, action)
+        };
+        let gesture_handled = self.recognize_gesture(action);
+        handled || gesture_handled
+    }
+
+    fn has_gesture_listener(&self) -> bool {
+        self.on_click.is_some() || self.on_long_press.is_some() || self.on_double_click.is_some()
+    }
+
+    fn recognize_gesture(&mut self, action: PointerAction) -> bool {
+        if !self.has_gesture_listener() {
+            return false;
+        }
+        let events = self.gesture_recognizer.handle(action, &self.layout_params, Instant::now());
+        let handled = !events.is_empty() || self.gesture_recognizer.is_pressed();
+        for event in events {
+            self.invoke_gesture_event(event, action);
+        }
+        if !self.gesture_recognizer.is_pressed() {
+            self.long_press_timer.cancel();
+        }
+        handled
+    }
+
+    fn invoke_gesture_event(&mut self, event: GestureEvent, action: PointerAction) {
+        match event {
+            GestureEvent::Pressed => {
+                if let Some(pointer_type) = action.pointer_type() {
+                    // Keep receiving this pointer even if it moves out of the item, so the gesture can be cancelled.
+                    self.app.catch_pointer(pointer_type, self.get_id());
+                }
+                if self.on_long_press.is_some() {
+                    self.start_long_press_timer();
+                }
+            }
+            GestureEvent::Click => {
+                if let Some(on_click) = &self.on_click {
+                    on_click();
+                }
+            }
+            GestureEvent::DoubleClick => {
+                if let Some(on_double_click) = &self.on_double_click {
+                    on_double_click();
+                }
+            }
+            GestureEvent::LongPress => {
+                if let Some(on_long_press) = &self.on_long_press {
+                    on_long_press();
+                }
+            }
+            GestureEvent::Cancel => {
+                if let Some(pointer_type) = action.pointer_type() {
+                    self.app.release_pointer(pointer_type);
+                }
+            }
+        }
+    }
+
+    fn start_long_press_timer(&mut self) {
+        let event_loop_proxy = self.app.lock().unwrap().event_loop_proxy();
+        if let Some(event_loop_proxy) = event_loop_proxy {
+            let id = self.get_id();
+            let timeout = self.gesture_recognizer.config().long_press_timeout;
+            self.long_press_timer.cancel();
+            self.long_press_timer.start(timeout, move || {
+                let _ = event_loop_proxy.send_event(UserEvent::TimerExpired(id, LONG_PRESS_TIMER.to_string()));
+            });
+        }
+    }
+
+    /// Called on the event loop thread when a timer started by this item expires.
+    pub(crate) fn timer_expired(&mut self, name: &str) {
+        if name == LONG_PRESS_TIMER {
+            if let Some(event) = self.gesture_recognizer.check_long_press(Instant::now()) {
+                self.invoke_gesture_event(event, PointerAction::Cancel);
+                self.app.request_redraw();
+            }
+        }
+    }
+
+    /// Deliver a mouse button event to the items under (x, y), see [`Item::dispatch_at`].
+    pub fn dispatch_mouse_input(&mut self, device_id: DeviceId, state: ButtonState, button: MouseButton, x: f32, y: f32) -> bool {
+        self.dispatch_at(x, y, &mut |item| item.mouse_input(device_id, state, button, x, y))
+    }
+
+    /// Deliver a cursor movement to the items under (x, y), see [`Item::dispatch_at`].
+    pub fn dispatch_cursor_moved(&mut self, x: f32, y: f32) -> bool {
+        self.dispatch_at(x, y, &mut |item| item.cursor_moved(x, y))
+    }
+
+    /// Deliver a mouse wheel event to the items under the cursor at (x, y), see [`Item::dispatch_at`].
+    pub fn dispatch_mouse_wheel(&mut self, x: f32, y: f32, delta_x: f32, delta_y: f32) -> bool {
+        self.dispatch_at(x, y, &mut |item| item.mouse_wheel(delta_x, delta_y))
+    }
+
+    /// Deliver a pointer action to the items under the pointer, see [`Item::dispatch_at`].
+    /// [`PointerAction::Cancel`] has no position, so it is only delivered to this item.
+    pub fn dispatch_pointer_input(&mut self, action: PointerAction) -> bool {
+        match action.position() {
+            Some((x, y)) => self.dispatch_at(x, y, &mut |item| item.pointer_input(action)),
+            None => self.pointer_input(action),
+        }
+    }
+
+    /// Hit-test the item tree at (x, y).
+    /// Children are visited top-most first, i.e. in the reverse order they are drawn, see [`ItemCollection::z_ordered_mut`](crate::property::ItemCollection::z_ordered_mut),
+    /// and the event bubbles up to the ancestors until `handler` returns `true`.
+    pub fn dispatch_at(&mut self, x: f32, y: f32, handler: &mut dyn FnMut(&mut Item) -> bool) -> bool {
+        if !self.active.get() || !self.get_layout_params().contains(x, y) {
+            return false;
+        }
+        let children = self.get_children();
+        let mut children = children.lock();
+        for child in children.z_ordered_mut().into_iter().rev() {
+            if child.dispatch_at(x, y, handler) {
+                return true;
+            }
+        }
+        drop(children);
+        handler(self)
+    }
+
+    pub fn is_cursor_inside(&self) -> bool {
+        self.is_cursor_inside
+    }
+
+    /// Update the hover state of this item and all its descendants.
+    /// `position` is the logical cursor position, or `None` when the cursor left the window.
+    /// Returns the cursor icon requested by the top-most hovered item.
+    pub fn update_hover(&mut self, position: Option<(f32, f32)>) -> Option<CursorIcon> {
+        let inside = match position {
+            Some((x, y)) => self.active.get() && self.get_layout_params().contains(x, y),
+            None => false,
+        };
+
+        if inside != self.is_cursor_inside {
+            self.is_cursor_inside = inside;
+            unsafe {
+                let s = self as *const Item;
+                if inside {
+                    let on_cursor_entered_event = &(*s).on_cursor_entered_event;
+                    on_cursor_entered_event(self);
+                    let on_cursor_entered = &(*s).on_cursor_entered;
+                    on_cursor_entered();
+                } else {
+                    let on_cursor_exited_event = &(*s).on_cursor_exited_event;
+                    on_cursor_exited_event(self);
+                    let on_cursor_exited = &(*s).on_cursor_exited;
+                    on_cursor_exited();
+                }
+            }
+        }
+
+        // Children that are outside of this item still need to receive the exit events.
+        let child_position = if inside { position } else { None };
+        let mut cursor_icon = None;
+        let children = self.get_children();
+        let mut children = children.lock();
+        for child in children.z_ordered_mut().into_iter().rev() {
+            let child_cursor_icon = child.update_hover(child_position);
+            if cursor_icon.is_none() {
+                cursor_icon = child_cursor_icon;
+            }
+        }
+        drop(children);
+
+        if inside && cursor_icon.is_none() {
+            cursor_icon = self.cursor_icon.get();
+        }
+        cursor_icon
+    }
+
+    /// Find the item with the given id in this subtree and run `f` on it.
+    pub fn find_item_mut<R>(&mut self, id: usize, f: impl FnOnce(&mut Item) -> R) -> Option<R> {
+        let mut f = Some(f);
+        let mut result = None;
+        self.visit_item_mut(id, &mut |item| {
+            if let Some(f) = f.take() {
+                result = Some(f(item));
+            }
+        });
+        result
+    }
+
+    fn visit_item_mut(&mut self, id: usize, visitor: &mut dyn FnMut(&mut Item)) -> bool {
+        if self.get_id() == id {
+            visitor(self);
+            return true;
+        }
+        let children = self.get_children();
+        let mut children = children.lock();
+        for child in children.iter_mut() {
+            if child.visit_item_mut(id, visitor) {
+                return true;
+            }
+        }
+        false
+    }
+
+    pub fn ime_input(&mut self, action: ImeAction) -> bool {
+        unsafe {
+            let s = self as *const Item;
+            let on_ime_input = &(*s).on_ime_input;
+            on_ime_input(self, action)
+        }
+    }
+
+    pub fn keyboard_input(&mut self, device_id: DeviceId, event: KeyEvent, is_synthetic: bool) -> bool  {
+        unsafe {
+            let s = self as *const Item;
+            let on_keyboard_input = &(*s).on_keyboard_input;
+            on_keyboard_input(self, device_id, event, is_synthetic)
+        }
+    }
+
+    /// Run `handler` on the item with the given id, then on its ancestors until one returns `true`.
+    /// Returns `None` if the item is not in this subtree.
+    pub fn bubble_from(&mut self, id: usize, handler: &mut dyn FnMut(&mut Item) -> bool) -> Option<bool> {
+        if self.get_id() == id {
+            return Some(handler(self));
+        }
+        let children = self.get_children();
+        let mut children = children.lock();
+        for child in children.iter_mut() {
+            if let Some(handled) = child.bubble_from(id, handler) {
+                drop(children);
+                return Some(handled || handler(self));
+            }
+        }
+        None
+    }
+
+    /// Collect the ids of the active, focusable items of this subtree in tree order.
+    pub fn collect_focusable(&self, ids: &mut Vec<usize>) {
+        if !self.active.get() {
+            return;
+        }
+        if self.focusable.get() {
+            ids.push(self.get_id());
+        }
+        self.get_children().lock().iter().for_each(|child| {
+            child.collect_focusable(ids);
+        });
+    }
 
     pub fn get_layout_params(&self) -> &LayoutParams {
         &self.layout_params
@@ -331,11 +738,51 @@ impl Item {
         self.on_click.as_ref()
     }
 
+    pub fn on_long_press(mut self, on_long_press: impl Fn() + 'static) -> Self {
+        self.on_long_press = Some(Box::new(on_long_press));
+        self
+    }
+
+    pub fn get_on_long_press(&self) -> Option<&Box<dyn Fn()>> {
+        self.on_long_press.as_ref()
+    }
+
+    pub fn on_double_click(mut self, on_double_click: impl Fn() + 'static) -> Self {
+        self.on_double_click = Some(Box::new(on_double_click));
+        self
+    }
+
+    pub fn get_on_double_click(&self) -> Option<&Box<dyn Fn()>> {
+        self.on_double_click.as_ref()
+    }
+
+    pub fn gesture_config(mut self, gesture_config: GestureConfig) -> Self {
+        self.gesture_recognizer.set_config(gesture_config);
+        self
+    }
+
+    pub fn get_gesture_config(&self) -> GestureConfig {
+        self.gesture_recognizer.config()
+    }
+
     pub fn on_blur(mut self, on_blur: impl Fn() + 'static) -> Self {
         self.on_blur = Some(Box::new(on_blur));
         self
     }
 
+    /// Update the `focused` property and invoke `on_focus` or `on_blur` if the state changed.
+    pub(crate) fn set_focus_state(&mut self, focused: bool) {
+        if self.focused.get() == focused {
+            return;
+        }
+        self.focused.set_value(focused);
+        if focused {
+            self.invoke_on_focus();
+        } else {
+            self.invoke_on_blur();
+        }
+    }
+
     pub fn invoke_on_blur(&mut self) {
         if let Some(on_blur) = &self.on_blur {
             on_blur();
@@ -368,21 +815,19 @@ impl Item {
         self.horizontal_gravity = horizontal_gravity;
         self.vertical_gravity = vertical_gravity;
         {
-            let app = self.app.clone();
-            self.horizontal_gravity.add_observer(
-                Observer::new_without_id(move||{
-                    app.lock().unwrap().request_layout();
-                })
-            );
+            let layout_node = self.layout_node.clone();
+            let horizontal_gravity = self.horizontal_gravity.clone();
+            self.subscribe(&horizontal_gravity, move||{
+                layout_node.invalidate();
+            });
         }
 
         {
-            let app = self.app.clone();
-            self.vertical_gravity.add_observer(
-                Observer::new_without_id(move||{
-                    app.lock().unwrap().request_layout();
-                })
-            );
+            let layout_node = self.layout_node.clone();
+            let vertical_gravity = self.vertical_gravity.clone();
+            self.subscribe(&vertical_gravity, move||{
+                layout_node.invalidate();
+            });
         }
 
         self
@@ -392,17 +837,15 @@ impl Item {
         self.focused = focused.into();
         let app = self.app.clone();
         let id = self.get_id();
+        let focused = self.focused.clone();
         let focused_clone = self.focused.clone();
-        self.focused.add_observer(
-            Observer::new_without_id(move||{
-                if focused_clone.get() {
-                    app.lock().unwrap().request_focus(id)
-                } else {
-                    app.lock().unwrap().request_focus(0)
-                }
-                app.lock().unwrap().request_layout();
-            })
-        );
+        self.subscribe(&focused, move||{
+            if focused_clone.get() {
+                app.lock().unwrap().request_focus(id)
+            } else {
+                app.lock().unwrap().request_focus(0)
+            }
+        });
         self
     }
 
@@ -1,6 +1,6 @@
 use skia_safe::Canvas;
 use winit::event::{DeviceId, KeyEvent, MouseButton};
-use crate::ui::{ButtonState, ImeAction, Item, MeasureMode, PointerAction, PointerType};
+use crate::ui::{ButtonState, ImeAction, IntrinsicSize, Item, measure_intrinsic_size, MeasureMode, PointerAction, PointerType};
 use crate::property::Gettable;
 
 
@@ -13,10 +13,14 @@ pub struct ItemEvent {
     pub measure_event: Box<dyn Fn(&mut Item, MeasureMode, MeasureMode)>,
     /// item, x, y
     pub layout_event: Box<dyn Fn(&mut Item, f32, f32)>,
+    /// item, intrinsic_size
+    pub intrinsic_size_event: Box<dyn Fn(&mut Item, IntrinsicSize) -> f32>,
     /// item, device_id, state, button, x, y
     pub on_mouse_input: Box<dyn Fn(&mut Item, DeviceId, ButtonState, MouseButton, f32, f32) -> bool>,
     /// item, x, y
     pub on_cursor_moved: Box<dyn Fn(&mut Item, f32, f32) -> bool>,
+    /// item, delta_x, delta_y
+    pub on_mouse_wheel: Box<dyn Fn(&mut Item, f32, f32) -> bool>,
     pub on_cursor_entered: Box<dyn Fn(&mut Item)>,
     pub on_cursor_exited: Box<dyn Fn(&mut Item)>,
     /// item, pointer_action
@@ -50,6 +54,12 @@ impl ItemEvent {
         self
     }
 
+    /// item, intrinsic_size
+    pub fn set_intrinsic_size_event(mut self, intrinsic_size_event: impl Fn(&mut Item, IntrinsicSize) -> f32 + 'static) -> Self {
+        self.intrinsic_size_event = Box::new(intrinsic_size_event);
+        self
+    }
+
     /// item, device_id, state, button, x, y
     pub fn set_on_mouse_input(mut self, on_mouse_input: impl Fn(&mut Item, DeviceId, ButtonState, MouseButton, f32, f32) -> bool + 'static) -> Self {
         self.on_mouse_input = Box::new(on_mouse_input);
@@ -62,6 +72,12 @@ impl ItemEvent {
         self
     }
 
+    /// item, delta_x, delta_y
+    pub fn set_on_mouse_wheel(mut self, on_mouse_wheel: impl Fn(&mut Item, f32, f32) -> bool + 'static) -> Self {
+        self.on_mouse_wheel = Box::new(on_mouse_wheel);
+        self
+    }
+
     pub fn set_on_cursor_entered(mut self, on_cursor_entered: impl Fn(&mut Item) + 'static) -> Self {
         self.on_cursor_entered = Box::new(on_cursor_entered);
         self
@@ -105,10 +121,10 @@ impl Default for ItemEvent {
                 }
 
                 item.on_draw(canvas);
-                item.get_children().lock().iter_mut().for_each(|child| {
+                item.get_children().lock().z_ordered_mut().into_iter().for_each(|child| {
                     let child_layout_params = child.get_layout_params_mut();
                     child_layout_params.parent_x = layout_params.x();
-                    child_layout_params.parent_y = layout_params.x();
+                    child_layout_params.parent_y = layout_params.y();
                     child.draw(canvas);
                 });
 
@@ -122,12 +138,16 @@ impl Default for ItemEvent {
             on_draw: Box::new(|_, _| {}),
             measure_event: Box::new(|_, _, _| {}),
             layout_event: Box::new(|_, _, _| {}),
+            intrinsic_size_event: Box::new(measure_intrinsic_size),
             on_mouse_input: Box::new(|_, _, _, _, _, _| {
                 false
             }),
             on_cursor_moved: Box::new(|_, _, _| {
                 false
             }),
+            on_mouse_wheel: Box::new(|_, _, _| {
+                false
+            }),
             on_cursor_entered: Box::new(|_| {}),
             on_cursor_exited: Box::new(|_| {}),
             on_pointer_input: Box::new(|_, _| {
@@ -0,0 +1,136 @@
+use std::sync::{Arc, Mutex, Weak};
+
+use crate::app::SharedApp;
+
+struct LayoutNodeState {
+    /// The measure event has to run again, the cached size is stale.
+    measure_dirty: bool,
+    /// The layout event has to run again, the cached positions of the children are stale.
+    layout_dirty: bool,
+    /// Some item below a relayout boundary in this subtree has to be measured or laid out again.
+    descendant_dirty: bool,
+    /// The measure event is running, invalidations raised by the item itself are already covered.
+    measuring: bool,
+    /// The size of the item does not depend on its content, see [`LayoutNode::is_relayout_boundary`].
+    relayout_boundary: bool,
+    parent: Weak<Mutex<LayoutNodeState>>,
+}
+
+/// The incremental layout state of an [`Item`](crate::ui::Item).
+///
+/// Every item owns one node. The node is shared with the observers of the item's properties, so that a changed
+/// property only invalidates the item and its ancestors instead of the whole tree. Items that were not invalidated
+/// reuse the size of their last measure pass as long as they are measured with the same measure modes.
+#[derive(Clone)]
+pub struct LayoutNode {
+    app: SharedApp,
+    state: Arc<Mutex<LayoutNodeState>>,
+}
+
+impl LayoutNode {
+    pub(crate) fn new(app: SharedApp) -> Self {
+        Self {
+            app,
+            state: Arc::new(Mutex::new(LayoutNodeState {
+                measure_dirty: true,
+                layout_dirty: true,
+                descendant_dirty: false,
+                measuring: false,
+                relayout_boundary: false,
+                parent: Weak::new(),
+            })),
+        }
+    }
+
+    /// Mark the item and its ancestors as dirty and schedule a layout pass.
+    ///
+    /// Ancestors above a relayout boundary are not measured again, only the boundary's subtree is.
+    pub fn invalidate(&self) {
+        let parent = {
+            let mut state = self.state.lock().unwrap();
+            state.measure_dirty = true;
+            state.layout_dirty = true;
+            if state.measuring {
+                None
+            } else {
+                state.parent.upgrade()
+            }
+        };
+        if let Some(parent) = parent {
+            invalidate_from_child(parent);
+        }
+        self.app.lock().unwrap().request_incremental_layout();
+    }
+
+    /// Whether the size of the item is fixed, so changes inside of it can not affect its ancestors.
+    pub fn is_relayout_boundary(&self) -> bool {
+        self.state.lock().unwrap().relayout_boundary
+    }
+
+    pub(crate) fn set_parent(&self, parent: &LayoutNode) {
+        self.state.lock().unwrap().parent = Arc::downgrade(&parent.state);
+    }
+
+    pub(crate) fn is_measure_dirty(&self) -> bool {
+        self.state.lock().unwrap().measure_dirty
+    }
+
+    pub(crate) fn is_layout_dirty(&self) -> bool {
+        self.state.lock().unwrap().layout_dirty
+    }
+
+    pub(crate) fn is_descendant_dirty(&self) -> bool {
+        self.state.lock().unwrap().descendant_dirty
+    }
+
+    /// Mark the item as dirty without its ancestors, for a measure result that is restored while it was dirty.
+    pub(crate) fn mark_measure_dirty(&self) {
+        let mut state = self.state.lock().unwrap();
+        state.measure_dirty = true;
+        state.layout_dirty = true;
+    }
+
+    pub(crate) fn begin_measure(&self) {
+        self.state.lock().unwrap().measuring = true;
+    }
+
+    pub(crate) fn end_measure(&self, relayout_boundary: bool) {
+        let mut state = self.state.lock().unwrap();
+        state.measuring = false;
+        state.measure_dirty = false;
+        state.layout_dirty = true;
+        state.relayout_boundary = relayout_boundary;
+    }
+
+    pub(crate) fn layout_done(&self) {
+        let mut state = self.state.lock().unwrap();
+        state.layout_dirty = false;
+        state.descendant_dirty = false;
+    }
+}
+
+fn invalidate_from_child(node: Arc<Mutex<LayoutNodeState>>) {
+    let mut state = node.lock().unwrap();
+    if state.measuring {
+        return;
+    }
+    if state.relayout_boundary {
+        state.measure_dirty = true;
+        state.layout_dirty = true;
+        let mut parent = state.parent.upgrade();
+        drop(state);
+        while let Some(node) = parent {
+            let mut state = node.lock().unwrap();
+            state.descendant_dirty = true;
+            parent = state.parent.upgrade();
+        }
+    } else {
+        state.measure_dirty = true;
+        state.layout_dirty = true;
+        let parent = state.parent.upgrade();
+        drop(state);
+        if let Some(parent) = parent {
+            invalidate_from_child(parent);
+        }
+    }
+}
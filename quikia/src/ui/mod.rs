@@ -3,11 +3,14 @@ use std::collections::{HashMap, LinkedList};
 
 use skia_safe::Color;
 use winit::dpi::LogicalPosition;
-use winit::event::{ElementState, Force, MouseButton, TouchPhase};
+use winit::event::{ElementState, Force, Ime, MouseButton, MouseScrollDelta, TouchPhase};
 
 pub use item::*;
 pub use item_event::*;
 pub use logical_x::*;
+pub use gesture::*;
+pub use scroller::*;
+pub use layout_node::*;
 
 use crate::property::{Gettable, SharedProperty, Size};
 
@@ -15,47 +18,139 @@ mod item;
 // mod rectangle;
 mod logical_x;
 mod item_event;
-// mod text_block;
-// mod image;
+mod gesture;
+mod scroller;
+mod layout_node;
+mod text_block;
+mod image;
 // mod ripple;
 pub mod additional_property;
 mod layout_params;
 pub use layout_params::*;
 
 // pub use rectangle::*;
-// pub use text_block::*;
-// pub use image::*;
+pub use text_block::*;
+pub use image::*;
 // pub use ripple::*;
 
+/// The default intrinsic size of an item: measure it with unspecified measure modes and read the result.
+///
+/// The queried axis is unbounded, so [`Size::Fill`] descendants take the size of their content along it.
+/// The layout params and measure caches of the item and its descendants are restored afterwards,
+/// so the query does not change the committed layout.
+pub fn measure_intrinsic_size(item: &mut Item, intrinsic_size: IntrinsicSize) -> f32 {
+    let (width_measure_mode, height_measure_mode) = match intrinsic_size {
+        IntrinsicSize::MinWidth(height) | IntrinsicSize::MaxWidth(height) => (MeasureMode::Unspecified(f32::INFINITY), MeasureMode::Unspecified(height)),
+        IntrinsicSize::MinHeight(width) | IntrinsicSize::MaxHeight(width) => (MeasureMode::Unspecified(width), MeasureMode::Unspecified(f32::INFINITY)),
+    };
+    let mut states = HashMap::new();
+    item.save_measure_state(&mut states);
+    item.measure(width_measure_mode, height_measure_mode);
+    let measured = item.get_layout_params().clone();
+    item.restore_measure_state(&mut states);
+    match intrinsic_size {
+        IntrinsicSize::MinWidth(_) | IntrinsicSize::MaxWidth(_) => measured.width,
+        IntrinsicSize::MinHeight(_) | IntrinsicSize::MaxHeight(_) => measured.height,
+    }
+}
+
+/// Find the measure modes of a child from the measure modes of its parent.
+///
+/// The space available to the child is the parent's size minus the parent's padding and the child's margins.
+/// [`Size::Default`] lets the child pick its size within that space, [`Size::Fill`] takes all of it,
+/// [`Size::Relative`] takes a fraction of it and [`Size::Fixed`] ignores it.
+/// If the space is unbounded, e.g. along the axis of a [`Scroll`](crate::layout::Scroll), `Fill` and `Relative` pick their size like `Default`.
+/// When the child has an aspect ratio (width / height) and only one of its sizes is specified, the other one follows from it.
+/// If neither is specified, the child gets the largest size with that ratio which fits the available space.
+/// The results are clamped to the min and max sizes of the child.
 pub fn measure_child(child: &Item, parent_layout_params: &LayoutParams, width_measure_mode: MeasureMode, height_measure_mode: MeasureMode) -> (MeasureMode, MeasureMode) {
     let layout_params = child.get_layout_params();
     let max_width = match width_measure_mode {
         MeasureMode::Specified(width) => width,
         MeasureMode::Unspecified(width) => width,
-    } - layout_params.margin_start - layout_params.margin_end - parent_layout_params.padding_start - parent_layout_params.margin_end;
+    } - layout_params.margin_start - layout_params.margin_end - parent_layout_params.padding_start - parent_layout_params.padding_end;
     let max_height = match height_measure_mode {
         MeasureMode::Specified(height) => height,
         MeasureMode::Unspecified(height) => height,
-    } - layout_params.margin_top - layout_params.margin_bottom - parent_layout_params.padding_top - parent_layout_params.margin_bottom;
+    } - layout_params.margin_top - layout_params.margin_bottom - parent_layout_params.padding_top - parent_layout_params.padding_bottom;
 
     let child_width = child.get_width().get();
     let child_height = child.get_height().get();
 
     let child_width_measure_mode = match child_width {
         Size::Default => MeasureMode::Unspecified(max_width),
+        Size::Fill if max_width.is_infinite() => MeasureMode::Unspecified(max_width),
         Size::Fill => MeasureMode::Specified(max_width),
         Size::Fixed(width) => MeasureMode::Specified(width),
+        Size::Relative(_) if max_width.is_infinite() => MeasureMode::Unspecified(max_width),
         Size::Relative(scale) => MeasureMode::Specified(max_width * scale),
     };
 
     let child_height_measure_mode = match child_height {
         Size::Default => MeasureMode::Unspecified(max_height),
+        Size::Fill if max_height.is_infinite() => MeasureMode::Unspecified(max_height),
         Size::Fill => MeasureMode::Specified(max_height),
         Size::Fixed(height) => MeasureMode::Specified(height),
+        Size::Relative(_) if max_height.is_infinite() => MeasureMode::Unspecified(max_height),
         Size::Relative(percent) => MeasureMode::Specified(max_height * percent),
     };
 
-    (child_width_measure_mode, child_height_measure_mode)
+    let width_range = (child.get_min_width().get(), child.get_max_width().get());
+    let height_range = (child.get_min_height().get(), child.get_max_height().get());
+    let child_width_measure_mode = clamp_measure_mode(child_width_measure_mode, width_range);
+    let child_height_measure_mode = clamp_measure_mode(child_height_measure_mode, height_range);
+
+    let aspect_ratio = match child.get_aspect_ratio().get() {
+        Some(aspect_ratio) if aspect_ratio > 0.0 => aspect_ratio,
+        _ => return (child_width_measure_mode, child_height_measure_mode),
+    };
+
+    match (child_width_measure_mode, child_height_measure_mode) {
+        (MeasureMode::Specified(width), MeasureMode::Unspecified(_)) => {
+            (child_width_measure_mode, clamp_measure_mode(MeasureMode::Specified(width / aspect_ratio), height_range))
+        }
+        (MeasureMode::Unspecified(_), MeasureMode::Specified(height)) => {
+            (clamp_measure_mode(MeasureMode::Specified(height * aspect_ratio), width_range), child_height_measure_mode)
+        }
+        (MeasureMode::Unspecified(width), MeasureMode::Unspecified(height)) => {
+            let width = width.min(height * aspect_ratio);
+            // Without a bound on either axis there is no largest size.
+            if width < f32::MAX {
+                (clamp_measure_mode(MeasureMode::Specified(width), width_range), clamp_measure_mode(MeasureMode::Specified(width / aspect_ratio), height_range))
+            } else {
+                (child_width_measure_mode, child_height_measure_mode)
+            }
+        }
+        (MeasureMode::Specified(_), MeasureMode::Specified(_)) => (child_width_measure_mode, child_height_measure_mode),
+    }
+}
+
+/// Keep the value of a measure mode within (min, max), see [`clamp_size`].
+fn clamp_measure_mode(measure_mode: MeasureMode, (min, max): (f32, f32)) -> MeasureMode {
+    match measure_mode {
+        MeasureMode::Specified(value) => MeasureMode::Specified(clamp_size(value, min, max)),
+        MeasureMode::Unspecified(value) => MeasureMode::Unspecified(clamp_size(value, min, max)),
+    }
+}
+
+/// Keep a size within (min, max). Unlike [`f32::clamp`] it does not panic: the min wins if they contradict each
+/// other, and a NaN bound is ignored.
+pub fn clamp_size(value: f32, min: f32, max: f32) -> f32 {
+    value.min(max).max(min)
+}
+
+/// The distance scrolled by one line of a mouse wheel, in logical pixels.
+pub const WHEEL_LINE_DISTANCE: f32 = 40.0;
+
+/// Convert a mouse wheel delta into logical pixels, as expected by [`Item::mouse_wheel`].
+pub fn mouse_wheel_delta(delta: MouseScrollDelta, scale_factor: f32) -> (f32, f32) {
+    match delta {
+        MouseScrollDelta::LineDelta(x, y) => (x * WHEEL_LINE_DISTANCE, y * WHEEL_LINE_DISTANCE),
+        MouseScrollDelta::PixelDelta(position) => {
+            let position = position.to_logical::<f32>(scale_factor as f64);
+            (position.x, position.y)
+        }
+    }
 }
 
 #[derive(Clone, Copy, Debug, PartialEq)]
@@ -65,7 +160,7 @@ pub enum Gravity {
     End,
 }
 
-#[derive(Clone, Copy, Debug)]
+#[derive(Clone, Copy, Debug, PartialEq)]
 pub enum MeasureMode {
     /// Indicates that the parent has determined an exact size for the child.
     Specified(f32),
@@ -73,6 +168,37 @@ pub enum MeasureMode {
     Unspecified(f32),
 }
 
+/// A size an item would like to have along one axis, queried without committing a layout, see [`Item::intrinsic_size`].
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum IntrinsicSize {
+    /// The narrowest width the content fits in without overflowing, for the given height.
+    MinWidth(f32),
+    /// The width the content takes when nothing has to wrap, for the given height.
+    MaxWidth(f32),
+    /// The lowest height the content fits in, for the given width.
+    MinHeight(f32),
+    /// The height the content takes when it is not constrained, for the given width.
+    MaxHeight(f32),
+}
+
+impl MeasureMode {
+    /// The size carried by the mode, whether it is the exact size or the maximum size.
+    pub fn value(&self) -> f32 {
+        match self {
+            MeasureMode::Specified(value) => *value,
+            MeasureMode::Unspecified(value) => *value,
+        }
+    }
+
+    /// The same kind of mode with another size.
+    pub fn with_value(&self, value: f32) -> Self {
+        match self {
+            MeasureMode::Specified(_) => MeasureMode::Specified(value),
+            MeasureMode::Unspecified(_) => MeasureMode::Unspecified(value),
+        }
+    }
+}
+
 
 #[derive(Clone, Copy, Debug, PartialEq)]
 pub enum LayoutDirection {
@@ -107,9 +233,10 @@ pub enum ImeAction {
     Disabled,
 }
 
-#[derive(Clone, Copy, Debug)]
+#[derive(Clone, Copy, Debug, PartialEq)]
 pub enum PointerType {
-    Cursor { mouse_button: MouseButton },
+    /// `mouse_button` is `None` for a cursor that moves without a pressed button.
+    Cursor { mouse_button: Option<MouseButton> },
     Touch { id: u64 },
 }
 
@@ -123,7 +250,27 @@ pub enum PointerAction {
 
 
 impl PointerAction {
+    /// The logical position of the pointer, or `None` for [`PointerAction::Cancel`].
+    pub fn position(&self) -> Option<(f32, f32)> {
+        match self {
+            PointerAction::Down { x, y, .. } => Some((*x, *y)),
+            PointerAction::Up { x, y, .. } => Some((*x, *y)),
+            PointerAction::Move { x, y, .. } => Some((*x, *y)),
+            PointerAction::Cancel => None,
+        }
+    }
+
+    pub fn pointer_type(&self) -> Option<PointerType> {
+        match self {
+            PointerAction::Down { pointer_type, .. } => Some(*pointer_type),
+            PointerAction::Up { pointer_type, .. } => Some(*pointer_type),
+            PointerAction::Move { pointer_type, .. } => Some(*pointer_type),
+            PointerAction::Cancel => None,
+        }
+    }
+
     pub fn from_mouse(state: ButtonState, button: MouseButton, x: f32, y: f32) -> Self {
+        let button = Some(button);
         match state {
             ButtonState::Pressed => PointerAction::Down {
                 x,
@@ -143,6 +290,16 @@ impl PointerAction {
         }
     }
 
+    /// A cursor movement while `pressed_buttons` are held, in the order they were pressed.
+    /// The movement belongs to the first of them, or to no button if none is held.
+    pub fn from_cursor_moved(pressed_buttons: &[MouseButton], x: f32, y: f32) -> Self {
+        PointerAction::Move {
+            x,
+            y,
+            pointer_type: PointerType::Cursor { mouse_button: pressed_buttons.first().copied() },
+        }
+    }
+
     pub fn from_touch(phase: TouchPhase, location: LogicalPosition<f32>, force: Option<Force>, id: u64) -> Self {
         match phase {
             TouchPhase::Started => PointerAction::Down {
@@ -210,12 +367,11 @@ macro_rules! impl_item_property {
         impl $struct_name{
             pub fn $property_name(mut self, $property_name: impl Into<$t>) -> Self{
                 self.$property_name=$property_name.into();
-                let app = self.get_app();
-                self.width.add_observer(
-                    $crate::property::Observer::new_without_id(move ||{
-                        app.request_layout();
-                    })
-                );
+                let layout_node = self.get_layout_node();
+                let property = self.$property_name.clone();
+                self.subscribe(&property, move ||{
+                    layout_node.invalidate();
+                });
                 self
             }
 
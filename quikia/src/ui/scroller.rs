@@ -0,0 +1,270 @@
+use std::time::{Duration, Instant};
+
+use crate::ui::PointerType;
+
+/// Tuning of [`Scroller`]. Distances are logical pixels and velocities logical pixels per second.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ScrollConfig {
+    /// How fast a fling slows down, the velocity is multiplied by `exp(-friction * seconds)`.
+    pub friction: f32,
+    /// How fast a fling slows down once it went past the bounds.
+    pub overscroll_friction: f32,
+    /// How fast an overscroll springs back, the overscroll is multiplied by `exp(-spring * seconds)`.
+    pub spring: f32,
+    /// The part of a drag that is applied while the content is pulled past the bounds.
+    pub overscroll_resistance: f32,
+    /// The furthest the content can be pulled past the bounds.
+    pub max_overscroll: f32,
+    /// Flings slower than this stop immediately.
+    pub min_fling_velocity: f32,
+    pub max_fling_velocity: f32,
+    /// How long the scrollbar stays visible after the last scroll.
+    pub scrollbar_fade_delay: Duration,
+    pub scrollbar_fade_duration: Duration,
+}
+
+impl Default for ScrollConfig {
+    fn default() -> Self {
+        Self {
+            friction: 2.0,
+            overscroll_friction: 20.0,
+            spring: 12.0,
+            overscroll_resistance: 0.5,
+            max_overscroll: 120.0,
+            min_fling_velocity: 50.0,
+            max_fling_velocity: 8000.0,
+            scrollbar_fade_delay: Duration::from_millis(1000),
+            scrollbar_fade_duration: Duration::from_millis(300),
+        }
+    }
+}
+
+/// Only the samples of the last moments of a drag are used to compute the fling velocity.
+const VELOCITY_WINDOW: Duration = Duration::from_millis(100);
+
+/// An overscroll smaller than this snaps back to the bound.
+const SNAP_DISTANCE: f32 = 0.5;
+
+#[derive(Clone, Debug)]
+struct Drag {
+    pointer_type: PointerType,
+    last_position: f32,
+    samples: Vec<(Instant, f32)>,
+}
+
+/// Keeps the scroll offset along one axis, and animates flings and overscroll bounces.
+///
+/// The offset is 0 when the start of the content is visible and [`Scroller::max_offset`] when its end is.
+/// It is outside of this range while the content is overscrolled.
+pub struct Scroller {
+    config: ScrollConfig,
+    offset: f32,
+    max_offset: f32,
+    velocity: f32,
+    drag: Option<Drag>,
+    last_frame: Option<Instant>,
+    last_activity: Option<Instant>,
+}
+
+impl Scroller {
+    pub fn new(config: ScrollConfig) -> Self {
+        Self {
+            config,
+            offset: 0.0,
+            max_offset: 0.0,
+            velocity: 0.0,
+            drag: None,
+            last_frame: None,
+            last_activity: None,
+        }
+    }
+
+    pub fn config(&self) -> ScrollConfig {
+        self.config
+    }
+
+    pub fn set_config(&mut self, config: ScrollConfig) {
+        self.config = config;
+    }
+
+    pub fn offset(&self) -> f32 {
+        self.offset
+    }
+
+    pub fn max_offset(&self) -> f32 {
+        self.max_offset
+    }
+
+    pub fn velocity(&self) -> f32 {
+        self.velocity
+    }
+
+    /// How far the content is pulled past the bounds, negative before the start and positive after the end.
+    pub fn overscroll(&self) -> f32 {
+        if self.offset < 0.0 {
+            self.offset
+        } else if self.offset > self.max_offset {
+            self.offset - self.max_offset
+        } else {
+            0.0
+        }
+    }
+
+    /// Set the scrollable distance, usually the content size minus the viewport size.
+    /// If the offset is now past the end, it springs back.
+    pub fn set_max_offset(&mut self, max_offset: f32) {
+        self.max_offset = max_offset.max(0.0);
+    }
+
+    /// Jump to an offset, clamped to the bounds, and stop any fling.
+    pub fn scroll_to(&mut self, offset: f32, now: Instant) {
+        self.velocity = 0.0;
+        self.offset = offset.clamp(0.0, self.max_offset);
+        self.last_activity = Some(now);
+    }
+
+    /// Scroll by a distance without overscrolling, as a mouse wheel does. Returns whether the offset changed.
+    pub fn scroll_by(&mut self, delta: f32, now: Instant) -> bool {
+        let old_offset = self.offset;
+        self.scroll_to(self.offset + delta, now);
+        self.offset != old_offset
+    }
+
+    pub fn is_dragging(&self) -> bool {
+        self.drag.is_some()
+    }
+
+    /// Whether a fling or a bounce is running, [`Scroller::advance`] needs to be called on each frame.
+    pub fn is_animating(&self) -> bool {
+        self.drag.is_none() && (self.velocity != 0.0 || self.overscroll() != 0.0)
+    }
+
+    /// Start dragging at `position`, a coordinate along the scroll axis. A running fling stops.
+    pub fn drag_start(&mut self, pointer_type: PointerType, position: f32, now: Instant) {
+        self.velocity = 0.0;
+        self.drag = Some(Drag {
+            pointer_type,
+            last_position: position,
+            samples: vec![(now, position)],
+        });
+        self.last_activity = Some(now);
+    }
+
+    /// Move the content with the pointer. Returns `false` if `pointer_type` is not the dragging pointer.
+    pub fn drag_update(&mut self, pointer_type: PointerType, position: f32, now: Instant) -> bool {
+        let config = self.config;
+        let Some(drag) = self.drag.as_mut() else { return false; };
+        if drag.pointer_type != pointer_type {
+            return false;
+        }
+        let delta = drag.last_position - position;
+        drag.last_position = position;
+        drag.samples.push((now, position));
+        drag.samples.retain(|(time, _)| now.duration_since(*time) <= VELOCITY_WINDOW);
+
+        // Map the offset back to where it would be without resistance, move it, and resist the part beyond the bounds again.
+        let bound = self.offset.clamp(0.0, self.max_offset);
+        let raw_offset = bound + (self.offset - bound) / config.overscroll_resistance + delta;
+        let bound = raw_offset.clamp(0.0, self.max_offset);
+        let overscroll = ((raw_offset - bound) * config.overscroll_resistance).clamp(-config.max_overscroll, config.max_overscroll);
+        let offset = bound + overscroll;
+        self.offset = offset;
+        self.last_activity = Some(now);
+        true
+    }
+
+    /// Release the drag and start a fling with the velocity of the last moments of the drag.
+    pub fn drag_end(&mut self, pointer_type: PointerType, now: Instant) -> bool {
+        let Some(drag) = self.drag.as_ref() else { return false; };
+        if drag.pointer_type != pointer_type {
+            return false;
+        }
+        let drag = self.drag.take().unwrap();
+        let samples: Vec<_> = drag.samples.iter().filter(|(time, _)| now.duration_since(*time) <= VELOCITY_WINDOW).collect();
+        let velocity = match (samples.first(), samples.last()) {
+            (Some((first_time, first_position)), Some((last_time, last_position))) => {
+                let duration = last_time.duration_since(*first_time).as_secs_f32();
+                if duration > 0.0 {
+                    (first_position - last_position) / duration
+                } else {
+                    0.0
+                }
+            }
+            _ => 0.0,
+        };
+        self.velocity = if velocity.abs() < self.config.min_fling_velocity {
+            0.0
+        } else {
+            velocity.clamp(-self.config.max_fling_velocity, self.config.max_fling_velocity)
+        };
+        self.last_frame = Some(now);
+        self.last_activity = Some(now);
+        true
+    }
+
+    /// Stop dragging without a fling, the content springs back if it is overscrolled.
+    pub fn drag_cancel(&mut self, now: Instant) {
+        self.drag = None;
+        self.velocity = 0.0;
+        self.last_frame = Some(now);
+    }
+
+    /// Advance the fling and the bounce to `now`. Returns whether the animation is still running.
+    pub fn advance(&mut self, now: Instant) -> bool {
+        if !self.is_animating() {
+            self.last_frame = None;
+            return false;
+        }
+        let last_frame = self.last_frame.unwrap_or(now);
+        let seconds = now.saturating_duration_since(last_frame).as_secs_f32();
+        self.last_frame = Some(now);
+        self.last_activity = Some(now);
+
+        if self.velocity != 0.0 {
+            self.offset += self.velocity * seconds;
+            let friction = if self.overscroll() != 0.0 {
+                self.config.overscroll_friction
+            } else {
+                self.config.friction
+            };
+            self.velocity *= (-friction * seconds).exp();
+            if self.velocity.abs() < self.config.min_fling_velocity {
+                self.velocity = 0.0;
+            }
+            let max_overscroll = self.config.max_overscroll;
+            if self.overscroll().abs() > max_overscroll {
+                self.offset = self.offset.clamp(-max_overscroll, self.max_offset + max_overscroll);
+                self.velocity = 0.0;
+            }
+        } else {
+            let bound = self.offset.clamp(0.0, self.max_offset);
+            let overscroll = (self.offset - bound) * (-self.config.spring * seconds).exp();
+            self.offset = if overscroll.abs() < SNAP_DISTANCE {
+                bound
+            } else {
+                bound + overscroll
+            };
+        }
+        self.is_animating()
+    }
+
+    /// The opacity of the scrollbar, from 1 while scrolling to 0 once it faded out.
+    pub fn scrollbar_alpha(&self, now: Instant) -> f32 {
+        if self.is_dragging() || self.is_animating() {
+            return 1.0;
+        }
+        let Some(last_activity) = self.last_activity else { return 0.0; };
+        let elapsed = now.saturating_duration_since(last_activity);
+        if elapsed <= self.config.scrollbar_fade_delay {
+            return 1.0;
+        }
+        let fade = (elapsed - self.config.scrollbar_fade_delay).as_secs_f32() / self.config.scrollbar_fade_duration.as_secs_f32();
+        (1.0 - fade).clamp(0.0, 1.0)
+    }
+}
+
+impl Default for Scroller {
+    fn default() -> Self {
+        Self::new(ScrollConfig::default())
+    }
+}
@@ -5,10 +5,11 @@ use skia_safe::{Color, Paint, Point, Rect};
 use skia_safe::textlayout::TextAlign;
 use winit::dpi::{LogicalPosition, LogicalSize};
 use winit::keyboard::{Key, NamedKey};
+use winit::window::CursorIcon;
 use crate::app::{SharedApp, ThemeColor};
-use crate::ui::{Gravity, ImeAction, Item, ItemEvent, LayoutDirection, MeasureMode, PointerAction};
+use crate::ui::{Gravity, ImeAction, IntrinsicSize, Item, ItemEvent, LayoutDirection, MeasureMode, PointerAction};
 use crate::ui::additional_property::BaseLine;
-use crate::property::{BoolProperty, ColorProperty, FloatProperty, Gettable, Observable, Observer, SharedProperty, TextProperty};
+use crate::property::{BoolProperty, ColorProperty, FloatProperty, Gettable, SharedProperty, TextProperty};
 use crate::text::{EdgeBehavior, ParagraphWrapper, Style, StyledText};
 
 pub struct TextBlockProperties {
@@ -16,6 +17,8 @@ pub struct TextBlockProperties {
     editable: BoolProperty,
     color: ColorProperty,
     size: FloatProperty,
+    /// Byte range into the text, kept within the text when the text is replaced from outside.
+    selection: SharedProperty<Range<usize>>,
 }
 
 pub struct TextBlock {
@@ -25,17 +28,18 @@ pub struct TextBlock {
 
 impl TextBlock {
     pub fn new(app: SharedApp) -> Self {
+        let selection: SharedProperty<Range<usize>> = SharedProperty::from_value(0..0);
         let properties = Arc::new(Mutex::new(TextBlockProperties {
             text: TextProperty::from_value(StyledText::from_str("")),
             editable: BoolProperty::from_value(true),
             color: Color::BLACK.into(),
             size: 14.0.into(),
+            selection: selection.clone(),
         }));
 
         let paragraph: SharedProperty<Option<ParagraphWrapper>> = SharedProperty::from_value(None);
         let show_cursor: SharedProperty<bool> = SharedProperty::from_value(true);
         let composing: SharedProperty<Option<(Range<usize>, Range<usize>)>> = SharedProperty::from_value(None);
-        let selection: SharedProperty<Range<usize>> = SharedProperty::from_value(0..0);
 
         let item = Item::new(
             app,
@@ -63,7 +67,7 @@ impl TextBlock {
                                 if selection.get().start != selection.get().end {
                                     paragraph.get_rects_for_range(selection.get().clone()).iter().for_each(|text_box| {
                                         let rect = text_box.rect;
-                                        let rect = Rect::from_xywh(rect.left + layout_params.x, rect.top + layout_params.y, rect.width(), rect.height());
+                                        let rect = Rect::from_xywh(rect.left + layout_params.x(), rect.top + layout_params.y(), rect.width(), rect.height());
                                         canvas.draw_rect(&rect, Paint::default().set_anti_alias(true).set_color(0x7f0000ff));
                                     });
                                 }
@@ -75,26 +79,26 @@ impl TextBlock {
                                     LayoutDirection::LeftToRight => {
                                         match horizontal_gravity {
                                             Gravity::Start => {
-                                                layout_params.x + layout_params.padding_start
+                                                layout_params.x() + layout_params.padding_start
                                             }
                                             Gravity::Center => {
-                                                layout_params.x + (layout_params.width - paragraph.layout_width()) / 2.0
+                                                layout_params.x() + (layout_params.width - paragraph.layout_width()) / 2.0
                                             }
                                             Gravity::End => {
-                                                layout_params.x + layout_params.width - layout_params.padding_end - paragraph.layout_width()
+                                                layout_params.x() + layout_params.width - layout_params.padding_end - paragraph.layout_width()
                                             }
                                         }
                                     }
                                     LayoutDirection::RightToLeft => {
                                         match horizontal_gravity {
                                             Gravity::Start => {
-                                                layout_params.x - layout_params.padding_start
+                                                layout_params.x() - layout_params.padding_start
                                             }
                                             Gravity::Center => {
-                                                layout_params.x - (layout_params.width - paragraph.layout_width()) / 2.0
+                                                layout_params.x() - (layout_params.width - paragraph.layout_width()) / 2.0
                                             }
                                             Gravity::End => {
-                                                layout_params.x - layout_params.width + layout_params.padding_end + paragraph.layout_width()
+                                                layout_params.x() - layout_params.width + layout_params.padding_end + paragraph.layout_width()
                                             }
                                         }
                                     }
@@ -102,46 +106,37 @@ impl TextBlock {
 
                                 let paragraph_y = match vertical_gravity {
                                     Gravity::Start => {
-                                        layout_params.y + layout_params.padding_top
+                                        layout_params.y() + layout_params.padding_top
                                     }
                                     Gravity::Center => {
-                                        layout_params.y + (layout_params.height - paragraph.layout_height()) / 2.0
+                                        layout_params.y() + (layout_params.height - paragraph.layout_height()) / 2.0
                                     }
                                     Gravity::End => {
-                                        layout_params.y + layout_params.height - layout_params.padding_bottom - paragraph.layout_height()
+                                        layout_params.y() + layout_params.height - layout_params.padding_bottom - paragraph.layout_height()
                                     }
                                 };
 
                                 // Draw text
-                                //paragraph.draw(canvas, layout_params.x+layout_params.padding_start, layout_params.y+layout_params.padding_top);
+                                //paragraph.draw(canvas, layout_params.x()+layout_params.padding_start, layout_params.y()+layout_params.padding_top);
                                 paragraph.draw(canvas, paragraph_x, paragraph_y);
 
-                                // Draw the underline for composing text
-                                if let Some((composing_range, _)) = composing.get() {
-                                    let color = properties.lock().unwrap().color.get();
-                                    for text_box in paragraph.get_rects_for_range(composing_range.clone()).iter()
-                                    {
-                                        let rect = text_box.rect;
-                                        let rect = Rect::from_xywh(rect.left + layout_params.x, rect.bottom + layout_params.y, rect.width(), 1.0);
-                                        canvas.draw_rect(&rect, Paint::default().set_anti_alias(true).set_color(color));
-                                    };
-                                }
-
                                 if properties.lock().unwrap().editable.get() && selection.get().start == selection.get().end{
                                     if show_cursor.get() {
                                         let (x, y, h) = paragraph.get_cursor_position(selection.get().start);
-                                        let mut x = x + layout_params.x;
-                                        if x < layout_params.x {
-                                            x = layout_params.x;
+                                        let mut x = x + layout_params.x();
+                                        if x < layout_params.x() {
+                                            x = layout_params.x();
                                         }
 
-                                        if x >= layout_params.x + layout_params.width - 2.0 {
-                                            x = layout_params.x + layout_params.width - 2.0;
+                                        if x >= layout_params.x() + layout_params.width - 2.0 {
+                                            x = layout_params.x() + layout_params.width - 2.0;
                                         }
-                                        let y = y + layout_params.y;
+                                        let y = y + layout_params.y();
                                         let rect = Rect::from_xywh(x, y, 2.0, h);
                                         canvas.draw_rect(&rect, Paint::default().set_anti_alias(true).set_color(0xffff0000));
-                                        item.get_app().lock().unwrap().window().set_ime_cursor_area(LogicalPosition::new(x, y + h), LogicalSize::new(0, 0));
+                                        if let Some(window) = item.get_app().lock().unwrap().try_window() {
+                                            window.set_ime_cursor_area(LogicalPosition::new(x, y + h), LogicalSize::new(0, 0));
+                                        }
                                     }
                                 }
                             }
@@ -153,7 +148,7 @@ impl TextBlock {
                     }
                 )
 
-                .set_on_measure(
+                .set_measure_event(
                     {
                         let mut paragraph = paragraph.clone();
                         let properties = properties.clone();
@@ -249,6 +244,44 @@ impl TextBlock {
                     }
                 )
 
+                .set_intrinsic_size_event(
+                    {
+                        let properties = properties.clone();
+                        move |item, intrinsic_size| {
+                            let mut layout_params = item.get_layout_params().clone();
+                            layout_params.init_from_item(item);
+                            let text_align = match item.get_layout_direction().get() {
+                                LayoutDirection::LeftToRight => TextAlign::Left,
+                                LayoutDirection::RightToLeft => TextAlign::Right,
+                            };
+
+                            let properties_guard = properties.lock().unwrap();
+                            let text_color = properties_guard.color.get();
+                            let text_size = properties_guard.size.get();
+                            let mut text_guard = properties_guard.text.lock();
+                            let text_ref = text_guard.as_mut();
+                            text_ref.set_style(Style::TextColor(text_color), 0..text_ref.len(), EdgeBehavior::IncludeAndInclude);
+                            text_ref.set_style(Style::FontSize(text_size), 0..text_ref.len(), EdgeBehavior::IncludeAndInclude);
+
+                            match intrinsic_size {
+                                IntrinsicSize::MinWidth(_) | IntrinsicSize::MaxWidth(_) => {
+                                    let paragraph = ParagraphWrapper::new(text_ref, 0..text_ref.len(), f32::MAX, text_align);
+                                    let content_width = match intrinsic_size {
+                                        IntrinsicSize::MinWidth(_) => paragraph.min_intrinsic_width(),
+                                        _ => paragraph.max_intrinsic_width(),
+                                    };
+                                    // The extra pixel matches the measure event, the text does not wrap at this width.
+                                    (content_width + 1.0 + layout_params.padding_start + layout_params.padding_end).min(layout_params.max_width).max(layout_params.min_width)
+                                }
+                                IntrinsicSize::MinHeight(width) | IntrinsicSize::MaxHeight(width) => {
+                                    let paragraph = ParagraphWrapper::new(text_ref, 0..text_ref.len(), width, text_align);
+                                    paragraph.layout_height().min(layout_params.max_height).max(layout_params.min_height)
+                                }
+                            }
+                        }
+                    }
+                )
+
                 .set_on_pointer_input(
                     {
                         let properties = properties.clone();
@@ -269,8 +302,8 @@ impl TextBlock {
                                             item.get_app().activate_ime();
                                         }
 
-                                        let x = x - layout_params.x - layout_params.padding_start;
-                                        let y = y - layout_params.y - layout_params.padding_top;
+                                        let x = x - layout_params.x() - layout_params.padding_start;
+                                        let y = y - layout_params.y() - layout_params.padding_top;
                                         let index = paragraph.get_closest_glyph_cluster_at(Point::new(x, y));
                                         selection.set_value(index..index);
 
@@ -281,8 +314,8 @@ impl TextBlock {
                                     }
                                     PointerAction::Move { x, y, .. } => {
                                         // if let Some(start) = self.selection_start_when_drag {
-                                        //     let x = x - self.layout_params.x - self.layout_params.padding_start;
-                                        //     let y = y - self.layout_params.y - self.layout_params.padding_top;
+                                        //     let x = x - self.layout_params.x() - self.layout_params.padding_start;
+                                        //     let y = y - self.layout_params.y() - self.layout_params.padding_top;
                                         //     let index = paragraph.get_closest_glyph_cluster_at(Point::new(x, y));
                                         //     if index > start {
                                         //         self.selection_range.start = start;
@@ -359,28 +392,41 @@ impl TextBlock {
                                     selection.set_value(prev_glyph_index..prev_glyph_index);
                                 }
                                 ImeAction::Preedit(pr_text, range) => {
+                                    // The new preedit text replaces the previous one.
+                                    if let Some((composing_range, old_selection_range)) = composing.get() {
+                                        text.as_mut().remove(composing_range.clone());
+                                        selection.set_value(old_selection_range.clone());
+                                        composing.set_value(None);
+                                    }
+
                                     let selection_range = selection.get().clone();
                                     if selection_range.start != selection_range.end {
                                         text.as_mut().remove(selection_range.clone());
                                         selection.set_value(selection_range.start..selection_range.start);
                                     }
 
+                                    if !pr_text.is_empty() {
+                                        let start = selection.get().start;
+                                        let composing_range = start..(start + pr_text.len());
+                                        text.as_mut().insert(start, &pr_text);
+                                        text.as_mut().set_style(Style::Underline, composing_range.clone(), EdgeBehavior::ExcludeAndExclude);
+                                        composing.set_value(Some((composing_range, start..start)));
+
+                                        // Without a cursor position the cursor is hidden, keep it at the end of the preedit text.
+                                        // The position is a byte range from the IME, it may not fall on char boundaries.
+                                        let (cursor_start, cursor_end) = range.unwrap_or((pr_text.len(), pr_text.len()));
+                                        let cursor_start = floor_char_boundary(&pr_text, cursor_start);
+                                        let cursor_end = floor_char_boundary(&pr_text, cursor_end).max(cursor_start);
+                                        selection.set_value((start + cursor_start)..(start + cursor_end));
+                                    }
+                                }
+                                ImeAction::Commit(commit_text) => {
                                     if let Some((composing_range, old_selection_range)) = composing.get() {
                                         text.as_mut().remove(composing_range.clone());
                                         selection.set_value(old_selection_range.clone());
                                         composing.set_value(None);
                                     }
 
-                                    if let Some((start, end)) = range {
-                                        text.as_mut().insert(selection.get().start, &pr_text);
-                                        composing.set_value(Some((selection.get().start..(selection.get().start + pr_text.len()), selection.get().clone())));
-                                        //self.composing = Some((self.selection_range.start..(self.selection_range.start + pr_text.len()), self.selection_range.clone()));
-                                        let new_selection_start = selection.get().start + start;
-                                        let new_selection_end = selection.get().start + end;
-                                        selection.set_value(new_selection_start..new_selection_end);
-                                    }
-                                }
-                                ImeAction::Commit(commit_text) => {
                                     let commit_text_len = commit_text.len();
                                     if selection.get().start != selection.get().end {
                                         text.as_mut().remove(selection.get().clone());
@@ -390,9 +436,15 @@ impl TextBlock {
                                     let new_index = selection.get().start + commit_text_len;
                                     selection.set_value(new_index..new_index);
                                 }
-                                ImeAction::Disabled => {}
+                                ImeAction::Disabled => {
+                                    if let Some((composing_range, old_selection_range)) = composing.get() {
+                                        text.as_mut().remove(composing_range.clone());
+                                        selection.set_value(old_selection_range.clone());
+                                        composing.set_value(None);
+                                    }
+                                }
                             }
-                            item.get_app().request_layout();
+                            item.invalidate_layout();
                             true
                         }
                     }
@@ -417,6 +469,7 @@ impl TextBlock {
                                 let mut text_clone = properties.lock().unwrap().text.clone();
                                 let mut text = text_clone.lock();
                                 if key_event.state == winit::event::ElementState::Pressed {
+                                    item.invalidate_layout();
                                     match key_event.logical_key {
                                         Key::Named(named_key) => {
                                             match named_key {
@@ -491,54 +544,53 @@ impl TextBlock {
             let len = text_guard.len();
             text_guard.set_style(Style::TextColor(app.lock().unwrap().theme().get_color(ThemeColor::OnSurfaceVariant)), 0..len, EdgeBehavior::IncludeAndInclude);
         }
+        let editable = properties.lock().unwrap().editable.clone();
         TextBlock {
-            item,
+            item: item.focusable(true).ime_enabled(&editable).cursor_icon(text_cursor_icon(&editable)),
             properties,
         }
     }
 
     pub fn text(mut self, text: impl Into<TextProperty>) -> Self {
         let text = text.into();
-        let app = self.item.get_app();
-        text.add_observer(
-            Observer::new_without_id(
-                move || {
-                    app.lock().unwrap().request_layout();
-                }
-            )
-        );
+        let layout_node = self.item.get_layout_node();
+        let selection = self.properties.lock().unwrap().selection.clone();
+        let text_clone = text.clone();
+        self.item.subscribe(&text, move || {
+            layout_node.invalidate();
+            // E.g. after an undo, the text may be shorter than the selection.
+            clamp_selection(&selection, text_clone.get().as_str());
+        });
         self.properties.lock().unwrap().text = text;
         self
     }
 
     pub fn color(mut self, color: impl Into<ColorProperty>) -> Self {
         let color = color.into();
-        let app = self.item.get_app();
-        color.add_observer(
-            Observer::new_without_id(
-                move || {
-                    app.lock().unwrap().request_layout();
-                }
-            )
-        );
+        let layout_node = self.item.get_layout_node();
+        self.item.subscribe(&color, move || {
+            layout_node.invalidate();
+        });
         self.properties.lock().unwrap().color = color;
         self
     }
 
     pub fn editable(mut self, editable: impl Into<BoolProperty>) -> Self {
         let editable = editable.into();
-        let app = self.item.get_app();
-        editable.add_observer(
-            Observer::new_without_id(
-                move || {
-                    app.lock().unwrap().request_layout();
-                }
-            )
-        );
-        self.properties.lock().unwrap().editable = editable;
+        let layout_node = self.item.get_layout_node();
+        self.item.subscribe(&editable, move || {
+            layout_node.invalidate();
+        });
+        self.properties.lock().unwrap().editable = editable.clone();
+        self.item = self.item.ime_enabled(&editable).cursor_icon(text_cursor_icon(&editable));
         self
     }
 
+    /// The selected byte range of the text, empty for a cursor.
+    pub fn get_selection(&self) -> SharedProperty<Range<usize>> {
+        self.properties.lock().unwrap().selection.clone()
+    }
+
     pub fn get_app(&self) -> SharedApp {
         self.item.get_app()
     }
@@ -546,4 +598,38 @@ impl TextBlock {
     pub fn unwrap(self) -> Item {
         self.item
     }
-}
\ No newline at end of file
+}
+
+/// The largest char boundary of `text` that is not after `index`.
+fn floor_char_boundary(text: &str, index: usize) -> usize {
+    let mut index = index.min(text.len());
+    while !text.is_char_boundary(index) {
+        index -= 1;
+    }
+    index
+}
+
+fn clamp_selection(selection: &SharedProperty<Range<usize>>, text: &str) {
+    let clamp = |index: usize| floor_char_boundary(text, index);
+    let range = selection.get();
+    let clamped = clamp(range.start)..clamp(range.end);
+    if clamped != range {
+        selection.set_value(clamped);
+    }
+}
+
+/// Show a text beam over the text block while it is editable.
+fn text_cursor_icon(editable: &BoolProperty) -> SharedProperty<Option<CursorIcon>> {
+    let cursor_icon = {
+        let editable = editable.clone();
+        SharedProperty::from_generator(Box::new(move || {
+            if editable.get() {
+                Some(CursorIcon::Text)
+            } else {
+                None
+            }
+        }))
+    };
+    cursor_icon.observe(editable);
+    cursor_icon
+}
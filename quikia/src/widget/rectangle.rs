@@ -5,7 +5,7 @@ use crate::app::{SharedApp, ThemeColor};
 use crate::ui::Item;
 use crate::ui::{ItemEvent, LayoutDirection, MeasureMode};
 use crate::ui::additional_property::{ShadowBlur, ShadowColor, ShadowOffsetX, ShadowOffsetY};
-use crate::property::{BoolProperty, ColorProperty, FloatProperty, Gettable, Observable, Observer};
+use crate::property::{BoolProperty, ColorProperty, FloatProperty, Gettable};
 
 struct RectangleProperties {
     color: ColorProperty,
@@ -59,11 +59,11 @@ impl Rectangle {
 
                         let properties = properties.lock().unwrap();
 
-                        let color = *layout_params.get_color_param("color").unwrap_or(&(properties.color.get()));
-                        let radius_start_top = *layout_params.get_float_param("radius_start_top").unwrap_or(&(properties.radius_start_top.get()));
-                        let radius_end_top = *layout_params.get_float_param("radius_end_top").unwrap_or(&(properties.radius_end_top.get()));
-                        let radius_start_bottom = *layout_params.get_float_param("radius_start_bottom").unwrap_or(&(properties.radius_start_bottom.get()));
-                        let radius_end_bottom = *layout_params.get_float_param("radius_end_bottom").unwrap_or(&(properties.radius_end_bottom.get()));
+                        let color = properties.color.get();
+                        let radius_start_top = properties.radius_start_top.get();
+                        let radius_end_top = properties.radius_end_top.get();
+                        let radius_start_bottom = properties.radius_start_bottom.get();
+                        let radius_end_bottom = properties.radius_end_bottom.get();
 
                         let radius_left_top = match layout_direction {
                             LayoutDirection::LeftToRight => {
@@ -123,18 +123,11 @@ impl Rectangle {
                     }
                 })
 
-                .set_measure_event({
-                    let properties = properties.clone();
-                    move |item, width_measure_mode, height_measure_mode| {
+                .set_measure_event(
+                    |item, width_measure_mode, height_measure_mode| {
                         let mut layout_params = item.get_layout_params().clone();
                         layout_params.init_from_item(item);
 
-                        layout_params.set_color_param("color", properties.lock().unwrap().color.get());
-                        layout_params.set_float_param("radius_start_top", properties.lock().unwrap().radius_start_top.get());
-                        layout_params.set_float_param("radius_end_top", properties.lock().unwrap().radius_end_top.get());
-                        layout_params.set_float_param("radius_start_bottom", properties.lock().unwrap().radius_start_bottom.get());
-                        layout_params.set_float_param("radius_end_bottom", properties.lock().unwrap().radius_end_bottom.get());
-
                         match width_measure_mode {
                             MeasureMode::Specified(width) => {
                                 layout_params.width = width + layout_params.padding_start + layout_params.padding_end;
@@ -196,7 +189,7 @@ impl Rectangle {
 
                         item.set_layout_params(&layout_params);
                     }
-                })
+                )
 
                 .set_layout_event(
                     |item, x, y| {
@@ -220,86 +213,62 @@ impl Rectangle {
         }
     }
 
-    pub fn color(self, color: impl Into<ColorProperty>) -> Self {
+    pub fn color(mut self, color: impl Into<ColorProperty>) -> Self {
         let color = color.into();
         let app = self.item.get_app();
-        color.add_observer(
-            Observer::new_without_id(
-                move || {
-                    app.request_layout();
-                }
-            )
-        );
+        self.item.subscribe(&color, move || {
+            app.request_redraw();
+        });
         self.properties.lock().unwrap().color = color;
         self
     }
 
-    pub fn radius_start_top(self, radius: impl Into<FloatProperty>) -> Self {
+    pub fn radius_start_top(mut self, radius: impl Into<FloatProperty>) -> Self {
         let radius = radius.into();
         let app = self.item.get_app();
-        radius.add_observer(
-            Observer::new_without_id(
-                move || {
-                    app.request_layout();
-                }
-            )
-        );
+        self.item.subscribe(&radius, move || {
+            app.request_redraw();
+        });
         self.properties.lock().unwrap().radius_start_top = radius;
         self
     }
 
-    pub fn radius_end_top(self, radius: impl Into<FloatProperty>) -> Self {
+    pub fn radius_end_top(mut self, radius: impl Into<FloatProperty>) -> Self {
         let radius = radius.into();
         let app = self.item.get_app();
-        radius.add_observer(
-            Observer::new_without_id(
-                move || {
-                    app.request_layout()
-                }
-            )
-        );
+        self.item.subscribe(&radius, move || {
+            app.request_redraw();
+        });
         self.properties.lock().unwrap().radius_end_top = radius;
         self
     }
 
-    pub fn radius_start_bottom(self, radius: impl Into<FloatProperty>) -> Self {
+    pub fn radius_start_bottom(mut self, radius: impl Into<FloatProperty>) -> Self {
         let radius = radius.into();
         let app = self.item.get_app();
-        radius.add_observer(
-            Observer::new_without_id(
-                move || {
-                    app.request_layout();
-                }
-            )
-        );
+        self.item.subscribe(&radius, move || {
+            app.request_redraw();
+        });
         self.properties.lock().unwrap().radius_start_bottom = radius;
         self
     }
 
-    pub fn radius_end_bottom(self, radius: impl Into<FloatProperty>) -> Self {
+    pub fn radius_end_bottom(mut self, radius: impl Into<FloatProperty>) -> Self {
         let radius = radius.into();
         let app = self.item.get_app();
-        radius.add_observer(
-            Observer::new_without_id(
-                move || {
-                    app.request_layout();
-                }
-            )
-        );
+        self.item.subscribe(&radius, move || {
+            app.request_redraw();
+        });
         self.properties.lock().unwrap().radius_end_bottom = radius;
         self
     }
 
-    pub fn radius(self, radius: impl Into<FloatProperty>) -> Self {
+    pub fn radius(mut self, radius: impl Into<FloatProperty>) -> Self {
         let radius = radius.into();
         let app = self.item.get_app();
-        radius.add_observer(
-            Observer::new_without_id(
-                move || {
-                    app.request_layout();
-                }
-            )
-        );
+        self.item.subscribe(&radius, move || {
+            app.request_redraw();
+        });
         let mut properties = self.properties.lock().unwrap();
         properties.radius_start_top = radius.clone();
         properties.radius_end_top = radius.clone();